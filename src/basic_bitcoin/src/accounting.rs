@@ -0,0 +1,131 @@
+// @notice Cycles accounting of the HTTPS outcalls
+// Each outcall costs the IC pricing of the request plus the fee of the provider, which is
+// accrued as `cycles_owed` until the provider owner withdraws it.
+
+use candid::Principal;
+use ic_cdk::api::management_canister::{
+    http_request::CanisterHttpRequestArgument,
+    main::{deposit_cycles, CanisterIdRecord},
+};
+
+use crate::{
+    auth::is_authorized,
+    provider::PROVIDERS,
+    types::{Auth, Provider, ProviderError},
+    HTTP_OUTCALL_REQUEST_BASE_COST, HTTP_OUTCALL_REQUEST_COST_PER_BYTE, HTTP_OUTCALL_REQUEST_PER_NODE_COST,
+    HTTP_OUTCALL_RESPONSE_COST_PER_BYTE, MINIMUM_WITHDRAWAL_CYCLES, NODES_IN_SUBNET,
+};
+
+/// Who pays for an outcall: the canister for the Syron operations, or the caller with attached cycles.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CyclesPayer {
+    Canister,
+    Caller,
+}
+
+/// Callers with the `FreeRpc` permission do not attach cycles to the indexer endpoints.
+pub fn get_rpc_payer(caller: &Principal) -> CyclesPayer {
+    if is_authorized(caller, Auth::FreeRpc) {
+        CyclesPayer::Canister
+    } else {
+        CyclesPayer::Caller
+    }
+}
+
+/// The request size as priced by the IC: url, headers, body and transform.
+pub fn get_request_size(request: &CanisterHttpRequestArgument) -> u64 {
+    let headers: usize = request
+        .headers
+        .iter()
+        .map(|header| header.name.len() + header.value.len())
+        .sum();
+    let body = request.body.as_ref().map_or(0, |body| body.len());
+    let transform = request.transform.as_ref().map_or(0, |transform| {
+        transform.function.0.method.len() + transform.context.len()
+    });
+    (request.url.len() + headers + body + transform) as u64
+}
+
+pub fn get_http_request_cost(request_size_bytes: u64, max_response_bytes: u64) -> u128 {
+    let nodes = NODES_IN_SUBNET as u128;
+    let cost_per_node = HTTP_OUTCALL_REQUEST_BASE_COST
+        + HTTP_OUTCALL_REQUEST_PER_NODE_COST * nodes
+        + HTTP_OUTCALL_REQUEST_COST_PER_BYTE * request_size_bytes as u128
+        + HTTP_OUTCALL_RESPONSE_COST_PER_BYTE * max_response_bytes as u128;
+    cost_per_node * nodes
+}
+
+/// The fee of the provider, which is charged by every node of the subnet.
+pub fn get_provider_cost(provider: &Provider, payload_size_bytes: u64) -> u128 {
+    let cost_per_node = provider.cycles_per_call as u128
+        + provider.cycles_per_message_byte as u128 * payload_size_bytes as u128;
+    cost_per_node * NODES_IN_SUBNET as u128
+}
+
+pub fn check_attached_cycles(available: u128, cost: u128) -> Result<(), ProviderError> {
+    if available < cost {
+        return Err(ProviderError::TooFewCycles {
+            expected: cost,
+            received: available,
+        });
+    }
+    Ok(())
+}
+
+/// Accepts the cost from the cycles attached to the call if the caller pays.
+pub fn collect_cycles(payer: CyclesPayer, cost: u128) -> Result<(), ProviderError> {
+    if payer == CyclesPayer::Caller {
+        check_attached_cycles(ic_cdk::api::call::msg_cycles_available128(), cost)?;
+        ic_cdk::api::call::msg_cycles_accept128(cost);
+    }
+    Ok(())
+}
+
+pub fn accrue_cycles_owed(provider_id: u64, cycles: u128) {
+    if cycles == 0 {
+        return;
+    }
+    PROVIDERS.with(|providers| {
+        let mut providers = providers.borrow_mut();
+        if let Some(mut provider) = providers.get(&provider_id) {
+            provider.cycles_owed = provider.cycles_owed.saturating_add(cycles);
+            providers.insert(provider_id, provider);
+        }
+    });
+}
+
+/// Resets the owed cycles of the provider and returns them; only the owner can withdraw them.
+pub fn take_cycles_owed(caller: &Principal, provider_id: u64) -> Result<u128, ProviderError> {
+    PROVIDERS.with(|providers| {
+        let mut providers = providers.borrow_mut();
+        let mut provider = providers.get(&provider_id).ok_or(ProviderError::ProviderNotFound)?;
+        if provider.owner != *caller {
+            return Err(ProviderError::NoPermission);
+        }
+        let amount = provider.cycles_owed;
+        if amount < MINIMUM_WITHDRAWAL_CYCLES {
+            return Err(ProviderError::TooFewCycles {
+                expected: MINIMUM_WITHDRAWAL_CYCLES,
+                received: amount,
+            });
+        }
+        provider.cycles_owed = 0;
+        providers.insert(provider_id, provider);
+        Ok(amount)
+    })
+}
+
+/// Deposits the owed cycles of the provider into the given canister.
+pub async fn withdraw_cycles_owed(caller: Principal, provider_id: u64, canister_id: Principal) -> Result<u128, ProviderError> {
+    // @dev The owed cycles are reset before the await so that they cannot be withdrawn twice
+    let amount = take_cycles_owed(&caller, provider_id)?;
+    match deposit_cycles(CanisterIdRecord { canister_id }, amount).await {
+        Ok(()) => Ok(amount),
+        Err((code, reason)) => {
+            accrue_cycles_owed(provider_id, amount);
+            Err(ProviderError::WithdrawalFailed {
+                reason: format!("{:?}: {}", code, reason),
+            })
+        }
+    }
+}
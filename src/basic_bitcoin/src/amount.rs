@@ -0,0 +1,53 @@
+// @notice BRC-20 amounts
+// Indexers report amounts as decimal strings (e.g. "15.5"). The Syron ledgers use
+// 8-decimal integer units, so amounts are converted exactly, without floating point.
+
+use crate::types::AmountError;
+
+/// Number of decimals of the Syron ledgers' unit.
+pub const SYRON_DECIMALS: u32 = 8;
+
+/// Parses a BRC-20 decimal amount into 8-decimal integer units, e.g. "15.5" -> 1_550_000_000.
+pub fn parse_brc20_amount(amt: &str) -> Result<u64, AmountError> {
+    if amt.is_empty() {
+        return Err(AmountError::Empty);
+    }
+    if amt.starts_with('-') {
+        return Err(AmountError::Negative);
+    }
+    if amt.contains(['e', 'E']) {
+        return Err(AmountError::Exponent);
+    }
+    if let Some(c) = amt.chars().find(|c| !c.is_ascii_digit() && *c != '.') {
+        return Err(AmountError::InvalidCharacter(c.to_string()));
+    }
+
+    let (integer, fraction) = match amt.split_once('.') {
+        Some((integer, fraction)) => (integer, fraction),
+        None => (amt, ""),
+    };
+    if integer.is_empty() || fraction.contains('.') || (amt.contains('.') && fraction.is_empty()) {
+        return Err(AmountError::Malformed);
+    }
+    if fraction.len() > SYRON_DECIMALS as usize {
+        return Err(AmountError::TooManyDecimals {
+            max: SYRON_DECIMALS,
+            found: fraction.len() as u32,
+        });
+    }
+
+    let scale = 10u64.pow(SYRON_DECIMALS - fraction.len() as u32);
+    let fraction = if fraction.is_empty() { 0 } else { parse_digits(fraction)? };
+    parse_digits(integer)?
+        .checked_mul(10u64.pow(SYRON_DECIMALS))
+        .and_then(|units| units.checked_add(fraction * scale))
+        .ok_or(AmountError::Overflow)
+}
+
+fn parse_digits(digits: &str) -> Result<u64, AmountError> {
+    digits.bytes().try_fold(0u64, |acc, b| {
+        acc.checked_mul(10)
+            .and_then(|acc| acc.checked_add((b - b'0') as u64))
+            .ok_or(AmountError::Overflow)
+    })
+}
@@ -0,0 +1,87 @@
+// @notice Authorization of principals
+// The foundation of this file comes from the EVM RPC canister.
+// Principals hold a set of `Auth` permissions; controllers can always manage the canister.
+
+use candid::Principal;
+
+use crate::{
+    provider::AUTH,
+    types::{Auth, AuthSet, PrincipalStorable},
+};
+
+pub fn is_authorized(principal: &Principal, auth: Auth) -> bool {
+    AUTH.with(|a| {
+        a.borrow()
+            .get(&PrincipalStorable(*principal))
+            .map_or(false, |auth_set| auth_set.is_authorized(auth))
+    })
+}
+
+pub fn do_authorize(principal: Principal, auth: Auth) -> bool {
+    AUTH.with(|a| {
+        let mut auth_map = a.borrow_mut();
+        let principal = PrincipalStorable(principal);
+        let mut auth_set = auth_map.get(&principal).unwrap_or_default();
+        let changed = auth_set.authorize(auth);
+        auth_map.insert(principal, auth_set);
+        changed
+    })
+}
+
+pub fn do_deauthorize(principal: Principal, auth: Auth) -> bool {
+    AUTH.with(|a| {
+        let mut auth_map = a.borrow_mut();
+        let principal = PrincipalStorable(principal);
+        match auth_map.get(&principal) {
+            Some(mut auth_set) => {
+                let changed = auth_set.deauthorize(auth);
+                if auth_set.is_empty() {
+                    auth_map.remove(&principal);
+                } else {
+                    auth_map.insert(principal, auth_set);
+                }
+                changed
+            }
+            None => false,
+        }
+    })
+}
+
+pub fn get_authorized(auth: Auth) -> Vec<Principal> {
+    AUTH.with(|a| {
+        a.borrow()
+            .iter()
+            .filter(|(_, auth_set)| auth_set.is_authorized(auth))
+            .map(|(principal, _)| principal.0)
+            .collect()
+    })
+}
+
+pub fn check_authorized(caller: &Principal, auth: Auth) -> Result<(), String> {
+    if is_authorized(caller, auth) {
+        Ok(())
+    } else {
+        Err(format!("{} is not authorized ({:?})", caller, auth))
+    }
+}
+
+pub fn is_manager(caller: &Principal) -> bool {
+    ic_cdk::api::is_controller(caller) || is_authorized(caller, Auth::Manage)
+}
+
+pub fn require_manage_or_controller() -> Result<(), String> {
+    let caller = ic_cdk::caller();
+    check_manage_or_controller(&caller, ic_cdk::api::is_controller(&caller))
+}
+
+/// Controllers are checked on every call rather than granted `Manage`, so that removed controllers lose access.
+pub fn check_manage_or_controller(caller: &Principal, is_controller: bool) -> Result<(), String> {
+    if is_controller {
+        return Ok(());
+    }
+    check_authorized(caller, Auth::Manage)
+}
+
+pub fn require_register_provider() -> Result<(), String> {
+    check_authorized(&ic_cdk::caller(), Auth::RegisterProvider)
+}
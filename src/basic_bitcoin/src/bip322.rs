@@ -0,0 +1,166 @@
+// @notice BIP-322 generic signed messages
+// Users prove ownership of their SSI address by signing a message with the address itself:
+// the signature is a witness (simple format) or a full transaction (full format) that spends
+// a virtual output locked by the address. Legacy P2PKH addresses use the Bitcoin signed message format.
+// https://github.com/bitcoin/bips/blob/master/bip-0322.mediawiki
+
+use std::str::FromStr;
+
+use bitcoin::{
+    blockdata::{opcodes, script::Builder},
+    consensus::deserialize,
+    hashes::{sha256, Hash, HashEngine},
+    secp256k1::{ecdsa, Message, Secp256k1, XOnlyPublicKey},
+    util::{
+        misc::{signed_msg_hash, MessageSignature},
+        schnorr::SchnorrSig,
+        sighash::{Prevouts, SighashCache},
+    },
+    Address, EcdsaSighashType, OutPoint, PublicKey, Script, Transaction, TxIn, TxOut, Witness,
+};
+
+const BIP322_TAG: &[u8] = b"BIP0322-signed-message";
+
+/// The BIP-340 tagged hash of the message.
+pub fn message_hash(message: &[u8]) -> [u8; 32] {
+    let tag = sha256::Hash::hash(BIP322_TAG);
+    let mut engine = sha256::Hash::engine();
+    engine.input(&tag[..]);
+    engine.input(&tag[..]);
+    engine.input(message);
+    sha256::Hash::from_engine(engine).into_inner()
+}
+
+/// The virtual transaction whose only output is locked by the signer's address.
+pub fn to_spend(script_pubkey: &Script, message: &[u8]) -> Transaction {
+    Transaction {
+        version: 0,
+        lock_time: 0,
+        input: vec![TxIn {
+            previous_output: OutPoint::null(),
+            script_sig: Builder::new()
+                .push_opcode(opcodes::all::OP_PUSHBYTES_0)
+                .push_slice(&message_hash(message))
+                .into_script(),
+            sequence: 0,
+            witness: Witness::new(),
+        }],
+        output: vec![TxOut {
+            value: 0,
+            script_pubkey: script_pubkey.clone(),
+        }],
+    }
+}
+
+/// The virtual transaction that spends `to_spend` with the given witness.
+pub fn to_sign(to_spend: &Transaction, witness: Witness) -> Transaction {
+    Transaction {
+        version: 0,
+        lock_time: 0,
+        input: vec![TxIn {
+            previous_output: OutPoint {
+                txid: to_spend.txid(),
+                vout: 0,
+            },
+            script_sig: Script::new(),
+            sequence: 0,
+            witness,
+        }],
+        output: vec![TxOut {
+            value: 0,
+            script_pubkey: Builder::new().push_opcode(opcodes::all::OP_RETURN).into_script(),
+        }],
+    }
+}
+
+/// Verifies a base64 BIP-322 signature (simple or full format, or a legacy signature for P2PKH addresses).
+pub fn verify(address: &str, message: &str, signature: &str) -> Result<(), String> {
+    let address = Address::from_str(address).map_err(|err| format!("invalid address: {}", err))?;
+    let bytes = base64::decode(signature).map_err(|err| format!("invalid base64: {}", err))?;
+    let script_pubkey = address.script_pubkey();
+
+    if script_pubkey.is_p2pkh() {
+        let signature = MessageSignature::from_slice(&bytes).map_err(|err| format!("invalid signature: {}", err))?;
+        return match signature.is_signed_by_address(&Secp256k1::verification_only(), &address, signed_msg_hash(message)) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err("signature does not match the address".to_string()),
+            Err(err) => Err(format!("invalid signature: {}", err)),
+        };
+    }
+
+    let to_spend = to_spend(&script_pubkey, message.as_bytes());
+    let to_sign = match deserialize::<Witness>(&bytes) {
+        Ok(witness) => to_sign(&to_spend, witness),
+        Err(_) => {
+            let to_sign: Transaction = deserialize(&bytes).map_err(|_| "invalid simple or full signature".to_string())?;
+            check_full_format(&to_sign, &to_spend)?;
+            to_sign
+        }
+    };
+
+    if script_pubkey.is_v0_p2wpkh() {
+        verify_p2wpkh(&to_sign, &script_pubkey)
+    } else if script_pubkey.is_v1_p2tr() {
+        verify_p2tr(&to_sign, &to_spend.output[0])
+    } else {
+        Err("unsupported address type".to_string())
+    }
+}
+
+fn check_full_format(to_sign: &Transaction, to_spend: &Transaction) -> Result<(), String> {
+    let spends_to_spend = to_sign.input.len() == 1
+        && to_sign.input[0].previous_output == OutPoint { txid: to_spend.txid(), vout: 0 };
+    let is_op_return = to_sign.output.len() == 1
+        && to_sign.output[0].value == 0
+        && to_sign.output[0].script_pubkey.is_op_return();
+    if spends_to_spend && is_op_return {
+        Ok(())
+    } else {
+        Err("the transaction is not a BIP-322 to_sign transaction".to_string())
+    }
+}
+
+fn verify_p2wpkh(to_sign: &Transaction, script_pubkey: &Script) -> Result<(), String> {
+    let witness = to_sign.input[0].witness.to_vec();
+    if witness.len() != 2 {
+        return Err("expected a P2WPKH witness (signature, public key)".to_string());
+    }
+    let (signature, hash_type) = match witness[0].split_last() {
+        Some((hash_type, signature)) => (signature, *hash_type),
+        None => return Err("empty signature".to_string()),
+    };
+    let public_key = PublicKey::from_slice(&witness[1]).map_err(|err| format!("invalid public key: {}", err))?;
+    let wpubkey_hash = public_key.wpubkey_hash().ok_or("uncompressed public key")?;
+    if script_pubkey != &Script::new_v0_p2wpkh(&wpubkey_hash) {
+        return Err("public key does not match the address".to_string());
+    }
+
+    let hash_type = EcdsaSighashType::from_standard(hash_type as u32).map_err(|err| format!("{}", err))?;
+    let script_code = Script::new_p2pkh(&public_key.pubkey_hash());
+    let sighash = SighashCache::new(to_sign)
+        .segwit_signature_hash(0, &script_code, 0, hash_type)
+        .map_err(|err| format!("{}", err))?;
+
+    let signature = ecdsa::Signature::from_der(signature).map_err(|err| format!("invalid signature: {}", err))?;
+    let message = Message::from_slice(&sighash[..]).map_err(|err| format!("{}", err))?;
+    Secp256k1::verification_only()
+        .verify_ecdsa(&message, &signature, &public_key.inner)
+        .map_err(|_| "signature does not match the address".to_string())
+}
+
+fn verify_p2tr(to_sign: &Transaction, spent: &TxOut) -> Result<(), String> {
+    let witness = to_sign.input[0].witness.to_vec();
+    if witness.len() != 1 {
+        return Err("expected a taproot key path witness (signature)".to_string());
+    }
+    let signature = SchnorrSig::from_slice(&witness[0]).map_err(|err| format!("invalid signature: {}", err))?;
+    let output_key = XOnlyPublicKey::from_slice(&spent.script_pubkey[2..34]).map_err(|err| format!("{}", err))?;
+
+    let sighash = SighashCache::new(to_sign)
+        .taproot_key_spend_signature_hash(0, &Prevouts::All(&[spent]), signature.hash_ty)
+        .map_err(|err| format!("{}", err))?;
+    let message = Message::from_slice(&sighash[..]).map_err(|err| format!("{}", err))?;
+    Secp256k1::verification_only()
+        .verify_schnorr(&signature.sig, &message, &output_key)
+        .map_err(|_| "signature does not match the address".to_string())
+}
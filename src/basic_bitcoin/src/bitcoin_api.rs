@@ -1,15 +1,16 @@
-use candid::Principal;
+use candid::{CandidType, Deserialize, Principal};
 use ic_cdk::api::call::call_with_payment;
 use ic_cdk::api::management_canister::bitcoin::{
     BitcoinNetwork, GetBalanceRequest, GetCurrentFeePercentilesRequest, GetUtxosRequest,
     GetUtxosResponse, MillisatoshiPerByte, Satoshi, SendTransactionRequest,
 };
-use ic_ckbtc_minter_tyron::updates::update_balance::UpdateBalanceError;
+use crate::types::SyronError;
 
 // The fees for the various bitcoin endpoints.
 const GET_BALANCE_COST_CYCLES: u64 = 100_000_000;
 const GET_UTXOS_COST_CYCLES: u64 = 10_000_000_000;
 const GET_CURRENT_FEE_PERCENTILES_CYCLES: u64 = 100_000_000;
+const GET_BLOCK_HEADERS_COST_CYCLES: u64 = 10_000_000_000;
 const SEND_TRANSACTION_BASE_CYCLES: u64 = 5_000_000_000;
 const SEND_TRANSACTION_PER_BYTE_CYCLES: u64 = 20_000_000;
 
@@ -53,6 +54,70 @@ pub async fn get_utxos(network: BitcoinNetwork, address: String) -> GetUtxosResp
     utxos_res.unwrap().0
 }
 
+/// Like `get_utxos`, but returns the rejection instead of trapping, for callers that can do without the UTXOs.
+pub async fn try_get_utxos(network: BitcoinNetwork, address: String) -> Result<GetUtxosResponse, String> {
+    let utxos_res: Result<(GetUtxosResponse,), _> = call_with_payment(
+        Principal::management_canister(),
+        "bitcoin_get_utxos",
+        (GetUtxosRequest {
+            address,
+            network: network.into(),
+            filter: None,
+        },),
+        GET_UTXOS_COST_CYCLES,
+    )
+    .await;
+
+    match utxos_res {
+        Ok((utxos,)) => Ok(utxos),
+        Err((code, message)) => Err(format!("{:?}: {}", code, message)),
+    }
+}
+
+#[derive(CandidType, Deserialize, Debug, Clone)]
+pub struct GetBlockHeadersRequest {
+    pub start_height: u32,
+    pub end_height: Option<u32>,
+    pub network: BitcoinNetwork,
+}
+
+#[derive(CandidType, Deserialize, Debug, Clone)]
+pub struct GetBlockHeadersResponse {
+    pub tip_height: u32,
+    // The 80-byte headers, starting at `start_height`.
+    pub block_headers: Vec<Vec<u8>>,
+}
+
+/// Returns the block headers from `start_height` to `end_height` (or up to the tip), and the tip height.
+///
+/// Relies on the `bitcoin_get_block_headers` endpoint.
+/// See https://internetcomputer.org/docs/current/references/ic-interface-spec/#ic-bitcoin_get_block_headers
+pub async fn get_block_headers(
+    network: BitcoinNetwork,
+    start_height: u32,
+    end_height: Option<u32>
+) -> Result<GetBlockHeadersResponse, SyronError> {
+    let res: Result<(GetBlockHeadersResponse,), _> = call_with_payment(
+        Principal::management_canister(),
+        "bitcoin_get_block_headers",
+        (GetBlockHeadersRequest {
+            start_height,
+            end_height,
+            network,
+        },),
+        GET_BLOCK_HEADERS_COST_CYCLES,
+    )
+    .await;
+
+    match res {
+        Ok((response,)) => Ok(response),
+        Err((code, message)) => Err(SyronError::CallFailed {
+            method: "bitcoin_get_block_headers".to_string(),
+            reason: format!("{:?}: {}", code, message),
+        }),
+    }
+}
+
 /// Returns the 100 fee percentiles measured in millisatoshi/byte.
 /// Percentiles are computed from the last 10,000 transactions (if available).
 ///
@@ -76,7 +141,7 @@ pub async fn get_current_fee_percentiles(network: BitcoinNetwork) -> Vec<Millisa
 ///
 /// Relies on the `bitcoin_send_transaction` endpoint.
 /// See https://internetcomputer.org/docs/current/references/ic-interface-spec/#ic-bitcoin_send_transaction
-pub async fn send_transaction(network: BitcoinNetwork, transaction: Vec<u8>) -> Result<(), UpdateBalanceError> {
+pub async fn send_transaction(network: BitcoinNetwork, transaction: Vec<u8>) -> Result<(), SyronError> {
     let transaction_fee = SEND_TRANSACTION_BASE_CYCLES
         + (transaction.len() as u64) * SEND_TRANSACTION_PER_BYTE_CYCLES;
 
@@ -93,9 +158,8 @@ pub async fn send_transaction(network: BitcoinNetwork, transaction: Vec<u8>) ->
     
     match res {
         Ok(()) => return Ok(()),
-        Err(_) => return Err(UpdateBalanceError::GenericError{
-            error_code: 499,
-            error_message: "bitcoin_send_transaction failed".to_string()
+        Err((code, message)) => return Err(SyronError::SendTransactionFailed {
+            reason: format!("{:?}: {}", code, message)
         }),
     }
 }
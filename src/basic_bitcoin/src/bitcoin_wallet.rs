@@ -6,7 +6,15 @@
 //! * Caching spent UTXOs so that they are not reused in future transactions.
 //! * Option to set the fee.
 
-use crate::{bitcoin_api, ecdsa_api};
+use crate::{
+    bitcoin_api,
+    classifier::get_classified_utxos,
+    coin_selection::{select_coins, CoinSelector, SelectionParams, DEFAULT_COIN_SELECTOR},
+    ecdsa_api,
+    fee::solve_fee,
+    types::SyronError,
+    vsize::{compute_fee, estimate_vsize, InputType},
+};
 use bitcoin::util::psbt::serialize::Serialize;
 use bitcoin::{
     blockdata::{script::Builder, witness::Witness},
@@ -23,7 +31,6 @@ use ic_ckbtc_minter_tyron::management::{get_utxos, Reason};
 use ic_ckbtc_minter_tyron::state::read_state;
 use ic_ckbtc_minter_tyron::updates::get_btc_address::init_ecdsa_public_key;
 use ic_ckbtc_minter_tyron::updates::get_withdrawal_account::compute_subaccount;
-use ic_ckbtc_minter_tyron::updates::update_balance::UpdateBalanceError;
 use ic_ckbtc_minter_tyron::{
     state,
     tx::{self, SignedTransaction, UnsignedInput, UnsignedTransaction, SignedInput},
@@ -39,10 +46,15 @@ use serde_bytes::ByteBuf;
 use ic_management_canister_types::DerivationPath;
 use ic_canister_log::log;
 use std::fmt;
-use regex;
 
 const SIG_HASH_TYPE: EcdsaSighashType = EcdsaSighashType::All;
 
+// 10 satoshis per vbyte, used on regtest networks without fee percentiles.
+const DEFAULT_FEE_PER_VBYTE: MillisatoshiPerByte = 10_000;
+
+// Change of at most this value is not worth an output and goes to the fee. @review (mainnet)
+const DUST_THRESHOLD: Satoshi = 546;
+
 struct DisplayOutpoint<'a>(pub &'a Outpoint);
 
 impl fmt::Display for DisplayOutpoint<'_> {
@@ -70,7 +82,7 @@ pub async fn syron_p2wpkh(
     dst_address: &str,
     tx_id: String,
     fee_per_byte: u64    
-) -> Result<String, UpdateBalanceError> {
+) -> Result<String, SyronError> {
     // @dev Fetch sender's public key, address, and UTXOs.
     let own_public_key =
         ecdsa_api::ecdsa_public_key(key_name.clone(), origin_derivation_path.clone()).await;
@@ -85,86 +97,30 @@ pub async fn syron_p2wpkh(
     // };
 
     print("Fetching UTXOs...");
-    // Note that pagination may have to be used to get all UTXOs for the given address.
-    // For the sake of simplicity, it is assumed here that the `utxo` field in the response
-    // contains all UTXOs.
-    let own_utxos: Vec<Utxo> =
-        bitcoin_api::get_utxos(btc_network, origin_address.clone())
-        .await
-        .utxos;
-
-    // let own_utxos: Vec<ic_btc_interface::Utxo> =
-    // get_utxos(network, &own_address, 1, CallSource::Client) // @review (mainnet) min confirmations
-    // .await
-    // .unwrap().utxos;
-
-    // for utxo in &own_utxos {
-    //     log!(
-    //         P1,
-    //         "Minter UTXO: {}",
-    //         DisplayOutpoint(&utxo.outpoint)
-    //     );
-    // }
-
-    let mut option_utxo: Option<Utxo> = None;
-    let mut fee_utxos = own_utxos.clone();
-
-    // @dev Remove the UTXO that has the minter's SYRON balance inscription & every UTXO with a value less than 600 satoshis.
-    for index in (0..fee_utxos.len()).rev() {
-        let utxo = &fee_utxos[index];
-
-        let txid_bytes = utxo.outpoint.txid.iter().rev().map(|n| *n as u8).collect::<Vec<u8>>();
-        let txid_hex = hex::encode(txid_bytes);
-
-        // @review (upgrade)
-        if txid_hex == "1cd2d3ef9657b6c2894d45e8769d76d63a7c6a66247aacf8c4b6d6d8fb614970".to_string() || utxo.value < 600 {
-            fee_utxos.remove(index);
-        }
-    }
+    // @dev Only cardinal UTXOs pay the fee, so that the minter's inscriptions (e.g. its SYRON balance) are never spent
+    let own_utxos = get_classified_utxos(btc_network, &origin_address).await?;
 
     // @dev Select the UTXO that has the required transfer inscribed.
-    for (index, utxo) in own_utxos.iter().enumerate() {
-        // let outpoint = Outpoint {
-        //     txid: utxo.outpoint.txid.as_ref().to_vec(),
-        //     vout: utxo.outpoint.vout
-        // };
-
-        log!(
-            P1,
-            "UTXO: {}",
-            DisplayOutpoint(&utxo.outpoint)
-        );
-
-        let txid_bytes = utxo.outpoint.txid.iter().rev().map(|n| *n as u8).collect::<Vec<u8>>();
-        let txid_hex = hex::encode(txid_bytes);
-
-        if txid_hex == tx_id {
-            // let ssi_utxo = Utxo {
-            //     outpoint,
-            //     value: utxo.value,
-            //     height: utxo.height
-            // };
-            option_utxo = Some(utxo.clone());
-            // fee_utxos.remove(index); @dev Removed already in previous iteration @protocol Inscription UTXO value must be less than 600 satoshis
-            break
-        }
-    }
-
-    let select_utxo = option_utxo.expect("No matching UTXO found in the SYRON minter.");
+    let select_utxo = own_utxos.inscription_utxo(&tx_id)?;
+    log!(
+        P1,
+        "UTXO: {}",
+        DisplayOutpoint(&select_utxo.outpoint)
+    );
+    let fee_utxos = own_utxos.cardinal();
 
     let syron_btc_address = BitcoinAddress::parse(&origin_address, network).unwrap();
     let dst_address = BitcoinAddress::parse(&dst_address, network).unwrap();
     
     // @dev Builds the transaction that sends the selected UTXO (transfer inscription) to the destination address.
     let transaction = build_unsigned_mint(
-        &own_public_key,
         syron_btc_address,
         select_utxo,
         &fee_utxos,
         dst_address,
         fee_per_byte,
-    )
-    .await?;
+        &DEFAULT_COIN_SELECTOR,
+    )?;
 
     // Sign the transaction.
     let signed_transaction: SignedTransaction = sign_transaction_p2wpkh(
@@ -173,7 +129,7 @@ pub async fn syron_p2wpkh(
         key_name,
         origin_derivation_path,
     )
-    .await.map_err(|err| UpdateBalanceError::CallError{method: err.method().to_string(), reason: Reason::to_string(err.reason())})?;
+    .await.map_err(|err| SyronError::CallFailed{method: err.method().to_string(), reason: Reason::to_string(err.reason())})?;
 
     print("Sending transaction...");
 
@@ -200,21 +156,9 @@ pub async fn burn_p2wpkh(
     dst_address: &str,
     syron_address: &str,
     txid: String
-) -> Result<[u8;32], UpdateBalanceError> {
-    // Get fee percentiles from previous transactions to estimate our own fee.
-    let fee_percentiles = bitcoin_api::get_current_fee_percentiles(btc_network)
-    .await;
-
-    // @dev Gas in satoshis per byte @review (signet)
-    let fee_per_byte = if fee_percentiles.is_empty() {
-        // There are no fee percentiles. This case can only happen on a regtest
-        // network where there are no non-coinbase transactions. In this case,
-        // we use a default of 5000 millisatoshis/byte (i.e. 5 satoshi/byte)
-        10000
-    } else {
-        // Choose the 50th percentile for sending fees.
-        fee_percentiles[50]
-    };
+) -> Result<[u8;32], SyronError> {
+    // @dev Gas in millisatoshis per vbyte @review (signet)
+    let fee_per_byte = get_fee_per_vbyte(btc_network, DEFAULT_FEE_PER_VBYTE).await;
 
     // let (ecdsa_public_key) =
     // read_state(|s| (s.ecdsa_public_key));
@@ -241,60 +185,28 @@ pub async fn burn_p2wpkh(
     // };
 
     print("Fetching UTXOs...");
-    // Note that pagination may have to be used to get all UTXOs for the given address.
-    // For the sake of simplicity, it is assumed here that the `utxo` field in the response
-    // contains all UTXOs.
-    let mut utxos: Vec<Utxo> =
-        bitcoin_api::get_utxos(btc_network, sdb.clone())
-        .await
-        .utxos;
+    let sdb_utxos = get_classified_utxos(btc_network, &sdb).await?;
 
     // @dev The SUSD inscribe-transfer UTXO
-    let mut select_utxo: Option<Utxo> = None;
-
-    // @dev Remove the UTXOs with a value less than 600 satoshis, which are probably inscriptions.
-    for index in (0..utxos.len()).rev() {
-        let utxo = &utxos[index];
-
-        let txid_bytes = utxo.outpoint.txid.iter().rev().map(|n| *n as u8).collect::<Vec<u8>>();
-        let txid_hex = hex::encode(txid_bytes);
-        if txid_hex == txid {
-            select_utxo = Some(utxo.clone());
-            utxos.remove(index);
-        } else if utxo.value < 600 {
-            utxos.remove(index);
-        }
-    }
-
-    let select_utxo = select_utxo.expect("No matching UTXO found!");
-
-    // let utxos: Vec<ic_btc_interface::Utxo> =
-    // get_utxos(network, &own_address, 1, CallSource::Client) // @review (mainnet) min confirmations
-    // .await
-    // .unwrap().utxos;
+    let select_utxo = sdb_utxos.inscription_utxo(&txid)?;
 
-    // for utxo in &own_utxos {
-    //     log!(
-    //         P1,
-    //         "Minter UTXO: {}",
-    //         DisplayOutpoint(&utxo.outpoint)
-    //     );
-    // }
+    // @dev Only cardinal UTXOs are redeemed; other inscriptions and runes stay in the SDB
+    let utxos = sdb_utxos.cardinal();
 
     let sdb_address = BitcoinAddress::parse(&sdb, network).unwrap();
     let dst_address = BitcoinAddress::parse(dst_address, network).unwrap();
     let syron_address = BitcoinAddress::parse(syron_address, network).unwrap();
     
     let transaction = build_unsigned_transaction(
-        &sdb_public_key,
         sdb_address,
         &utxos,
         dst_address,
         amount,
         fee_per_byte,
         syron_address,
-        select_utxo
-    ).await?;
+        select_utxo,
+        &DEFAULT_COIN_SELECTOR
+    )?;
 
     // Sign the transaction.
     let derivation_path: Vec<Vec<u8>> = get_ssi_derivation_path(&account, ssi).into_iter().map(|index| index.0).collect();
@@ -305,7 +217,7 @@ pub async fn burn_p2wpkh(
         key_name,
         derivation_path,
     )
-    .await.unwrap();
+    .await.map_err(|err| SyronError::CallFailed{method: err.method().to_string(), reason: Reason::to_string(err.reason())})?;
 
     print("Sending transaction...");
     let signed_transaction_bytes = signed_transaction.serialize();
@@ -316,79 +228,45 @@ pub async fn burn_p2wpkh(
 
 pub async fn gas_p2wpkh(
     amount: u64,
-    ssi: &str,
     btc_network: BitcoinNetwork,
     sdb: String,
     dst_address: &str,
     syron_address: &str
-) -> u64 {
-    // Get fee percentiles from previous transactions to estimate our own fee.
-    let fee_percentiles = bitcoin_api::get_current_fee_percentiles(btc_network).await;
-
-    // @dev Gas in satoshis per byte @review (signet)
-    let fee_per_byte = if fee_percentiles.is_empty() {
-        // There are no fee percentiles. This case can only happen on a regtest
-        // network where there are no non-coinbase transactions. In this case,
-        // we use a default of 5000 millisatoshis/byte (i.e. 5 satoshi/byte)
-        10000
-    } else {
-        // Choose the 50th percentile for sending fees.
-        fee_percentiles[50]
-    };
+) -> Result<u64, SyronError> {
+    // @dev Gas in millisatoshis per vbyte @review (signet)
+    let fee_per_byte = get_fee_per_vbyte(btc_network, DEFAULT_FEE_PER_VBYTE).await;
 
-    let ecdsa_public_key = init_ecdsa_public_key().await;
-
-    let sdb_subaccount = compute_subaccount(1, &ssi);
-    
-    let account = Account {
-        owner: ic_cdk::id(),
-        subaccount: Some(sdb_subaccount)
-    };
-
-    // @dev Fetch SDB's public key and UTXOs.
-    let sdb_public_key = derive_ssi_public_key(&ecdsa_public_key, &account, &ssi).public_key;
-    
     let network =
         state::read_state(|s| (s.btc_network));
 
     print("Fetching UTXOs...");
-    // Note that pagination may have to be used to get all UTXOs for the given address.
-    // For the sake of simplicity, it is assumed here that the `utxo` field in the response
-    // contains all UTXOs.
-    let mut utxos: Vec<Utxo> =
-        bitcoin_api::get_utxos(btc_network, sdb.clone())
-        .await
-        .utxos;
-
-    // @dev The SUSD inscribe-transfer UTXO (dummy)
-    let mut select_utxo: Option<Utxo> = None;
-
-    // @dev Remove the UTXOs with a value less than 600 satoshis, which are probably inscriptions BUT select one of them
-    for index in (0..utxos.len()).rev() {
-        let utxo = &utxos[index];
-        
-        if utxo.value < 600 {
-            select_utxo = Some(utxo.clone());
-            utxos.remove(index);
-        }
-    }
-
-    let select_utxo = select_utxo.expect("No matching UTXO found!");
+    let utxos = get_classified_utxos(btc_network, &sdb).await?.cardinal();
+
+    // @dev The fee does not depend on which inscription is returned to the minter, so a placeholder of the
+    // SUSD inscribe-transfer UTXO stands in for it
+    let select_utxo = Utxo {
+        outpoint: Outpoint {
+            txid: vec![0; 32],
+            vout: 0,
+        },
+        value: DUST_THRESHOLD,
+        height: 0,
+    };
 
     let sdb_address = BitcoinAddress::parse(&sdb, network).unwrap();
     let dst_address = BitcoinAddress::parse(&dst_address, network).unwrap();
     let syron_address = BitcoinAddress::parse(syron_address, network).unwrap();
     
     build_transaction_gas(
-        &sdb_public_key,
         sdb_address,
         &utxos,
         dst_address,
         amount,
         fee_per_byte,
         syron_address,
-        select_utxo
-    ).await
+        select_utxo,
+        &DEFAULT_COIN_SELECTOR
+    )
 }
 
 pub async fn liquidate_p2wpkh(
@@ -399,20 +277,9 @@ pub async fn liquidate_p2wpkh(
     sdb: String,
     dst_address: &str,
 
-) -> [u8;32] {
-    // Get fee percentiles from previous transactions to estimate our own fee.
-    let fee_percentiles = bitcoin_api::get_current_fee_percentiles(btc_network).await;
-
-    // @dev Gas in satoshis per byte @review (signet)
-    let fee_per_byte = if fee_percentiles.is_empty() {
-        // There are no fee percentiles. This case can only happen on a regtest
-        // network where there are no non-coinbase transactions. In this case,
-        // we use a default of 5000 millisatoshis/byte (i.e. 5 satoshi/byte)
-        10000
-    } else {
-        // Choose the 50th percentile for sending fees.
-        fee_percentiles[50]
-    };
+) -> Result<[u8;32], SyronError> {
+    // @dev Gas in millisatoshis per vbyte @review (signet)
+    let fee_per_byte = get_fee_per_vbyte(btc_network, DEFAULT_FEE_PER_VBYTE).await;
 
     let ecdsa_public_key = init_ecdsa_public_key().await;
 
@@ -430,34 +297,20 @@ pub async fn liquidate_p2wpkh(
         state::read_state(|s| (s.btc_network));
 
     print("Fetching UTXOs...");
-    // Note that pagination may have to be used to get all UTXOs for the given address.
-    // For the sake of simplicity, it is assumed here that the `utxo` field in the response
-    // contains all UTXOs.
-    let mut utxos: Vec<Utxo> =
-        bitcoin_api::get_utxos(btc_network, sdb.clone())
-        .await
-        .utxos;
-
-    // @dev Remove the UTXOs with a value less than 600 satoshis, which are probably inscriptions.
-    for index in (0..utxos.len()).rev() {
-        let utxo = &utxos[index];
-
-        if utxo.value < 600 {
-            utxos.remove(index);
-        }
-    }
+    // @dev Only cardinal UTXOs are liquidated; inscriptions and runes stay in the SDB
+    let utxos = get_classified_utxos(btc_network, &sdb).await?.cardinal();
 
     let sdb_address = BitcoinAddress::parse(&sdb, network).unwrap();
     let dst_address = BitcoinAddress::parse(dst_address, network).unwrap();
 
     let transaction = build_unsigned_liquidation(
-        &sdb_public_key,
         sdb_address,
         &utxos,
         dst_address,
         amount,
-        fee_per_byte
-    ).await;
+        fee_per_byte,
+        &DEFAULT_COIN_SELECTOR
+    )?;
 
     // Sign the transaction.
     let derivation_path: Vec<Vec<u8>> = get_ssi_derivation_path(&account, ssi).into_iter().map(|index| index.0).collect();
@@ -468,154 +321,159 @@ pub async fn liquidate_p2wpkh(
         key_name,
         derivation_path,
     )
-    .await.unwrap();
+    .await.map_err(|err| SyronError::CallFailed{method: err.method().to_string(), reason: Reason::to_string(err.reason())})?;
 
     print("Sending transaction...");
     let signed_transaction_bytes = signed_transaction.serialize();
-    bitcoin_api::send_transaction(btc_network, signed_transaction_bytes).await;
+    bitcoin_api::send_transaction(btc_network, signed_transaction_bytes).await?;
     print("Done");
 
-    signed_transaction.wtxid()
+    Ok(signed_transaction.wtxid())
 }
 
-async fn build_unsigned_transaction(
-    public_key: &[u8],
+fn build_unsigned_transaction(
     address: BitcoinAddress,
     utxos: &[Utxo],
     dst_address: BitcoinAddress,
     amount: Satoshi,
     fee_per_byte: MillisatoshiPerByte,
     syron_address: BitcoinAddress,
-    select_utxo: Utxo
-) -> Result<UnsignedTransaction, UpdateBalanceError> {
+    select_utxo: Utxo,
+    selector: &dyn CoinSelector
+) -> Result<UnsignedTransaction, SyronError> {
     // We have a chicken-and-egg problem where we need to know the length
     // of the transaction in order to compute its proper fee, but we need
     // to know the proper fee in order to figure out the inputs needed for
     // the transaction.
     //
-    // We solve this problem iteratively. We start with a fee of zero, build
-    // and sign a transaction, see what its size is, and then update the fee,
-    // rebuild the transaction, until the fee is set to the correct amount.
+    // The selector chooses the inputs, accounting for the weight of each
+    // of them and of the change output. Then `solve_fee` settles the fee of
+    // the transaction with these inputs, estimating its size once signed;
+    // only the final transaction is signed.
     print("Building transaction...");
-    let mut total_fee = 0;
-    loop {
-        let transaction =
-            build_unsigned_tx_with_fee(utxos, address.clone(), dst_address.clone(), amount, total_fee, syron_address.clone(), select_utxo.clone())
-                .expect("Error building transaction");
-
-        // Sign the transaction. In this case, we only care about the size
-        // of the signed transaction, so we use a mock signer here for efficiency.
-        let signed_transaction = sign_transaction_p2wpkh(
-            public_key,
-            transaction.clone(),
-            String::from(""), // mock key name
-            vec![],           // mock derivation path
-        )
-        .await.unwrap();
-
-        let signed_tx_bytes_len = signed_transaction.serialize().len() as u64;
-
-        if (signed_tx_bytes_len * fee_per_byte) / 1000 == total_fee {
-            print(&format!("Transaction built with fee {}.", total_fee));
-            return Ok(transaction);
-        } else {
-            total_fee = (signed_tx_bytes_len * fee_per_byte) / 1000;
-        }
-    }
+    let params = redemption_params(&address, &dst_address, amount, fee_per_byte, &syron_address);
+    let utxos_to_spend = select_redemption_utxos(selector, utxos, &params);
+    let (transaction, total_fee) = solve_fee(
+        0,
+        |fee| build_unsigned_tx_with_fee(&utxos_to_spend, address.clone(), dst_address.clone(), amount, fee, syron_address.clone(), select_utxo.clone()),
+        |transaction| estimate_fee(transaction, fee_per_byte),
+    )?;
+    print(&format!("Transaction built with fee {}.", total_fee));
+    Ok(transaction)
 }
 
-async fn build_transaction_gas(
-    public_key: &[u8],
+/// Returns the satoshis to deposit into the SDB for it to pay the fee of the redemption, if any.
+fn build_transaction_gas(
     address: BitcoinAddress,
     utxos: &[Utxo],
     dst_address: BitcoinAddress,
     amount: Satoshi,
     fee_per_byte: MillisatoshiPerByte,
     syron_address: BitcoinAddress,
-    select_utxo: Utxo
-) -> u64 {
-    let mut total_fee = 0;
-    loop {
-        match build_unsigned_tx_with_fee(utxos, address.clone(), dst_address.clone(), amount, total_fee, syron_address.clone(), select_utxo.clone()) {
-            Ok(transaction) => {
-                // Sign the transaction. In this case, we only care about the size
-                // of the signed transaction, so we use a mock signer here for efficiency.
-                let signed_transaction = sign_transaction_p2wpkh(
-                    public_key,
-                    transaction.clone(),
-                    String::from(""), // mock key name
-                    vec![],           // mock derivation path
-                )
-                .await.unwrap();
-
-                let signed_tx_bytes_len = signed_transaction.serialize().len() as u64;
-
-                if (signed_tx_bytes_len * fee_per_byte) / 1000 == total_fee {
-                    return 0; // @dev no extra gas required
-                } else {
-                    total_fee = (signed_tx_bytes_len * fee_per_byte) / 1000;
-                }
-            },
-            Err (error) => {
-                // Extract the required additional balance from the error message
-                if let Some(captures) = regex::Regex::new(r"Please deposit at least (\d+) sats into your SDB.")
-                .unwrap()
-                .captures(&error)
-                {
-                    if let Some(matched) = captures.get(1) {
-                        let gas = matched.as_str().parse().unwrap();
-                        return gas;
-                    }
-                }
-            }
-        }
+    select_utxo: Utxo,
+    selector: &dyn CoinSelector
+) -> Result<u64, SyronError> {
+    let params = redemption_params(&address, &dst_address, amount, fee_per_byte, &syron_address);
+    let utxos_to_spend = select_redemption_utxos(selector, utxos, &params);
+    let result = solve_fee(
+        0,
+        |fee| build_unsigned_tx_with_fee(&utxos_to_spend, address.clone(), dst_address.clone(), amount, fee, syron_address.clone(), select_utxo.clone()),
+        |transaction| estimate_fee(transaction, fee_per_byte),
+    );
+    match result {
+        Ok(_) => Ok(0), // @dev no extra gas required
+        Err(SyronError::InsufficientFunds { available, required }) => Ok(required - available),
+        Err(err) => Err(err),
     }
 }
 
-async fn build_unsigned_liquidation(
-    public_key: &[u8],
+fn build_unsigned_liquidation(
     address: BitcoinAddress,
     utxos: &[Utxo],
     dst_address: BitcoinAddress,
     amount: Satoshi,
-    fee_per_byte: MillisatoshiPerByte
-) -> UnsignedTransaction {
+    fee_per_byte: MillisatoshiPerByte,
+    selector: &dyn CoinSelector
+) -> Result<UnsignedTransaction, SyronError> {
     // We have a chicken-and-egg problem where we need to know the length
     // of the transaction in order to compute its proper fee, but we need
     // to know the proper fee in order to figure out the inputs needed for
     // the transaction.
     //
-    // We solve this problem iteratively. We start with a fee of zero, build
-    // and sign a transaction, see what its size is, and then update the fee,
-    // rebuild the transaction, until the fee is set to the correct amount.
+    // The selector chooses the inputs, accounting for the weight of each
+    // of them and of the change output. Then `solve_fee` settles the fee of
+    // the transaction with these inputs, from the fee of the selection,
+    // estimating its size once signed; only the final transaction is signed.
     print("Building transaction...");
-    let mut total_fee = 0;
-    loop {
-        let transaction =
-            build_unsigned_liquidation_with_fee(utxos, address.clone(), dst_address.clone(), amount, total_fee)
-                .expect("Error building transaction");
-
-        // Sign the transaction. In this case, we only care about the size
-        // of the signed transaction, so we use a mock signer here for efficiency.
-        let signed_transaction = sign_transaction_p2wpkh(
-            public_key,
-            transaction.clone(),
-            String::from(""), // mock key name
-            vec![],           // mock derivation path
-        )
-        .await.unwrap();
-
-        let signed_tx_bytes_len = signed_transaction.serialize().len() as u64;
-
-        if (signed_tx_bytes_len * fee_per_byte) / 1000 == total_fee {
-            print(&format!("Transaction built with fee {}.", total_fee));
-            return transaction;
-        } else {
-            total_fee = (signed_tx_bytes_len * fee_per_byte) / 1000;
-        }
+    let params = SelectionParams {
+        target: amount,
+        fee_per_vbyte: fee_per_byte,
+        fixed_inputs: vec![],
+        fixed_outputs: vec![dst_address.clone()],
+        input_type: InputType::P2wpkh,
+        change_address: address.clone(),
+        dust_threshold: DUST_THRESHOLD,
+    };
+    let selection = select_coins(selector, utxos, &params)?;
+    let (transaction, total_fee) = solve_fee(
+        selection.fee,
+        |fee| build_unsigned_liquidation_with_fee(&selection.utxos, address.clone(), dst_address.clone(), amount, fee),
+        |transaction| estimate_fee(transaction, fee_per_byte),
+    )?;
+    print(&format!("Transaction built with fee {}.", total_fee));
+    Ok(transaction)
+}
+
+/// The redemption sends the inscription UTXO back to the minter, and the selected UTXOs to the destination.
+fn redemption_params(
+    address: &BitcoinAddress,
+    dst_address: &BitcoinAddress,
+    amount: Satoshi,
+    fee_per_byte: MillisatoshiPerByte,
+    syron_address: &BitcoinAddress
+) -> SelectionParams {
+    SelectionParams {
+        target: amount,
+        fee_per_vbyte: fee_per_byte,
+        fixed_inputs: vec![InputType::P2wpkh],
+        fixed_outputs: vec![syron_address.clone(), dst_address.clone()],
+        input_type: InputType::P2wpkh,
+        change_address: address.clone(),
+        dust_threshold: DUST_THRESHOLD,
     }
 }
 
+/// The UTXOs that fund the redemption. The fee is deducted from the redeemed amount, so every UTXO is spent
+/// if they cannot cover both.
+fn select_redemption_utxos(selector: &dyn CoinSelector, utxos: &[Utxo], params: &SelectionParams) -> Vec<Utxo> {
+    selector
+        .select(utxos, params)
+        .map(|selection| selection.utxos)
+        .unwrap_or_else(|| utxos.to_vec())
+}
+
+/// Returns the median fee rate of recent transactions, in millisatoshis per vbyte.
+/// There are no fee percentiles only on a regtest network without non-coinbase transactions; `default` is used then.
+pub async fn get_fee_per_vbyte(network: BitcoinNetwork, default: MillisatoshiPerByte) -> MillisatoshiPerByte {
+    // Get fee percentiles from previous transactions to estimate our own fee.
+    let fee_percentiles = bitcoin_api::get_current_fee_percentiles(network).await;
+
+    // Choose the 50th percentile for sending fees.
+    fee_percentiles.get(50).copied().unwrap_or(default)
+}
+
+/// The fee of the transaction once signed, at `fee_per_vbyte` millisatoshis per vbyte.
+fn estimate_fee(transaction: &UnsignedTransaction, fee_per_vbyte: MillisatoshiPerByte) -> Satoshi {
+    compute_fee(estimate_signed_vsize(transaction), fee_per_vbyte)
+}
+
+/// The virtual size of the transaction once signed by `sign_transaction_p2wpkh`, which spends every input as P2WPKH.
+fn estimate_signed_vsize(transaction: &UnsignedTransaction) -> u64 {
+    let inputs = vec![InputType::P2wpkh; transaction.inputs.len()];
+    let outputs: Vec<BitcoinAddress> = transaction.outputs.iter().map(|output| output.address.clone()).collect();
+    estimate_vsize(&inputs, &outputs)
+}
+
 fn vec_to_txid(vec: Vec<u8>) -> ic_ckbtc_minter_tyron::tx::Txid {
     let bytes: [u8; 32] = std::convert::TryInto::try_into(vec).expect("Can't convert to [u8; 32]");
     bytes.into()
@@ -629,31 +487,17 @@ fn build_unsigned_tx_with_fee(
     fee: u64,
     syron_address: BitcoinAddress,
     select_utxo: Utxo
-) -> Result<UnsignedTransaction, String> {
-    // Assume that any amount below this threshold is dust.
-    //@review (mainnet)
-    const DUST_THRESHOLD: u64 = 0;
-
-    // Select which UTXOs to spend. We naively spend the oldest available UTXOs,
-    // even if they were previously spent in a transaction. This isn't a
-    // problem as long as at most one transaction is created per block and
-    // we're using min_confirmations of 1.
-    let mut utxos_to_spend = vec![];
-    let mut utxos_balance = 0;
-    for utxo in utxos.iter().rev() {
-        utxos_balance += utxo.value;
-        utxos_to_spend.push(utxo);
-        if utxos_balance >= amount + fee {
-            // We have enough inputs to cover the amount we want to spend.
-            break;
-        }
-    }
+) -> Result<UnsignedTransaction, SyronError> {
+    // @dev The UTXOs to spend were chosen by the coin selector
+    let utxos_to_spend = utxos;
+    let utxos_balance: u64 = utxos_to_spend.iter().map(|utxo| utxo.value).sum();
 
     if utxos_balance < fee {
-        return Err(format!(
-            "Insufficient balance ({} sats) - Trying to transfer {} sats with a fee of {} sats. Please deposit at least {} sats into your SDB.", // @review suggested deposit amount 
-            utxos_balance, amount, fee, fee + amount - utxos_balance, // address @review (format) since now it prints P2wpkhV0([8, 102, 59, 71, 220, 132, 106, 200, 211, 158, 166, 47, 226, 90, 232, 191, 111, 237, 157, 197])
-        ));
+        // @dev The SDB must hold the amount and the fee, so the missing gas is `required - available` @review suggested deposit amount
+        return Err(SyronError::InsufficientFunds {
+            available: utxos_balance,
+            required: fee + amount
+        });
     } else {
         amount = utxos_balance - fee;
     }
@@ -716,31 +560,16 @@ fn build_unsigned_liquidation_with_fee(
     dst_address: BitcoinAddress,
     amount: u64,
     fee: u64
-) -> Result<UnsignedTransaction, String> {
-    // Assume that any amount below this threshold is dust.
-    //@review (mainnet)
-    const DUST_THRESHOLD: u64 = 0;
-
-    // Select which UTXOs to spend. We naively spend the oldest available UTXOs,
-    // even if they were previously spent in a transaction. This isn't a
-    // problem as long as at most one transaction is created per block and
-    // we're using min_confirmations of 1.
-    let mut utxos_to_spend = vec![];
-    let mut utxos_balance = 0;
-    for utxo in utxos.iter().rev() {
-        utxos_balance += utxo.value;
-        utxos_to_spend.push(utxo);
-        if utxos_balance >= amount + fee {
-            // We have enough inputs to cover the amount we want to spend.
-            break;
-        }
-    }
+) -> Result<UnsignedTransaction, SyronError> {
+    // @dev The UTXOs to spend were chosen by the coin selector
+    let utxos_to_spend = utxos;
+    let utxos_balance: u64 = utxos_to_spend.iter().map(|utxo| utxo.value).sum();
 
     if utxos_balance < amount + fee {
-        return Err(format!(
-            "Insufficient balance ({} sats) - Trying to transfer {} sats with a fee of {} sats. Please deposit at least {} sats into your SDB.",
-            utxos_balance, amount, fee, fee + amount - utxos_balance, // address @review (format) since now it prints P2wpkhV0([8, 102, 59, 71, 220, 132, 106, 200, 211, 158, 166, 47, 226, 90, 232, 191, 111, 237, 157, 197])
-        ));
+        return Err(SyronError::InsufficientFunds {
+            available: utxos_balance,
+            required: amount + fee
+        });
     }
 
     let inputs: Vec<UnsignedInput> = utxos_to_spend
@@ -776,48 +605,43 @@ fn build_unsigned_liquidation_with_fee(
     })
 }
 
-async fn build_unsigned_mint(
-    own_public_key: &[u8],
+fn build_unsigned_mint(
     own_address: BitcoinAddress,
     select_utxo: Utxo,
     // fee_utxos: &[ic_btc_interface::Utxo],
     fee_utxos: &[Utxo],
     dst_address: BitcoinAddress,
     fee_per_byte: MillisatoshiPerByte,
-) -> Result<UnsignedTransaction, UpdateBalanceError>  {
+    selector: &dyn CoinSelector
+) -> Result<UnsignedTransaction, SyronError>  {
     // We have a chicken-and-egg problem where we need to know the length
     // of the transaction in order to compute its proper fee, but we need
     // to know the proper fee in order to figure out the UTXO inputs needed for
     // the transaction.
     //
-    // We solve this problem iteratively. We start with a fee of zero, build
-    // and sign a transaction, see what its size is, and then update the fee,
-    // rebuild the transaction, until the fee is set to the correct amount.
+    // The selector chooses the inputs, accounting for the weight of each
+    // of them and of the change output. Then `solve_fee` settles the fee of
+    // the transaction with these inputs, from the fee of the selection,
+    // estimating its size once signed; only the final transaction is signed.
     print("Building transaction...");
-    let mut total_fee = 0;
-    loop {
-        let transaction =
-            build_unsigned_mint_with_fee(select_utxo.clone(), fee_utxos, own_address.clone(), dst_address.clone(), total_fee)?;
-
-        // Sign the transaction. In this case, we only care about the size
-        // of the signed transaction, so we use a mock signer here for efficiency.
-        let signed_transaction = sign_transaction_p2wpkh(
-            own_public_key,
-            transaction.clone(),
-            String::from(""), // mock key name
-            vec![],           // mock derivation path
-        )
-        .await.unwrap();
-
-        let signed_tx_bytes_len = signed_transaction.serialize().len() as u64;
-
-        if (signed_tx_bytes_len * fee_per_byte) / 1000 == total_fee {
-            print(&format!("Transaction built with fee {}.", total_fee));
-            return Ok(transaction);
-        } else {
-            total_fee = (signed_tx_bytes_len * fee_per_byte) / 1000;
-        }
-    }
+    // @dev The fee UTXOs only pay the fee; the inscription UTXO goes to the destination
+    let params = SelectionParams {
+        target: 0,
+        fee_per_vbyte: fee_per_byte,
+        fixed_inputs: vec![InputType::P2wpkh],
+        fixed_outputs: vec![dst_address.clone()],
+        input_type: InputType::P2wpkh,
+        change_address: own_address.clone(),
+        dust_threshold: DUST_THRESHOLD,
+    };
+    let selection = select_coins(selector, fee_utxos, &params)?;
+    let (transaction, total_fee) = solve_fee(
+        selection.fee,
+        |fee| build_unsigned_mint_with_fee(select_utxo.clone(), &selection.utxos, own_address.clone(), dst_address.clone(), fee),
+        |transaction| estimate_fee(transaction, fee_per_byte),
+    )?;
+    print(&format!("Transaction built with fee {}.", total_fee));
+    Ok(transaction)
 }
 
 fn build_unsigned_mint_with_fee(
@@ -827,29 +651,15 @@ fn build_unsigned_mint_with_fee(
     own_address: BitcoinAddress,
     dst_address: BitcoinAddress,
     fee: u64,
-) -> Result<UnsignedTransaction, UpdateBalanceError> {
-    // Select which UTXOs to spend. We naively spend the oldest available UTXOs,
-    // even if they were previously spent in a transaction. This isn't a
-    // problem as long as at most one transaction is created per block and
-    // we're using min_confirmations of 1.
-    let mut utxos_to_spend = vec![];
-    let mut to_spend_in_fees = 0;
-    for utxo in fee_utxos.iter().rev() {
-        to_spend_in_fees += utxo.value;
-        utxos_to_spend.push(utxo);
-        if to_spend_in_fees >= fee {
-            // We have enough inputs to cover the amount we want to spend.
-            break;
-        }
-    }
+) -> Result<UnsignedTransaction, SyronError> {
+    // @dev The fee UTXOs to spend were chosen by the coin selector
+    let utxos_to_spend = fee_utxos;
+    let to_spend_in_fees: u64 = utxos_to_spend.iter().map(|utxo| utxo.value).sum();
 
     if to_spend_in_fees < fee {
-        return Err(UpdateBalanceError::GenericError{
-            error_code: 5001,
-            error_message: format!(
-                "Insufficient balance: {}, to cover fee of {}",
-                to_spend_in_fees, fee
-            )
+        return Err(SyronError::InsufficientFunds {
+            available: to_spend_in_fees,
+            required: fee
         });
     }
 
@@ -886,10 +696,12 @@ fn build_unsigned_mint_with_fee(
     
     let remaining_amount = to_spend_in_fees - fee;
 
-    outputs.push(ic_ckbtc_minter_tyron::tx::TxOut {
-        address: own_address,
-        value: remaining_amount,
-    });
+    if remaining_amount > DUST_THRESHOLD {
+        outputs.push(ic_ckbtc_minter_tyron::tx::TxOut {
+            address: own_address,
+            value: remaining_amount,
+        });
+    }
     
     Ok(UnsignedTransaction {
         inputs,
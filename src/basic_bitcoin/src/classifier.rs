@@ -0,0 +1,168 @@
+// @notice Classification of the UTXOs spent by the canister
+// A UTXO is cardinal when it only carries satoshis. Inscribed and rune-bearing UTXOs must never fund fees or change,
+// whatever their value, or their inscriptions and runes would go to the miner or to the wrong output.
+// The indexers of the canister's chain report the UTXOs of an address that carry inscriptions or runes, and the local
+// registry tags outpoints that the indexers miss (e.g. runes on BestInSlot, or before they catch up). Both sources
+// are merged, so a UTXO is only cardinal if neither of them tags it.
+
+use ic_cdk::api::management_canister::bitcoin::{BitcoinNetwork, Utxo};
+use ic_stable_structures::{BoundedStorable, Storable};
+
+use crate::{
+    accounting::CyclesPayer,
+    bitcoin_api,
+    http::get_service_utxos,
+    indexer::inscription_id,
+    inscription_cache::get_network_chain_id,
+    provider::UTXO_TAGS,
+    types::{IndexedUtxo, OutpointKey, ServiceProvider, SyronError, UtxoAssets, UtxoClass},
+};
+
+/// The txid of the UTXO in hex; the bitcoin API returns its bytes in reverse order.
+pub fn utxo_txid(utxo: &Utxo) -> String {
+    let txid_bytes: Vec<u8> = utxo.outpoint.txid.iter().rev().copied().collect();
+    hex::encode(txid_bytes)
+}
+
+pub fn utxo_outpoint(utxo: &Utxo) -> OutpointKey {
+    OutpointKey::new(&utxo_txid(utxo), utxo.outpoint.vout)
+}
+
+/// Inscriptions take precedence: a UTXO with both inscriptions and runes is classified as inscribed.
+pub fn classify(assets: UtxoAssets) -> UtxoClass {
+    if !assets.inscription_ids.is_empty() {
+        UtxoClass::Inscribed {
+            inscription_ids: assets.inscription_ids,
+        }
+    } else if !assets.rune_ids.is_empty() {
+        UtxoClass::RuneBearing {
+            rune_ids: assets.rune_ids,
+        }
+    } else {
+        UtxoClass::Cardinal
+    }
+}
+
+/// Adds the assets that are not listed yet.
+fn merge_assets(assets: &mut UtxoAssets, other: UtxoAssets) {
+    for inscription_id in other.inscription_ids {
+        if !assets.inscription_ids.contains(&inscription_id) {
+            assets.inscription_ids.push(inscription_id);
+        }
+    }
+    for rune_id in other.rune_ids {
+        if !assets.rune_ids.contains(&rune_id) {
+            assets.rune_ids.push(rune_id);
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClassifiedUtxo {
+    pub utxo: Utxo,
+    pub class: UtxoClass,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ClassifiedUtxos(pub Vec<ClassifiedUtxo>);
+
+impl ClassifiedUtxos {
+    /// The UTXOs that can pay fees and change.
+    pub fn cardinal(&self) -> Vec<Utxo> {
+        self.0
+            .iter()
+            .filter(|classified| classified.class == UtxoClass::Cardinal)
+            .map(|classified| classified.utxo.clone())
+            .collect()
+    }
+
+    /// The UTXO that carries the inscription revealed by `txid`, which may have moved to another UTXO of the address.
+    pub fn inscription_utxo(&self, txid: &str) -> Result<Utxo, SyronError> {
+        let id = inscription_id(txid);
+        let inscribed = self.0.iter().find(|classified| {
+            matches!(&classified.class, UtxoClass::Inscribed { inscription_ids } if inscription_ids.contains(&id))
+        });
+        if let Some(classified) = inscribed {
+            return Ok(classified.utxo.clone());
+        }
+        match self.0.iter().find(|classified| utxo_txid(&classified.utxo) == txid) {
+            Some(classified) => Err(SyronError::UtxoNotInscribed {
+                txid: txid.to_string(),
+                vout: classified.utxo.outpoint.vout,
+                inscription_id: id,
+                class: classified.class.clone(),
+            }),
+            None => Err(SyronError::UtxoNotFound { txid: txid.to_string() }),
+        }
+    }
+}
+
+/// Classifies the UTXOs with the indexed assets and the tags of the local registry.
+pub fn classify_utxos(utxos: Vec<Utxo>, indexed: Vec<IndexedUtxo>) -> ClassifiedUtxos {
+    ClassifiedUtxos(
+        utxos
+            .into_iter()
+            .map(|utxo| {
+                let outpoint = utxo_outpoint(&utxo);
+                let mut assets = get_utxo_tags(&outpoint).unwrap_or_default();
+                for indexed in indexed.iter().filter(|indexed| indexed.outpoint == outpoint) {
+                    merge_assets(&mut assets, indexed.assets.clone());
+                }
+                ClassifiedUtxo {
+                    utxo,
+                    class: classify(assets),
+                }
+            })
+            .collect(),
+    )
+}
+
+/// Fetches and classifies the UTXOs of the address. Without an indexer for the network (regtest), only the local
+/// registry tags UTXOs; otherwise an indexer failure fails the classification, rather than spending unknown UTXOs.
+pub async fn get_classified_utxos(network: BitcoinNetwork, address: &str) -> Result<ClassifiedUtxos, SyronError> {
+    // Note that pagination may have to be used to get all UTXOs for the given address.
+    // For the sake of simplicity, it is assumed here that the `utxo` field in the response
+    // contains all UTXOs.
+    let utxos = bitcoin_api::get_utxos(network, address.to_string()).await.utxos;
+    let indexed = match get_network_chain_id(network) {
+        Some(chain_id) => {
+            get_service_utxos(address.to_string(), ServiceProvider::Chain(chain_id), CyclesPayer::Canister).await?
+        }
+        None => vec![],
+    };
+    Ok(classify_utxos(utxos, indexed))
+}
+
+// @dev Local registry of UTXO tags
+
+/// Tags the outpoint with the given assets, returning false if they are too large to be stored.
+/// Empty assets remove the tags: the outpoint is cardinal unless an indexer tags it.
+pub fn tag_utxo(outpoint: OutpointKey, assets: UtxoAssets) -> bool {
+    if assets.is_empty() {
+        untag_utxo(&outpoint);
+        return true;
+    }
+    if outpoint.to_bytes().len() > OutpointKey::MAX_SIZE as usize || assets.to_bytes().len() > UtxoAssets::MAX_SIZE as usize {
+        return false;
+    }
+    UTXO_TAGS.with(|tags| tags.borrow_mut().insert(outpoint, assets));
+    true
+}
+
+pub fn untag_utxo(outpoint: &OutpointKey) -> Option<UtxoAssets> {
+    UTXO_TAGS.with(|tags| tags.borrow_mut().remove(outpoint))
+}
+
+pub fn get_utxo_tags(outpoint: &OutpointKey) -> Option<UtxoAssets> {
+    UTXO_TAGS.with(|tags| tags.borrow().get(outpoint))
+}
+
+pub fn list_utxo_tags(offset: u64, limit: u64) -> Vec<(OutpointKey, UtxoAssets)> {
+    UTXO_TAGS.with(|tags| {
+        tags.borrow()
+            .iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect()
+    })
+}
@@ -0,0 +1,253 @@
+// @notice Coin selection of the transaction builders
+// A selector chooses which UTXOs fund a transaction, given the inputs and outputs that the transaction has whatever
+// the selection (e.g. the inscription UTXO and its destination). Fees are accounted for with the weight of each input
+// and of the change output, so that a UTXO is only worth its effective value (its value minus the fee to spend it).
+// Change below the dust threshold is not worth an output and goes to the fee.
+
+use ic_cdk::api::management_canister::bitcoin::{MillisatoshiPerByte, Satoshi, Utxo};
+use ic_ckbtc_minter_tyron::address::BitcoinAddress;
+
+use crate::{
+    types::SyronError,
+    vsize::{compute_fee, estimate_vsize, output_weight, weight_to_vsize, InputType},
+};
+
+// The number of branches that Branch-and-Bound explores before giving up, as in Bitcoin Core.
+const BNB_MAX_TRIES: u32 = 100_000;
+
+pub struct SelectionParams {
+    /// The value that the selected UTXOs must cover, besides the fee.
+    pub target: Satoshi,
+    pub fee_per_vbyte: MillisatoshiPerByte,
+    /// The inputs and outputs of the transaction that do not depend on the selection.
+    pub fixed_inputs: Vec<InputType>,
+    pub fixed_outputs: Vec<BitcoinAddress>,
+    /// How the selected UTXOs are spent.
+    pub input_type: InputType,
+    pub change_address: BitcoinAddress,
+    /// Change of at most this value goes to the fee.
+    pub dust_threshold: Satoshi,
+}
+
+impl SelectionParams {
+    /// The fee of the transaction with `inputs` selected UTXOs, with or without a change output.
+    pub fn fee(&self, inputs: usize, change: bool) -> Satoshi {
+        let mut input_types = self.fixed_inputs.clone();
+        input_types.resize(self.fixed_inputs.len() + inputs, self.input_type);
+        let mut outputs = self.fixed_outputs.clone();
+        if change {
+            outputs.push(self.change_address.clone());
+        }
+        compute_fee(estimate_vsize(&input_types, &outputs), self.fee_per_vbyte)
+    }
+
+    /// The fee to spend one more UTXO, rounded up.
+    pub fn input_fee(&self) -> Satoshi {
+        compute_fee(weight_to_vsize(self.input_type.weight()), self.fee_per_vbyte)
+    }
+
+    /// What a change output costs: the output itself, and spending it later.
+    pub fn change_cost(&self) -> Satoshi {
+        compute_fee(weight_to_vsize(output_weight(&self.change_address)), self.fee_per_vbyte) + self.input_fee()
+    }
+
+    /// The selection of these UTXOs, if they cover the target and the fee.
+    pub fn selection(&self, utxos: Vec<Utxo>) -> Option<Selection> {
+        let total: Satoshi = utxos.iter().map(|utxo| utxo.value).sum();
+        let fee_with_change = self.fee(utxos.len(), true);
+        let change = total.saturating_sub(self.target + fee_with_change);
+        if change > self.dust_threshold {
+            Some(Selection {
+                utxos,
+                fee: fee_with_change,
+                change,
+            })
+        } else {
+            self.selection_without_change(utxos)
+        }
+    }
+
+    /// The selection of these UTXOs without change: the excess goes to the fee.
+    pub fn selection_without_change(&self, utxos: Vec<Utxo>) -> Option<Selection> {
+        let total: Satoshi = utxos.iter().map(|utxo| utxo.value).sum();
+        let fee = total.checked_sub(self.target)?;
+        if fee < self.fee(utxos.len(), false) {
+            return None;
+        }
+        Some(Selection { utxos, fee, change: 0 })
+    }
+}
+
+/// The selected UTXOs pay the target, the fee and the change.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Selection {
+    pub utxos: Vec<Utxo>,
+    pub fee: Satoshi,
+    pub change: Satoshi,
+}
+
+impl Selection {
+    pub fn value(&self) -> Satoshi {
+        self.utxos.iter().map(|utxo| utxo.value).sum()
+    }
+}
+
+pub trait CoinSelector {
+    /// Returns `None` if the strategy cannot fund the transaction with these UTXOs.
+    fn select(&self, utxos: &[Utxo], params: &SelectionParams) -> Option<Selection>;
+}
+
+/// Adds the UTXOs in order until they cover the target and the fee.
+fn accumulate<'a>(utxos: impl IntoIterator<Item = &'a Utxo>, params: &SelectionParams) -> Option<Selection> {
+    let mut selected = vec![];
+    let mut total = 0;
+    for utxo in utxos {
+        selected.push(utxo.clone());
+        total += utxo.value;
+        if total >= params.target + params.fee(selected.len(), false) {
+            return params.selection(selected);
+        }
+    }
+    None
+}
+
+/// Spends the largest UTXOs first, for the fewest inputs and the lowest fee.
+pub struct LargestFirst;
+
+impl CoinSelector for LargestFirst {
+    fn select(&self, utxos: &[Utxo], params: &SelectionParams) -> Option<Selection> {
+        let mut sorted: Vec<&Utxo> = utxos.iter().collect();
+        sorted.sort_by_key(|utxo| std::cmp::Reverse(utxo.value));
+        accumulate(sorted, params)
+    }
+}
+
+/// Spends the UTXOs with the most confirmations first, which consolidates the old ones.
+pub struct OldestFirst;
+
+impl CoinSelector for OldestFirst {
+    fn select(&self, utxos: &[Utxo], params: &SelectionParams) -> Option<Selection> {
+        let mut sorted: Vec<&Utxo> = utxos.iter().collect();
+        sorted.sort_by_key(|utxo| utxo.height);
+        accumulate(sorted, params)
+    }
+}
+
+/// Avoids linking UTXOs together as inputs of the same transaction: spends the smallest UTXO that funds the
+/// transaction on its own, or else as few UTXOs as possible.
+pub struct PrivacyPreserving;
+
+impl CoinSelector for PrivacyPreserving {
+    fn select(&self, utxos: &[Utxo], params: &SelectionParams) -> Option<Selection> {
+        let single = utxos
+            .iter()
+            .filter(|utxo| utxo.value >= params.target + params.fee(1, false))
+            .min_by_key(|utxo| utxo.value);
+        match single {
+            Some(utxo) => params.selection(vec![utxo.clone()]),
+            None => LargestFirst.select(utxos, params),
+        }
+    }
+}
+
+/// Searches for UTXOs that fund the transaction without change, wasting less than the change would cost.
+/// The excess goes to the fee, even if it is above the dust threshold.
+pub struct BranchAndBound;
+
+struct Search<'a> {
+    candidates: Vec<(&'a Utxo, Satoshi)>,
+    // The effective value of the candidates from each index on.
+    remaining: Vec<Satoshi>,
+    params: &'a SelectionParams,
+    target: Satoshi,
+    upper_bound: Satoshi,
+    tries: u32,
+    best: Option<(Satoshi, Vec<usize>)>,
+}
+
+impl Search<'_> {
+    fn explore(&mut self, index: usize, value: Satoshi, selected: &mut Vec<usize>) {
+        if self.tries >= BNB_MAX_TRIES || self.best.as_ref().is_some_and(|(waste, _)| *waste == 0) {
+            return;
+        }
+        self.tries += 1;
+        if value > self.upper_bound {
+            return;
+        }
+        if value >= self.target {
+            let waste = value - self.target;
+            let better = !matches!(&self.best, Some((best, _)) if *best <= waste);
+            if better && self.covers(selected) {
+                self.best = Some((waste, selected.clone()));
+            }
+            return;
+        }
+        if index == self.candidates.len() || value + self.remaining[index] < self.target {
+            return;
+        }
+        let effective_value = self.candidates[index].1;
+        selected.push(index);
+        self.explore(index + 1, value + effective_value, selected);
+        selected.pop();
+        self.explore(index + 1, value, selected);
+    }
+
+    /// The effective values are rounded per input, so the exact fee is checked again.
+    fn covers(&self, selected: &[usize]) -> bool {
+        let total: Satoshi = selected.iter().map(|index| self.candidates[*index].0.value).sum();
+        total >= self.params.target + self.params.fee(selected.len(), false)
+    }
+}
+
+impl CoinSelector for BranchAndBound {
+    fn select(&self, utxos: &[Utxo], params: &SelectionParams) -> Option<Selection> {
+        let input_fee = params.input_fee();
+        let mut candidates: Vec<(&Utxo, Satoshi)> = utxos
+            .iter()
+            .filter(|utxo| utxo.value > input_fee)
+            .map(|utxo| (utxo, utxo.value - input_fee))
+            .collect();
+        candidates.sort_by_key(|(_, effective_value)| std::cmp::Reverse(*effective_value));
+
+        let mut remaining = vec![0; candidates.len() + 1];
+        for index in (0..candidates.len()).rev() {
+            remaining[index] = remaining[index + 1] + candidates[index].1;
+        }
+
+        let target = params.target + params.fee(0, false);
+        let mut search = Search {
+            candidates,
+            remaining,
+            params,
+            target,
+            upper_bound: target + params.change_cost(),
+            tries: 0,
+            best: None,
+        };
+        search.explore(0, 0, &mut vec![]);
+
+        let (_, selected) = search.best?;
+        let utxos = selected.iter().map(|index| search.candidates[*index].0.clone()).collect();
+        // @dev The excess is worth less than a change output, which would be spent later
+        params.selection_without_change(utxos)
+    }
+}
+
+/// Tries the first strategy, then the second one.
+pub struct WithFallback<A, B>(pub A, pub B);
+
+impl<A: CoinSelector, B: CoinSelector> CoinSelector for WithFallback<A, B> {
+    fn select(&self, utxos: &[Utxo], params: &SelectionParams) -> Option<Selection> {
+        self.0.select(utxos, params).or_else(|| self.1.select(utxos, params))
+    }
+}
+
+/// A transaction without change if there is one, or else the oldest UTXOs first.
+pub const DEFAULT_COIN_SELECTOR: WithFallback<BranchAndBound, OldestFirst> = WithFallback(BranchAndBound, OldestFirst);
+
+pub fn select_coins(selector: &dyn CoinSelector, utxos: &[Utxo], params: &SelectionParams) -> Result<Selection, SyronError> {
+    selector.select(utxos, params).ok_or_else(|| SyronError::InsufficientFunds {
+        available: utxos.iter().map(|utxo| utxo.value).sum(),
+        required: params.target + params.fee(utxos.len(), false),
+    })
+}
@@ -18,29 +18,124 @@ pub const AUTH_SET_STORABLE_MAX_SIZE: u32 = 1000;
 
 pub const PROVIDER_MAX_SIZE: u32 = 1024;
 
+pub const PROVIDER_CREDENTIALS_MAX_SIZE: u32 = 1024;
+
+pub const PROVIDER_HEALTH_MAX_SIZE: u32 = 512;
+
+// Error messages kept in the provider health are truncated to this length.
+pub const PROVIDER_HEALTH_ERROR_MAX_LEN: usize = 256;
+
+// A provider is skipped by the failover after this many consecutive failures...
+pub const PROVIDER_MAX_CONSECUTIVE_FAILURES: u32 = 3;
+
+// ...until this time has passed since its last failure (5 minutes).
+pub const PROVIDER_RETRY_AFTER_NS: u64 = 300_000_000_000;
+
 pub const STORABLE_SERVICE_MAX_SIZE: u32 = 1024;
 
+pub const INSCRIPTION_ID_MAX_SIZE: u32 = 80;
+
+pub const CONSUMED_INSCRIPTION_MAX_SIZE: u32 = 256;
+
+pub const INSCRIPTION_CACHE_KEY_MAX_SIZE: u32 = 160;
+
+pub const CACHED_INSCRIPTION_MAX_SIZE: u32 = 512;
+
+pub const INSCRIPTION_CACHE_USE_MAX_SIZE: u32 = 200;
+
+// When the inscription cache is full, the least recently used entry is evicted.
+pub const INSCRIPTION_CACHE_MAX_ENTRIES: u64 = 10_000;
+
+// Inscription results are cached once their UTXO has this many confirmations.
+pub const INSCRIPTION_CACHE_MIN_CONFIRMATIONS: u32 = 6;
+
+pub const OUTPOINT_KEY_MAX_SIZE: u32 = 100;
+
+// The assets of a tagged UTXO, which bounds the number of inscriptions and runes it can list.
+pub const UTXO_ASSETS_MAX_SIZE: u32 = 1024;
+
+pub const JOURNAL_ENTRY_MAX_SIZE: u32 = 1024;
+
+pub const SSI_MAX_SIZE: u32 = 100;
+
+// Ownership proofs cannot be valid for longer than an hour.
+pub const PROOF_MAX_VALIDITY_NS: u64 = 3_600_000_000_000;
+
+// Interval between reconciliations of the operation journal.
+pub const RECONCILE_INTERVAL_SECS: u64 = 300;
+
+// Incomplete journal entries are reconciled once they have not progressed for this long (10 minutes).
+pub const JOURNAL_STALE_AFTER_NS: u64 = 600_000_000_000;
+
+pub const JOURNAL_MAX_ATTEMPTS: u32 = 5;
+
+// @dev HTTPS outcall pricing (https://internetcomputer.org/docs/current/developer-docs/gas-cost)
+
+pub const NODES_IN_SUBNET: u32 = 13;
+
+pub const HTTP_OUTCALL_REQUEST_BASE_COST: u128 = 3_000_000;
+
+pub const HTTP_OUTCALL_REQUEST_PER_NODE_COST: u128 = 60_000;
+
+pub const HTTP_OUTCALL_REQUEST_COST_PER_BYTE: u128 = 400;
+
+pub const HTTP_OUTCALL_RESPONSE_COST_PER_BYTE: u128 = 800;
+
+// The response size limit of the first indexer call of an endpoint type.
+pub const DEFAULT_MAX_RESPONSE_BYTES: u64 = 2_048;
+
+// The IC does not accept responses larger than 2MB.
+pub const MAX_RESPONSE_BYTES_CAP: u64 = 2_000_000;
+
+// Factor by which the response size limit grows when the response is too large.
+pub const RESPONSE_BYTES_GROWTH_FACTOR: u64 = 4;
+
+// Successful calls with an escalated response size limit before it shrinks by the growth factor.
+pub const RESPONSE_BYTES_DECAY_CALLS: u64 = 10;
+
+// SPV proofs can require at most this many confirmations (a day of blocks).
+pub const SPV_MAX_CONFIRMATIONS: u32 = 144;
+
+// The fee solver gives up after this many iterations. Each iteration raises the fee, which adds inputs or
+// outputs to the transaction, so it only takes more with as many small UTXOs.
+pub const MAX_FEE_ITERATIONS: u32 = 64;
+
+// Providers cannot withdraw less than this amount of owed cycles.
+pub const MINIMUM_WITHDRAWAL_CYCLES: u128 = 1_000_000_000;
+
 pub const CONTENT_TYPE_HEADER: &str = "Content-Type";
 
 pub const CONTENT_TYPE_VALUE: &str = "application/json";
 
+// @governance
+
+pub const MIN_WITHDRAWAL_AMOUNT: u64 = 20_000_000;
+
+pub const LIQUIDATION_THRESHOLD: u64 = 12_000;
+
+// BRC-20 ticker of the Syron stablecoin (tickers are case-insensitive).
+pub const SYRON_TICKER: &str = "SYRON";
+
 // @dev Providers
 
-pub const TYRON_MAINNET_HOSTNAME: &str = "btc.tyrondao.org/";
+pub const TYRON_MAINNET_HOSTNAME: &str = "btc.tyrondao.org";
 
-pub const TYRON_TESTNET_HOSTNAME: &str = "testnet.tyron.io/";
+pub const TYRON_TESTNET_HOSTNAME: &str = "testnet.tyron.io";
 
 pub const TYRON_CREDENTIAL_PATH: &str = "api/";
 
-pub const UNISAT_MAINNET_HOSTNAME: &str = "open-api.unisat.io/";
+pub const UNISAT_MAINNET_HOSTNAME: &str = "open-api.unisat.io";
 
-pub const UNISAT_TESTNET_HOSTNAME: &str = "open-api-testnet.unisat.io/";
+pub const UNISAT_TESTNET_HOSTNAME: &str = "open-api-testnet.unisat.io";
 
-pub const BIS_MAINNET_HOSTNAME: &str = "api.bestinslot.xyz/";
+pub const BIS_MAINNET_HOSTNAME: &str = "api.bestinslot.xyz";
 
-pub const BIS_TESTNET_HOSTNAME: &str = "testnet.api.bestinslot.xyz/";
+pub const BIS_TESTNET_HOSTNAME: &str = "testnet.api.bestinslot.xyz";
 
-pub const BIS_SIGNET_HOSTNAME: &str = "signet_api.bestinslot.xyz/";
+pub const BIS_SIGNET_HOSTNAME: &str = "signet_api.bestinslot.xyz";
 
 pub const BIS_CREDENTIAL_PATH: &str = "v3/";
 
+// Header names that can carry provider credentials.
+pub const CREDENTIAL_HEADER_ALLOWLIST: [&str; 2] = ["Authorization", "x-api-key"];
+
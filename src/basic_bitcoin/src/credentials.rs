@@ -0,0 +1,80 @@
+// @notice Provider credentials
+// API keys are write-only: the header values are kept in their own stable map and only read to build
+// the provider requests. Providers keep the header names with masked values, so no query can leak them.
+
+use ic_canister_log::log;
+use ic_cdk::api::management_canister::http_request::HttpHeader;
+use ic_ckbtc_minter_tyron::logs::P1;
+
+use crate::{
+    provider::{CREDENTIALS, PROVIDERS},
+    types::ProviderCredentials,
+};
+
+pub const CREDENTIAL_MASK: &str = "*****";
+
+pub fn mask_headers(headers: &[HttpHeader]) -> Vec<HttpHeader> {
+    headers
+        .iter()
+        .map(|header| HttpHeader {
+            name: header.name.clone(),
+            value: CREDENTIAL_MASK.to_string(),
+        })
+        .collect()
+}
+
+/// Stores the credential headers of the provider and returns them masked.
+pub fn store_credentials(provider_id: u64, headers: Vec<HttpHeader>) -> Vec<HttpHeader> {
+    let masked = mask_headers(&headers);
+    CREDENTIALS.with(|credentials| {
+        let mut credentials = credentials.borrow_mut();
+        if headers.is_empty() {
+            credentials.remove(&provider_id);
+        } else {
+            credentials.insert(provider_id, ProviderCredentials { headers });
+        }
+    });
+    masked
+}
+
+pub fn remove_credentials(provider_id: u64) {
+    CREDENTIALS.with(|credentials| credentials.borrow_mut().remove(&provider_id));
+}
+
+pub fn get_credentials(provider_id: u64) -> Vec<HttpHeader> {
+    CREDENTIALS.with(|credentials| {
+        credentials
+            .borrow()
+            .get(&provider_id)
+            .unwrap_or_default()
+            .headers
+    })
+}
+
+/// Reads the credentials to call the provider; every access is logged, without the values.
+pub fn read_credentials(provider_id: u64) -> Vec<HttpHeader> {
+    let headers = get_credentials(provider_id);
+    if !headers.is_empty() {
+        let names: Vec<&str> = headers.iter().map(|header| header.name.as_str()).collect();
+        log!(P1, "[credentials] Provider {} credentials read: {:?}", provider_id, names);
+    }
+    headers
+}
+
+/// Moves plaintext header values of providers stored before the credential store into it.
+pub fn migrate_credentials() -> usize {
+    let providers: Vec<_> = PROVIDERS.with(|providers| {
+        providers
+            .borrow()
+            .iter()
+            .map(|(_, provider)| provider)
+            .filter(|provider| provider.credential_headers.iter().any(|header| header.value != CREDENTIAL_MASK))
+            .collect()
+    });
+    let migrated = providers.len();
+    for mut provider in providers {
+        provider.credential_headers = store_credentials(provider.provider_id, provider.credential_headers);
+        PROVIDERS.with(|p| p.borrow_mut().insert(provider.provider_id, provider));
+    }
+    migrated
+}
@@ -0,0 +1,248 @@
+// @notice Ordinals envelopes of transfer inscriptions
+// The content of a transfer inscription is read from the witness of its reveal transaction,
+// so that the indexer is only trusted to confirm that the transfer is valid (not over-balance).
+// The envelope is `OP_FALSE OP_IF "ord" <tag> <value> ... OP_0 <body> ... OP_ENDIF` in a tapscript.
+
+use bitcoin::{
+    blockdata::{
+        opcodes::{self, all::{OP_ENDIF, OP_IF, OP_PUSHNUM_1, OP_PUSHNUM_16, OP_PUSHNUM_NEG1}},
+        script::{Instruction, Script},
+        witness::Witness,
+    },
+    consensus::deserialize,
+    Transaction,
+};
+use serde_json::Value;
+
+use crate::{
+    amount::parse_brc20_amount,
+    indexer::inscription_id,
+    types::{Brc20Transfer, EnvelopeError, InscriptionInfo, SyronError},
+};
+
+const PROTOCOL_ID: &[u8] = b"ord";
+
+const BODY_TAG: &[u8] = &[];
+
+const CONTENT_TYPE_TAG: &[u8] = &[1];
+
+const CONTENT_ENCODING_TAG: &[u8] = &[9];
+
+// Even tags known to ord: pointer, parent, metadata, metaprotocol, delegate and rune.
+const KNOWN_EVEN_TAGS: [u8; 6] = [2, 4, 6, 10, 12, 14];
+
+/// Content types under which indexers accept BRC-20 operations.
+const BRC20_CONTENT_TYPES: [&str; 2] = ["text/plain", "application/json"];
+
+/// The pushes between `OP_IF "ord"` and `OP_ENDIF`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Envelope {
+    pub fields: Vec<(Vec<u8>, Vec<u8>)>,
+    pub body: Option<Vec<u8>>,
+}
+
+impl Envelope {
+    pub fn field(&self, tag: &[u8]) -> Option<&[u8]> {
+        self.fields
+            .iter()
+            .find(|(field_tag, _)| field_tag == tag)
+            .map(|(_, value)| value.as_slice())
+    }
+}
+
+/// Decodes the raw reveal transaction and checks that it hashes to the claimed txid.
+pub fn decode_reveal_transaction(txid: &str, raw_tx: &[u8]) -> Result<Transaction, EnvelopeError> {
+    let transaction: Transaction =
+        deserialize(raw_tx).map_err(|err| EnvelopeError::InvalidTransaction(err.to_string()))?;
+    let found = transaction.txid().to_string();
+    if !found.eq_ignore_ascii_case(txid.trim()) {
+        return Err(EnvelopeError::TxidMismatch {
+            expected: txid.to_string(),
+            found,
+        });
+    }
+    Ok(transaction)
+}
+
+/// The tapscript of a taproot script-path spend: the element before the control block (and annex, if any).
+pub fn get_tapscript(witness: &Witness) -> Option<Script> {
+    let mut elements = witness.to_vec();
+    if elements.len() >= 2 && elements.last().is_some_and(|last| last.first() == Some(&0x50)) {
+        elements.pop();
+    }
+    let control_block = elements.pop()?;
+    let tapscript = elements.pop()?;
+    // @dev 0xc0 is the tapscript leaf version; the lowest bit is the parity of the output key
+    let is_control_block = control_block.len() >= 33
+        && (control_block.len() - 33) % 32 == 0
+        && control_block[0] & 0xfe == 0xc0;
+    is_control_block.then(|| Script::from(tapscript))
+}
+
+/// Parses the first envelope of the tapscript, if any.
+pub fn parse_envelope(tapscript: &Script) -> Result<Option<Envelope>, EnvelopeError> {
+    let instructions: Vec<Instruction> = tapscript
+        .instructions()
+        .collect::<Result<_, _>>()
+        .map_err(|err| EnvelopeError::MalformedEnvelope(format!("{:?}", err)))?;
+
+    let start = instructions.windows(3).position(|window| {
+        matches!(
+            window,
+            [Instruction::PushBytes(&[]), Instruction::Op(OP_IF), Instruction::PushBytes(PROTOCOL_ID)]
+        )
+    });
+    let start = match start {
+        Some(start) => start + 3,
+        None => return Ok(None),
+    };
+
+    let mut pushes: Vec<Vec<u8>> = vec![];
+    for instruction in &instructions[start..] {
+        match instruction {
+            Instruction::Op(OP_ENDIF) => return parse_payload(pushes).map(Some),
+            Instruction::PushBytes(bytes) => pushes.push(bytes.to_vec()),
+            Instruction::Op(op) => pushes.push(vec![push_num(*op).ok_or_else(|| {
+                EnvelopeError::MalformedEnvelope(format!("unexpected {:?} in envelope", op))
+            })?]),
+        }
+    }
+    Err(EnvelopeError::MalformedEnvelope("missing OP_ENDIF".to_string()))
+}
+
+/// The byte pushed by `OP_1NEGATE` and `OP_1` to `OP_16`.
+fn push_num(op: opcodes::All) -> Option<u8> {
+    let code = op.into_u8();
+    if op == OP_PUSHNUM_NEG1 {
+        Some(0x81)
+    } else if (OP_PUSHNUM_1.into_u8()..=OP_PUSHNUM_16.into_u8()).contains(&code) {
+        Some(code - OP_PUSHNUM_1.into_u8() + 1)
+    } else {
+        None
+    }
+}
+
+/// Splits the pushes into tag/value fields and the body, which follows the first empty tag.
+fn parse_payload(pushes: Vec<Vec<u8>>) -> Result<Envelope, EnvelopeError> {
+    let mut fields: Vec<(Vec<u8>, Vec<u8>)> = vec![];
+    let mut pushes = pushes.into_iter();
+    while let Some(tag) = pushes.next() {
+        if tag == BODY_TAG {
+            return Ok(Envelope {
+                fields,
+                body: Some(pushes.flatten().collect()),
+            });
+        }
+        let value = pushes
+            .next()
+            .ok_or_else(|| EnvelopeError::MalformedEnvelope(format!("field {:?} has no value", tag)))?;
+        if fields.iter().any(|(field_tag, _)| *field_tag == tag) {
+            return Err(EnvelopeError::MalformedEnvelope(format!("duplicate field {:?}", tag)));
+        }
+        fields.push((tag, value));
+    }
+    Ok(Envelope { fields, body: None })
+}
+
+/// The envelope of the first inscription (`i0`) of the transaction.
+pub fn find_first_envelope(transaction: &Transaction) -> Result<Envelope, EnvelopeError> {
+    for input in &transaction.input {
+        if let Some(tapscript) = get_tapscript(&input.witness) {
+            if let Some(envelope) = parse_envelope(&tapscript)? {
+                return Ok(envelope);
+            }
+        }
+    }
+    Err(EnvelopeError::EnvelopeNotFound)
+}
+
+/// Validates the envelope of the inscription as a BRC-20 transfer of the given ticker.
+pub fn parse_brc20_transfer(inscription_id: String, envelope: &Envelope, ticker: &str) -> Result<Brc20Transfer, EnvelopeError> {
+    // @dev Inscriptions with unknown even fields or encoded content are not recognized by the indexers
+    if let Some((tag, _)) = envelope.fields.iter().find(|(tag, _)| {
+        matches!(tag.as_slice(), [tag] if tag % 2 == 0 && !KNOWN_EVEN_TAGS.contains(tag))
+    }) {
+        return Err(EnvelopeError::MalformedEnvelope(format!("unrecognized even field {:?}", tag)));
+    }
+    if envelope.field(CONTENT_ENCODING_TAG).is_some() {
+        return Err(EnvelopeError::MalformedEnvelope("encoded content".to_string()));
+    }
+
+    let content_type = envelope
+        .field(CONTENT_TYPE_TAG)
+        .map(|content_type| String::from_utf8_lossy(content_type).to_string())
+        .unwrap_or_default();
+    let media_type = content_type.split(';').next().unwrap_or_default().trim();
+    if !BRC20_CONTENT_TYPES.iter().any(|allowed| allowed.eq_ignore_ascii_case(media_type)) {
+        return Err(EnvelopeError::UnsupportedContentType(content_type));
+    }
+
+    let body = envelope.body.as_deref().unwrap_or_default();
+    let json: Value = serde_json::from_slice(body).map_err(|err| EnvelopeError::InvalidJson(err.to_string()))?;
+    let field = |name: &str| -> Result<String, EnvelopeError> {
+        json.get(name)
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .ok_or_else(|| EnvelopeError::NotBrc20Transfer(format!("missing string field '{}'", name)))
+    };
+
+    let protocol = field("p")?;
+    if protocol != "brc-20" {
+        return Err(EnvelopeError::NotBrc20Transfer(format!("protocol '{}'", protocol)));
+    }
+    let op = field("op")?;
+    if op != "transfer" {
+        return Err(EnvelopeError::NotBrc20Transfer(format!("operation '{}'", op)));
+    }
+    let tick = field("tick")?;
+    if !tick.eq_ignore_ascii_case(ticker) {
+        return Err(EnvelopeError::TickerMismatch {
+            expected: ticker.to_string(),
+            found: tick,
+        });
+    }
+    let amt = field("amt")?;
+    let amount = parse_brc20_amount(&amt).map_err(EnvelopeError::InvalidAmount)?;
+    if amount == 0 {
+        return Err(EnvelopeError::NotBrc20Transfer("zero amount".to_string()));
+    }
+    Ok(Brc20Transfer {
+        inscription_id,
+        content_type,
+        tick,
+        amt,
+        amount,
+    })
+}
+
+/// Verifies the transfer inscription revealed by `txid` from the raw reveal transaction.
+pub fn verify_reveal_transaction(txid: &str, raw_tx: &[u8], ticker: &str) -> Result<Brc20Transfer, SyronError> {
+    let invalid = |error| SyronError::InvalidInscription {
+        txid: txid.to_string(),
+        error,
+    };
+    let transaction = decode_reveal_transaction(txid, raw_tx).map_err(invalid)?;
+    let envelope = find_first_envelope(&transaction).map_err(invalid)?;
+    parse_brc20_transfer(inscription_id(&transaction.txid().to_string()), &envelope, ticker).map_err(invalid)
+}
+
+/// Checks that the indexer reports the transfer inscribed in the witness.
+pub fn check_indexed_transfer(transfer: &Brc20Transfer, indexed: &InscriptionInfo) -> Result<(), SyronError> {
+    let mismatch = |field: &str, inscribed: &str, indexed: &str| {
+        Err(SyronError::InscriptionContentMismatch {
+            field: field.to_string(),
+            inscribed: inscribed.to_string(),
+            indexed: indexed.to_string(),
+        })
+    };
+    if indexed.op != "transfer" {
+        return mismatch("op", "transfer", &indexed.op);
+    }
+    if !indexed.ticker.trim().eq_ignore_ascii_case(&transfer.tick) {
+        return mismatch("ticker", &transfer.tick, &indexed.ticker);
+    }
+    if parse_brc20_amount(indexed.amt.trim()).ok() != Some(transfer.amount) {
+        return mismatch("amt", &transfer.amt, &indexed.amt);
+    }
+    Ok(())
+}
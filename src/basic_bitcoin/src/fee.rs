@@ -0,0 +1,34 @@
+// @notice Fee solver of the transaction builders
+// The fee of a transaction depends on its inputs, and the inputs depend on the fee. The solver starts with a lower
+// bound of the fee (zero, or the fee of the coin selection), builds the transaction and raises the fee to what the
+// transaction requires, until the transaction covers it.
+// The fee never decreases, so a transaction that gets smaller with a higher fee (e.g. without change) cannot make it
+// oscillate; it pays the higher fee instead. Every iteration raises the fee, and there are at most
+// `MAX_FEE_ITERATIONS` of them.
+
+use ic_cdk::api::management_canister::bitcoin::Satoshi;
+
+use crate::{types::SyronError, MAX_FEE_ITERATIONS};
+
+/// Returns the transaction built with the lowest fee (on the path from `initial_fee`) that covers the fee it
+/// requires, and that fee.
+/// `build` returns an error when the fee cannot be paid, which is returned as is.
+pub fn solve_fee<T>(
+    initial_fee: Satoshi,
+    mut build: impl FnMut(Satoshi) -> Result<T, SyronError>,
+    mut required_fee: impl FnMut(&T) -> Satoshi,
+) -> Result<(T, Satoshi), SyronError> {
+    let mut fee = initial_fee;
+    for _ in 0..MAX_FEE_ITERATIONS {
+        let transaction = build(fee)?;
+        let required = required_fee(&transaction);
+        if required <= fee {
+            return Ok((transaction, fee));
+        }
+        fee = required;
+    }
+    Err(SyronError::FeeNotConverged {
+        iterations: MAX_FEE_ITERATIONS,
+        fee,
+    })
+}
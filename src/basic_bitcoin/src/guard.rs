@@ -0,0 +1,75 @@
+// @notice Per-SSI operation locks
+// Operations read balances, await outcalls and signatures, and then act on what they read.
+// A guard locks the SSI (and the liquidator's SSI) for the lifetime of the operation so that two
+// calls for the same account cannot interleave, like the minter's `AlreadyProcessing` guard.
+// The lock is released when the guard is dropped, which ic-cdk also does when a callback traps.
+
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+
+use crate::types::SyronError;
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LockKey {
+    Ssi(String),
+}
+
+impl LockKey {
+    fn account(&self) -> &str {
+        match self {
+            LockKey::Ssi(ssi) => ssi,
+        }
+    }
+}
+
+thread_local! {
+    static LOCKS: RefCell<BTreeSet<LockKey>> = const { RefCell::new(BTreeSet::new()) };
+}
+
+#[must_use]
+#[derive(Debug)]
+pub struct OperationGuard {
+    keys: Vec<LockKey>,
+}
+
+impl OperationGuard {
+    /// Locks all the given keys, or none of them if any is already locked.
+    pub fn new(mut keys: Vec<LockKey>) -> Result<Self, SyronError> {
+        keys.sort();
+        keys.dedup();
+        LOCKS.with(|locks| {
+            let mut locks = locks.borrow_mut();
+            if let Some(locked) = keys.iter().find(|key| locks.contains(key)) {
+                return Err(SyronError::AlreadyProcessing {
+                    ssi: locked.account().to_string(),
+                });
+            }
+            locks.extend(keys.iter().cloned());
+            Ok(Self { keys })
+        })
+    }
+
+    pub fn ssi(ssi: &str) -> Result<Self, SyronError> {
+        Self::new(vec![LockKey::Ssi(ssi.to_string())])
+    }
+
+    /// Locks the debtor's SSI and the liquidator's, whose safety deposit box pays the liquidation.
+    pub fn liquidation(ssi: &str, liquidator: &str) -> Result<Self, SyronError> {
+        Self::new(vec![LockKey::Ssi(ssi.to_string()), LockKey::Ssi(liquidator.to_string())])
+    }
+}
+
+impl Drop for OperationGuard {
+    fn drop(&mut self) {
+        LOCKS.with(|locks| {
+            let mut locks = locks.borrow_mut();
+            for key in &self.keys {
+                locks.remove(key);
+            }
+        });
+    }
+}
+
+pub fn is_locked(key: &LockKey) -> bool {
+    LOCKS.with(|locks| locks.borrow().contains(key))
+}
@@ -0,0 +1,82 @@
+// @notice Provider health and failover
+// Every indexer call records its outcome. Requests for a `ServiceProvider::Chain` are retried on
+// the next healthy provider of the chain, so that an indexer outage does not block the Syron operations.
+
+use crate::{
+    provider::{PROVIDERS, PROVIDER_HEALTH},
+    types::{ProviderHealth, ServiceProvider},
+    PROVIDER_HEALTH_ERROR_MAX_LEN, PROVIDER_MAX_CONSECUTIVE_FAILURES, PROVIDER_RETRY_AFTER_NS,
+};
+
+pub fn get_provider_health(provider_id: u64) -> ProviderHealth {
+    PROVIDER_HEALTH.with(|health| health.borrow().get(&provider_id).unwrap_or_default())
+}
+
+pub fn list_provider_health() -> Vec<(u64, ProviderHealth)> {
+    PROVIDER_HEALTH.with(|health| health.borrow().iter().collect())
+}
+
+fn update_health(provider_id: u64, update: impl FnOnce(&mut ProviderHealth)) {
+    PROVIDER_HEALTH.with(|health| {
+        let mut health = health.borrow_mut();
+        let mut provider_health = health.get(&provider_id).unwrap_or_default();
+        update(&mut provider_health);
+        health.insert(provider_id, provider_health);
+    });
+}
+
+pub fn record_success(provider_id: u64, latency_ns: u64, now: u64) {
+    update_health(provider_id, |health| {
+        health.successes += 1;
+        health.consecutive_failures = 0;
+        health.last_latency_ns = Some(latency_ns);
+        health.last_success_at = Some(now);
+    });
+}
+
+pub fn record_failure(provider_id: u64, error: &str, now: u64) {
+    update_health(provider_id, |health| {
+        health.failures += 1;
+        health.consecutive_failures = health.consecutive_failures.saturating_add(1);
+        health.last_error = Some(error.chars().take(PROVIDER_HEALTH_ERROR_MAX_LEN).collect());
+        health.last_failure_at = Some(now);
+    });
+}
+
+/// A failing provider is given another chance once `PROVIDER_RETRY_AFTER_NS` has passed since its last failure.
+pub fn is_healthy(health: &ProviderHealth, now: u64) -> bool {
+    health.consecutive_failures < PROVIDER_MAX_CONSECUTIVE_FAILURES
+        || health
+            .last_failure_at
+            .map_or(true, |failed_at| failed_at.saturating_add(PROVIDER_RETRY_AFTER_NS) <= now)
+}
+
+/// The providers to call for the service, in order. A chain is served by its primary provider
+/// and then by the other healthy providers of the chain; if none is healthy, the first one is still tried.
+pub fn failover_providers(service: &ServiceProvider, now: u64) -> Vec<u64> {
+    let chain_id = match service {
+        ServiceProvider::Provider(provider_id) => return vec![*provider_id],
+        ServiceProvider::Chain(chain_id) => *chain_id,
+    };
+    let mut providers: Vec<(bool, u64)> = PROVIDERS.with(|providers| {
+        providers
+            .borrow()
+            .iter()
+            .filter(|(_, provider)| provider.chain_id == chain_id)
+            .map(|(id, provider)| (!provider.primary, id))
+            .collect()
+    });
+    providers.sort();
+    let providers: Vec<u64> = providers.into_iter().map(|(_, id)| id).collect();
+
+    let healthy: Vec<u64> = providers
+        .iter()
+        .copied()
+        .filter(|id| is_healthy(&get_provider_health(*id), now))
+        .collect();
+    if healthy.is_empty() {
+        providers.into_iter().take(1).collect()
+    } else {
+        healthy
+    }
+}
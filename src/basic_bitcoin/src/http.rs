@@ -1,53 +1,188 @@
 use ic_cdk::api::management_canister::http_request::{CanisterHttpRequestArgument, HttpHeader, HttpMethod, HttpResponse, TransformArgs, TransformContext};
-use ic_ckbtc_minter_tyron::updates::update_balance::UpdateBalanceError;
-use serde_json::Value;
-use crate::{resolve_service_provider, HttpOutcallError, ResolvedServiceProvider, ServiceError, ServiceProvider, ServiceResult, CONTENT_TYPE_HEADER, CONTENT_TYPE_VALUE };
+use ic_canister_log::log;
+use ic_ckbtc_minter_tyron::logs::P1;
+use serde_json::{Map, Value};
+use crate::{accounting::{accrue_cycles_owed, collect_cycles, get_http_request_cost, get_provider_cost, get_request_size, CyclesPayer}, amount::parse_brc20_amount, credentials::read_credentials, indexer::{inscription_id, provider_client, IndexerClient}, resolve_service_provider, Brc20Balance, HttpOutcallError, IndexedUtxo, InscriptionInfo, ProviderApi, ResolvedServiceProvider, ServiceError, ServiceProvider, ServiceResult, SyronError, CONTENT_TYPE_HEADER, CONTENT_TYPE_VALUE };
 use num_traits::ToPrimitive;
+use crate::{health::{failover_providers, record_failure, record_success}, IndexerEndpoint, Provider, ProviderError, BTC_MAINNET_CHAIN_ID, RESPONSE_BYTES_GROWTH_FACTOR, SYRON_TICKER};
+use crate::response_size::{get_cached_response_bytes, get_max_response_bytes_cap, record_response_size};
 
 pub async fn call_indexer_inscription(
     provider: u64,
     txid: String,
-    cycles_cost: u128
-) -> Result<String, UpdateBalanceError> {
-    let endpoint = match provider {
-        0 | 1 =>   format!("get-unisat-inscription-info?id={}i0", txid), // @tyron
-        2 | 3 =>   format!("v1/indexer/inscription/info/{}i0", txid), // @unisat
-        _ => format!("inscription/single_info_id?inscription_id={}i0", txid) // @bis
-    };
+    payer: CyclesPayer
+) -> Result<InscriptionInfo, SyronError> {
+    let client = get_indexer_client(provider)?;
+    let endpoint = client.inscription_endpoint(&inscription_id(&txid));
 
-    let outcall = match web3_request(ServiceProvider::Provider(provider), &endpoint, "", 2048, cycles_cost).await {
-        Ok(result) => result,
-        Err(err) => {
-            return Err(UpdateBalanceError::GenericError{
-                error_code: 333,
-                error_message: format!("Failed to finalize HTTPS Outcall with error: {:?}", err),
-            });
-        }
+    let started_at = ic_cdk::api::time();
+    let result = call_indexer(provider, IndexerEndpoint::Inscription, &endpoint, payer).await.and_then(|outcall| {
+        client.parse_inscription(&outcall).map_err(|reason| SyronError::InvalidIndexerResponse { provider, reason })
+    });
+    let result = match (result, client.holder_endpoint(&inscription_id(&txid))) {
+        (Ok(inscription), Some(endpoint)) => with_holder(client, provider, inscription, &endpoint, payer).await,
+        (result, _) => result,
     };
-    Ok(outcall)
+    record_health(provider, &result, started_at);
+    result
+}
+
+/// Replaces the address that inscribed the inscription with its current holder.
+async fn with_holder(
+    client: &dyn IndexerClient,
+    provider: u64,
+    mut inscription: InscriptionInfo,
+    endpoint: &str,
+    payer: CyclesPayer
+) -> Result<InscriptionInfo, SyronError> {
+    let outcall = call_indexer(provider, IndexerEndpoint::Holder, endpoint, payer).await?;
+    let (owner_address, satpoint) = client
+        .parse_holder(&outcall)
+        .map_err(|reason| SyronError::InvalidIndexerResponse { provider, reason })?;
+    inscription.owner_address = owner_address;
+    inscription.satpoint = satpoint.or(inscription.satpoint);
+    Ok(inscription)
 }
 
 pub async fn call_indexer_balance(
     address: String,
     provider: u64,
-    cycles_cost: u128
-) -> Result<String, UpdateBalanceError> {
-    let endpoint = match provider {
-        0 | 1 =>   format!("get-unisat-brc20-info?id={}", address), // @tyron
-        2 | 3 =>   format!("v1/indexer/address/{}/brc20/summary", address), // @unisat
-        _ => format!("inscription/single_info_id?inscription_id={}i0", address) // @bis
-    };
+    payer: CyclesPayer
+) -> Result<Vec<Brc20Balance>, SyronError> {
+    let client = get_indexer_client(provider)?;
+    let endpoint = client.balance_endpoint(&address);
 
-    let outcall = match web3_request(ServiceProvider::Provider(provider), &endpoint, "", 2048, cycles_cost).await {
-        Ok(result) => result,
-        Err(err) => {
-            return Err(UpdateBalanceError::GenericError{
-                error_code: 444,
-                error_message: format!("Failed to finalize HTTPS Outcall with error: {:?}", err),
-            });
+    let started_at = ic_cdk::api::time();
+    let result = call_indexer(provider, IndexerEndpoint::Balance, &endpoint, payer).await.and_then(|outcall| {
+        client.parse_balances(&outcall).map_err(|reason| SyronError::InvalidIndexerResponse { provider, reason })
+    });
+    record_health(provider, &result, started_at);
+    result
+}
+
+pub async fn call_indexer_utxos(
+    address: String,
+    provider: u64,
+    payer: CyclesPayer
+) -> Result<Vec<IndexedUtxo>, SyronError> {
+    let client = get_indexer_client(provider)?;
+    let endpoint = client.utxos_endpoint(&address);
+
+    let started_at = ic_cdk::api::time();
+    let result = call_indexer(provider, IndexerEndpoint::Utxos, &endpoint, payer).await.and_then(|outcall| {
+        client.parse_utxos(&outcall).map_err(|reason| SyronError::InvalidIndexerResponse { provider, reason })
+    });
+    record_health(provider, &result, started_at);
+    result
+}
+
+/// Gets the inscription from the providers of the service, failing over to the next provider while they are unavailable.
+pub async fn get_service_inscription(
+    service: ServiceProvider,
+    txid: String,
+    payer: CyclesPayer
+) -> Result<InscriptionInfo, SyronError> {
+    let mut result = Err(ProviderError::ProviderNotFound.into());
+    for provider in failover_providers(&service, ic_cdk::api::time()) {
+        result = call_indexer_inscription(provider, txid.clone(), payer).await;
+        if !is_provider_failure(&result) {
+            break;
         }
-    };
-    Ok(outcall)
+    }
+    result
+}
+
+/// Gets the BRC-20 balances from the providers of the service, failing over like `get_service_inscription`.
+pub async fn get_service_balance(
+    address: String,
+    service: ServiceProvider,
+    payer: CyclesPayer
+) -> Result<Vec<Brc20Balance>, SyronError> {
+    let mut result = Err(ProviderError::ProviderNotFound.into());
+    for provider in failover_providers(&service, ic_cdk::api::time()) {
+        result = call_indexer_balance(address.clone(), provider, payer).await;
+        if !is_provider_failure(&result) {
+            break;
+        }
+    }
+    result
+}
+
+/// Gets the UTXOs that carry inscriptions or runes from the providers of the service, failing over like
+/// `get_service_inscription`.
+pub async fn get_service_utxos(
+    address: String,
+    service: ServiceProvider,
+    payer: CyclesPayer
+) -> Result<Vec<IndexedUtxo>, SyronError> {
+    let mut result = Err(ProviderError::ProviderNotFound.into());
+    for provider in failover_providers(&service, ic_cdk::api::time()) {
+        result = call_indexer_utxos(address.clone(), provider, payer).await;
+        if !is_provider_failure(&result) {
+            break;
+        }
+    }
+    result
+}
+
+async fn call_indexer(
+    provider: u64,
+    endpoint_type: IndexerEndpoint,
+    endpoint: &str,
+    payer: CyclesPayer
+) -> Result<String, SyronError> {
+    match web3_request_escalating(ServiceProvider::Provider(provider), endpoint_type, endpoint, "", payer).await {
+        Ok(result) => Ok(result),
+        Err(ServiceError::ProviderError(err)) => Err(err.into()),
+        Err(ServiceError::HttpOutcallError(err)) if is_transport_error(&err) => Err(SyronError::IndexerUnavailable {
+            provider,
+            reason: format!("Failed to finalize HTTPS Outcall with error: {:?}", err),
+        }),
+        // @dev The indexer answered, e.g. that the inscription is not found
+        Err(err) => Err(SyronError::InvalidIndexerResponse {
+            provider,
+            reason: format!("{:?}", err),
+        }),
+    }
+}
+
+/// Whether the outcall failed before the indexer could answer: IC rejects (including timeouts), server errors and
+/// rate limits. Any other status is the indexer's answer.
+pub fn is_transport_error(err: &HttpOutcallError) -> bool {
+    match err {
+        HttpOutcallError::IcError { .. } => true,
+        HttpOutcallError::InvalidHttpJsonRpcResponse { status, .. } => *status >= 500 || *status == 408 || *status == 429,
+    }
+}
+
+/// Whether the provider could not be reached, so that another provider may succeed. Indexer rejections, such as an
+/// inscription that is not found or does not parse, are returned as they are: failing over on them would let a caller
+/// shop across indexers until one accepts the inscription.
+pub fn is_provider_failure<T>(result: &Result<T, SyronError>) -> bool {
+    matches!(result, Err(SyronError::IndexerUnavailable { .. }))
+}
+
+fn record_health<T>(provider: u64, result: &Result<T, SyronError>, started_at: u64) {
+    let now = ic_cdk::api::time();
+    match result {
+        Ok(_) => record_success(provider, now.saturating_sub(started_at), now),
+        Err(err) if is_provider_failure(result) => {
+            log!(P1, "Provider {} failed: {}", provider, err);
+            record_failure(provider, &format!("{}", err), now)
+        }
+        // @dev e.g. too few cycles: not the provider's fault
+        Err(_) => {}
+    }
+}
+
+fn get_indexer_client(provider: u64) -> Result<&'static dyn IndexerClient, SyronError> {
+    match resolve_service_provider(ServiceProvider::Provider(provider)) {
+        Ok(ResolvedServiceProvider::Provider(provider)) => Ok(provider_client(&provider)),
+        Err(err) => Err(SyronError::IndexerUnavailable {
+            provider,
+            reason: format!("{:?}", err),
+        }),
+    }
 }
 
 pub async fn web3_request(
@@ -55,27 +190,94 @@ pub async fn web3_request(
     endpoint: &str,
     payload: &str,
     max_response_bytes: u64,
-    cycles_cost: u128
+    payer: CyclesPayer
 ) -> Result<String, ServiceError> {
-    let response = do_request(
-        resolve_service_provider(service)?,
-        endpoint,
-        payload,
-        max_response_bytes,
-        cycles_cost
-    )
-    .await?;
+    let ResolvedServiceProvider::Provider(provider) = resolve_service_provider(service)?;
+    let request = build_request(&provider, endpoint, payload, max_response_bytes, vec![]);
+    let response = do_request(&provider, request, payload, payer).await?;
     get_http_response_body(response)
 }
 
-async fn do_request(
-    service: ResolvedServiceProvider,
+/// Like `web3_request`, but retries with a larger `max_response_bytes` while the response is too large,
+/// starting from the size that last succeeded for the provider's endpoint type. The response only keeps the fields
+/// that the indexer client reads for the endpoint type.
+pub async fn web3_request_escalating(
+    service: ServiceProvider,
+    endpoint_type: IndexerEndpoint,
+    endpoint: &str,
+    payload: &str,
+    payer: CyclesPayer
+) -> Result<String, ServiceError> {
+    let ResolvedServiceProvider::Provider(provider) = resolve_service_provider(service)?;
+    let cap = get_max_response_bytes_cap();
+    let mut max_response_bytes = get_cached_response_bytes(provider.provider_id, endpoint_type).min(cap);
+    let mut escalations = 0;
+    let mut cycles_spent = 0;
+    let mut initial_cost = 0;
+    let context = transform_context(provider_client(&provider).response_fields(endpoint_type));
+    loop {
+        let request = build_request(&provider, endpoint, payload, max_response_bytes, context.clone());
+        let cycles_cost = get_http_request_cost(get_request_size(&request), max_response_bytes);
+        cycles_spent += cycles_cost;
+        if escalations == 0 {
+            initial_cost = cycles_cost;
+        }
+
+        let result = do_request(&provider, request, payload, payer).await;
+        let next = match &result {
+            Err(ServiceError::HttpOutcallError(err)) if err.is_response_too_large() => {
+                next_max_response_bytes(max_response_bytes, cap)
+            }
+            _ => None,
+        };
+        match next {
+            Some(next) => {
+                escalations += 1;
+                max_response_bytes = next;
+            }
+            None => {
+                let succeeded = result.is_ok().then_some(max_response_bytes);
+                record_response_size(provider.provider_id, endpoint_type, succeeded, escalations, cycles_spent - initial_cost);
+                return get_http_response_body(check_http_status(result?)?);
+            }
+        }
+    }
+}
+
+/// The next response size limit, or `None` once the cap is reached.
+pub fn next_max_response_bytes(current: u64, cap: u64) -> Option<u64> {
+    if current >= cap {
+        None
+    } else {
+        Some(current.saturating_mul(RESPONSE_BYTES_GROWTH_FACTOR).min(cap))
+    }
+}
+
+/// The API URL of the provider, with its credential headers.
+fn provider_api(provider: &Provider) -> ProviderApi {
+    let headers = read_credentials(provider.provider_id);
+    ProviderApi {
+        url: format!(
+            "https://{}/{}",
+            provider.hostname.trim_end_matches('/'),
+            provider.credential_path.trim_start_matches('/')
+        ),
+        headers: if headers.is_empty() {
+            None
+        } else {
+            Some(headers)
+        },
+    }
+}
+
+fn build_request(
+    provider: &Provider,
     endpoint: &str,
     payload: &str,
     max_response_bytes: u64,
-    cycles_cost: u128
-) -> ServiceResult<HttpResponse> {
-    let api = service.api();
+    transform_context: Vec<u8>
+) -> CanisterHttpRequestArgument {
+    let api = provider_api(provider);
     let mut request_headers = vec![HttpHeader {
         name: CONTENT_TYPE_HEADER.to_string(),
         value: CONTENT_TYPE_VALUE.to_string(),
@@ -90,33 +292,35 @@ async fn do_request(
     }
 
     // Match service provider to the appropriate transform function
-    let transform_fn: Option<TransformContext> = match service {
-        ResolvedServiceProvider::Provider(provider) => {
-            match provider.provider_id {
-                0 | 1 => Some(TransformContext::from_name(
-                    "transform_request".to_string(),
-                    vec![],
-                )),
-                2 | 3 => Some(TransformContext::from_name(
-                    "transform_unisat_request".to_string(),
-                    vec![],
-                )),
-                _ => None,
-            }
-        }
-    };
+    let transform_fn: Option<TransformContext> = provider_client(provider)
+        .transform_name()
+        .map(|name| TransformContext::from_name(name.to_string(), transform_context));
 
-    let request = CanisterHttpRequestArgument {
+    CanisterHttpRequestArgument {
         url: api.url + endpoint,
         max_response_bytes: Some(max_response_bytes),
         method,
         headers: request_headers,
         body: Some(payload.as_bytes().to_vec()),
         transform: transform_fn,
-    };
+    }
+}
+
+async fn do_request(
+    provider: &Provider,
+    request: CanisterHttpRequestArgument,
+    payload: &str,
+    payer: CyclesPayer
+) -> ServiceResult<HttpResponse> {
+    // @dev The IC pricing is paid with the outcall, and the provider fee is owed to the provider
+    let max_response_bytes = request.max_response_bytes.unwrap_or_default();
+    let cycles_cost = get_http_request_cost(get_request_size(&request), max_response_bytes);
+    let provider_cost = get_provider_cost(provider, payload.len() as u64);
+    collect_cycles(payer, cycles_cost + provider_cost)?;
 
     match ic_cdk::api::management_canister::http_request::http_request(request, cycles_cost).await {
         Ok((response,)) => {
+            accrue_cycles_owed(provider.provider_id, provider_cost);
             Ok(response)
         }
         Err((code, message)) => {
@@ -125,6 +329,21 @@ async fn do_request(
     }
 }
 
+/// Fails on a status other than 2xx; the transform has already cleared the body of the response.
+fn check_http_status(response: HttpResponse) -> Result<HttpResponse, ServiceError> {
+    let status = get_http_response_status(response.status.clone());
+    if (200..300).contains(&status) {
+        Ok(response)
+    } else {
+        Err(HttpOutcallError::InvalidHttpJsonRpcResponse {
+            status,
+            body: "".to_string(),
+            parsing_error: None,
+        }
+        .into())
+    }
+}
+
 fn get_http_response_body(response: HttpResponse) -> Result<String, ServiceError> {
     String::from_utf8(response.body).map_err(|e| {
         HttpOutcallError::InvalidHttpJsonRpcResponse {
@@ -140,36 +359,24 @@ pub fn get_http_response_status(status: candid::Nat) -> u16 {
     status.0.to_u16().unwrap_or(u16::MAX)
 }
 
-pub fn do_transform_request(mut args: TransformArgs) -> HttpResponse {
-    let body = canonicalize_json(&args.response.body).unwrap_or(args.response.body.clone());
-    args.response.body = body;
-    
-    // Remove potentially conflicting fields to reach a consensus across replicas
-    args.response.headers.clear();
-
-    args.response
-}
-
-pub fn do_transform_unisat_request(mut args: TransformArgs) -> HttpResponse {
-    // if args.response.status >= 300u64 {
-    //     // The error response might contain non-deterministic fields that make it impossible to reach consensus,
-    //     // such as timestamps:
-    //     // {"timestamp":"2023-03-01T20:35:49.416+00:00","status":403,"error":"Forbidden","message":"AccessDenied","path":"/api/kyt/v2/users/cktestbtc/transfers"}
-    //     args.response.body.clear()
-    // } else {
-        // The response body is expected to be JSON, so let's canonicalize it to remove non-deterministic fields
-        let body = canonicalize_json(&args.response.body).unwrap_or(args.response.body.clone());
-        let body_json: Value = serde_json::from_slice(&body).unwrap();
-
-        // Access the "amt" field in the "brc20" object
-        let pointer = "/data";
-        let syron_inscription: String = body_json.pointer(pointer)
-            .and_then(Value::as_str)
-            .unwrap_or("")
-            .to_string();
-
-        args.response.body = body;//syron_inscription.into();
-    // }
+/// Keeps only the response fields listed in the transform context (see `IndexerClient::response_fields`).
+pub fn transform_response(mut args: TransformArgs) -> HttpResponse {
+    if get_http_response_status(args.response.status.clone()) >= 300 {
+        // The error response might contain non-deterministic fields that make it impossible to reach consensus,
+        // such as timestamps:
+        // {"timestamp":"2023-03-01T20:35:49.416+00:00","status":403,"error":"Forbidden","message":"AccessDenied","path":"/api/kyt/v2/users/cktestbtc/transfers"}
+        args.response.body.clear();
+    } else {
+        let fields = serde_json::from_slice::<Vec<String>>(&args.context).unwrap_or_default();
+        let body = match serde_json::from_slice::<Value>(&args.response.body) {
+            Ok(json) if !fields.is_empty() => serde_json::to_vec(&project_json(&json, &fields)).ok(),
+            // @dev Without fields, the response is only canonicalized
+            _ => canonicalize_json(&args.response.body),
+        };
+        if let Some(body) = body {
+            args.response.body = body;
+        }
+    }
 
     // Remove potentially conflicting fields to reach a consensus across replicas
     args.response.headers.clear();
@@ -177,31 +384,57 @@ pub fn do_transform_unisat_request(mut args: TransformArgs) -> HttpResponse {
     args.response
 }
 
-pub fn do_transform_bis_request(mut args: TransformArgs) -> HttpResponse {
-    // if args.response.status >= 300u64 {
-    //     // The error response might contain non-deterministic fields that make it impossible to reach consensus,
-    //     // such as timestamps:
-    //     // {"timestamp":"2023-03-01T20:35:49.416+00:00","status":403,"error":"Forbidden","message":"AccessDenied","path":"/api/kyt/v2/users/cktestbtc/transfers"}
-    //     args.response.body.clear()
-    // } else {
-        // The response body is expected to be JSON, so let's canonicalize it to remove non-deterministic fields
-        let body = canonicalize_json(&args.response.body).unwrap_or(args.response.body.clone());
-        let body_json: Value = serde_json::from_slice(&body).unwrap();
-
-        // Access the "amt" field in the "brc20" object
-        let pointer = "/";
-        let syron_inscription: String = body_json.pointer(pointer)
-            .and_then(Value::as_str)
-            .unwrap_or("")
-            .to_string();
-
-        args.response.body = syron_inscription.into();
-    // }
+/// The transform context of the fields to keep.
+pub fn transform_context(fields: &[&str]) -> Vec<u8> {
+    serde_json::to_vec(fields).unwrap_or_default()
+}
 
-    // Remove potentially conflicting fields to reach a consensus across replicas
-    args.response.headers.clear();
+/// The JSON value reduced to the given pointers, keeping their paths.
+pub fn project_json(json: &Value, pointers: &[String]) -> Value {
+    let mut projected = Value::Object(Map::new());
+    for pointer in pointers {
+        let segments: Vec<String> = pointer
+            .split('/')
+            .skip(1)
+            .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+            .collect();
+        project(json, &segments, &mut projected);
+    }
+    projected
+}
 
-    args.response
+fn project(source: &Value, segments: &[String], target: &mut Value) {
+    let (segment, rest) = match segments.split_first() {
+        Some(split) => split,
+        None => {
+            *target = source.clone();
+            return;
+        }
+    };
+    match source {
+        Value::Array(items) if segment == "*" => {
+            if !matches!(target, Value::Array(projected) if projected.len() == items.len()) {
+                *target = Value::Array(vec![Value::Object(Map::new()); items.len()]);
+            }
+            if let Value::Array(projected) = target {
+                for (item, projected) in items.iter().zip(projected.iter_mut()) {
+                    project(item, rest, projected);
+                }
+            }
+        }
+        Value::Object(fields) => {
+            if let Some(child) = fields.get(segment) {
+                if !target.is_object() {
+                    *target = Value::Object(Map::new());
+                }
+                if let Value::Object(projected) = target {
+                    let entry = projected.entry(segment.clone()).or_insert(Value::Object(Map::new()));
+                    project(child, rest, entry);
+                }
+            }
+        }
+        _ => {}
+    }
 }
 
 pub fn canonicalize_json(text: &[u8]) -> Option<Vec<u8>> {
@@ -209,33 +442,18 @@ pub fn canonicalize_json(text: &[u8]) -> Option<Vec<u8>> {
     serde_json::to_vec(&json).ok()
 }
 
-pub async fn get_syron_balance(sdb: String) -> Option<u64> {
+pub async fn get_syron_balance(sdb: String) -> Result<u64, SyronError> {
     // @mainnet
-    let outcall = match call_indexer_balance(sdb.clone(), 0, 72_000_000).await {
-        Ok(result) => result,
-        Err(_err) => {
-            return None;
-        }
-    };
-    
-    let outcall_json: Value = serde_json::from_str(&outcall).unwrap();
-
-    // Access the "overallBalance" field
-    let user_balances = outcall_json.pointer("/detail").and_then(Value::as_array).expect("Expected user balance '/detail' to be an array");
-    let syron_balance = user_balances.iter()
-        .filter_map(|token| {
-            let ticker = token.pointer("/ticker").and_then(Value::as_str);
-            let balance = token.pointer("/overallBalance").and_then(Value::as_str);
-            match (ticker, balance) { //@mainnet
-                (Some("SYRON"), Some(balance)) => Some(balance.to_string()),
-                _ => None,
-            }
-        })
-        .next()
-        .unwrap_or("".to_string());
+    let balances = get_service_balance(sdb.clone(), ServiceProvider::Chain(BTC_MAINNET_CHAIN_ID), CyclesPayer::Canister).await?;
 
-    let syron_f64: f64 = syron_balance.parse().unwrap_or(0.0);
-    let syron_u64: u64 = (syron_f64 * 100_000_000 as f64) as u64;
+    let syron_balance = balances.iter()
+        .find(|balance| balance.ticker.eq_ignore_ascii_case(SYRON_TICKER)); //@mainnet
 
-    Some(syron_u64)
+    match syron_balance {
+        Some(balance) => parse_brc20_amount(&balance.overall_balance).map_err(|error| SyronError::InvalidAmount {
+            amt: balance.overall_balance.clone(),
+            error,
+        }),
+        None => Ok(0),
+    }
 }
@@ -0,0 +1,428 @@
+// @notice BRC-20 indexer clients
+// Each provider exposes the same data with a different URL and JSON shape,
+// so every adapter builds its own requests and normalizes its own responses.
+
+use serde_json::Value;
+
+use crate::inscription_cache::satpoint_outpoint;
+use crate::types::{Brc20Balance, IndexedUtxo, IndexerEndpoint, IndexerKind, InscriptionInfo, OutpointKey, Provider, UtxoAssets};
+use crate::{BIS_MAINNET_HOSTNAME, BIS_SIGNET_HOSTNAME, BIS_TESTNET_HOSTNAME, UNISAT_MAINNET_HOSTNAME, UNISAT_TESTNET_HOSTNAME};
+
+/// Decimals used by BestInSlot to encode BRC-20 amounts as integers.
+const BIS_AMOUNT_DECIMALS: usize = 18;
+
+pub trait IndexerClient {
+    /// Endpoint (relative to the provider's API URL) that returns the given inscription.
+    fn inscription_endpoint(&self, inscription_id: &str) -> String;
+
+    /// Endpoint (relative to the provider's API URL) that returns the BRC-20 balances of the given address.
+    fn balance_endpoint(&self, address: &str) -> String;
+
+    /// Endpoint (relative to the provider's API URL) that returns the UTXOs of the given address that carry
+    /// inscriptions or runes.
+    fn utxos_endpoint(&self, address: &str) -> String;
+
+    /// Endpoint that returns the current holder of the given inscription, for the indexers whose inscription
+    /// endpoint only reports the address that inscribed it.
+    fn holder_endpoint(&self, inscription_id: &str) -> Option<String>;
+
+    /// Name of the canister query used to transform this provider's responses.
+    fn transform_name(&self) -> Option<&'static str>;
+
+    /// JSON pointers of the response fields read by the parser; `*` matches every array element.
+    /// The transform keeps only these fields, so that volatile ones (timestamps, heights) are dropped.
+    fn response_fields(&self, endpoint: IndexerEndpoint) -> &'static [&'static str];
+
+    fn parse_inscription(&self, body: &str) -> Result<InscriptionInfo, String>;
+
+    fn parse_balances(&self, body: &str) -> Result<Vec<Brc20Balance>, String>;
+
+    fn parse_utxos(&self, body: &str) -> Result<Vec<IndexedUtxo>, String>;
+
+    /// The current holder of the inscription and its satpoint.
+    fn parse_holder(&self, body: &str) -> Result<(String, Option<String>), String>;
+}
+
+pub struct TyronIndexer;
+
+pub struct UnisatIndexer;
+
+pub struct BisIndexer;
+
+pub fn indexer_client(kind: IndexerKind) -> &'static dyn IndexerClient {
+    match kind {
+        IndexerKind::Tyron => &TyronIndexer,
+        IndexerKind::UniSat => &UnisatIndexer,
+        IndexerKind::BestInSlot => &BisIndexer,
+    }
+}
+
+/// The kind of the provider; the ones stored before the kind was recorded are matched by their default hostname.
+pub fn provider_kind(provider: &Provider) -> IndexerKind {
+    provider.kind.unwrap_or_else(|| match provider.hostname.as_str() {
+        UNISAT_MAINNET_HOSTNAME | UNISAT_TESTNET_HOSTNAME => IndexerKind::UniSat,
+        BIS_MAINNET_HOSTNAME | BIS_TESTNET_HOSTNAME | BIS_SIGNET_HOSTNAME => IndexerKind::BestInSlot,
+        _ => IndexerKind::Tyron,
+    })
+}
+
+pub fn provider_client(provider: &Provider) -> &'static dyn IndexerClient {
+    indexer_client(provider_kind(provider))
+}
+
+/// The id of the inscription revealed in the first output of the given transaction.
+pub fn inscription_id(txid: &str) -> String {
+    format!("{}i0", txid)
+}
+
+impl IndexerClient for TyronIndexer {
+    fn inscription_endpoint(&self, inscription_id: &str) -> String {
+        format!("get-unisat-inscription-info?id={}", inscription_id)
+    }
+
+    fn balance_endpoint(&self, address: &str) -> String {
+        format!("get-unisat-brc20-info?id={}", address)
+    }
+
+    fn utxos_endpoint(&self, address: &str) -> String {
+        format!("get-unisat-inscription-utxos?id={}", address)
+    }
+
+    fn holder_endpoint(&self, _inscription_id: &str) -> Option<String> {
+        None
+    }
+
+    fn transform_name(&self) -> Option<&'static str> {
+        Some("transform_request")
+    }
+
+    fn response_fields(&self, endpoint: IndexerEndpoint) -> &'static [&'static str] {
+        match endpoint {
+            IndexerEndpoint::Inscription => &["/utxo/address", "/brc20/tick", "/brc20/op", "/brc20/amt", "/location"],
+            IndexerEndpoint::Balance => &[
+                "/detail/*/ticker",
+                "/detail/*/overallBalance",
+                "/detail/*/availableBalance",
+                "/detail/*/transferableBalance",
+            ],
+            IndexerEndpoint::Utxos => &[
+                "/utxo/*/txid",
+                "/utxo/*/vout",
+                "/utxo/*/inscriptions/*/inscriptionId",
+                "/utxo/*/runes/*/runeid",
+            ],
+            IndexerEndpoint::Holder => &[],
+        }
+    }
+
+    // @dev The Tyron indexer returns the UniSat `data` object.
+    fn parse_inscription(&self, body: &str) -> Result<InscriptionInfo, String> {
+        parse_unisat_inscription(&parse_json(body)?)
+    }
+
+    fn parse_balances(&self, body: &str) -> Result<Vec<Brc20Balance>, String> {
+        parse_unisat_balances(&parse_json(body)?)
+    }
+
+    fn parse_utxos(&self, body: &str) -> Result<Vec<IndexedUtxo>, String> {
+        parse_unisat_utxos(&parse_json(body)?)
+    }
+
+    fn parse_holder(&self, _body: &str) -> Result<(String, Option<String>), String> {
+        Err("the inscription reports its holder".to_string())
+    }
+}
+
+impl IndexerClient for UnisatIndexer {
+    fn inscription_endpoint(&self, inscription_id: &str) -> String {
+        format!("v1/indexer/inscription/info/{}", inscription_id)
+    }
+
+    fn balance_endpoint(&self, address: &str) -> String {
+        format!("v1/indexer/address/{}/brc20/summary", address)
+    }
+
+    fn utxos_endpoint(&self, address: &str) -> String {
+        format!("v1/indexer/address/{}/inscription-utxo-data?cursor=0&size=1000", address)
+    }
+
+    fn holder_endpoint(&self, _inscription_id: &str) -> Option<String> {
+        None
+    }
+
+    fn transform_name(&self) -> Option<&'static str> {
+        Some("transform_request")
+    }
+
+    fn response_fields(&self, endpoint: IndexerEndpoint) -> &'static [&'static str] {
+        match endpoint {
+            IndexerEndpoint::Inscription => &[
+                "/code",
+                "/msg",
+                "/data/utxo/address",
+                "/data/brc20/tick",
+                "/data/brc20/op",
+                "/data/brc20/amt",
+                "/data/location",
+            ],
+            IndexerEndpoint::Balance => &[
+                "/code",
+                "/msg",
+                "/data/detail/*/ticker",
+                "/data/detail/*/overallBalance",
+                "/data/detail/*/availableBalance",
+                "/data/detail/*/transferableBalance",
+            ],
+            IndexerEndpoint::Utxos => &[
+                "/code",
+                "/msg",
+                "/data/utxo/*/txid",
+                "/data/utxo/*/vout",
+                "/data/utxo/*/inscriptions/*/inscriptionId",
+                "/data/utxo/*/runes/*/runeid",
+            ],
+            IndexerEndpoint::Holder => &[],
+        }
+    }
+
+    fn parse_inscription(&self, body: &str) -> Result<InscriptionInfo, String> {
+        parse_unisat_inscription(unisat_data(&parse_json(body)?)?)
+    }
+
+    fn parse_balances(&self, body: &str) -> Result<Vec<Brc20Balance>, String> {
+        parse_unisat_balances(unisat_data(&parse_json(body)?)?)
+    }
+
+    fn parse_utxos(&self, body: &str) -> Result<Vec<IndexedUtxo>, String> {
+        parse_unisat_utxos(unisat_data(&parse_json(body)?)?)
+    }
+
+    fn parse_holder(&self, _body: &str) -> Result<(String, Option<String>), String> {
+        Err("the inscription reports its holder".to_string())
+    }
+}
+
+impl IndexerClient for BisIndexer {
+    // @dev BIS reports the BRC-20 transfer as an event of the reveal transaction.
+    fn inscription_endpoint(&self, inscription_id: &str) -> String {
+        let txid = inscription_id.split('i').next().unwrap_or(inscription_id);
+        format!("brc20/event_from_txid?txid={}", txid)
+    }
+
+    fn balance_endpoint(&self, address: &str) -> String {
+        format!("brc20/wallet_balances?address={}", address)
+    }
+
+    // @dev BIS lists the inscriptions of the wallet with their satpoints; runes are only tagged by the local registry
+    fn utxos_endpoint(&self, address: &str) -> String {
+        format!("wallet/inscriptions?address={}&sort_by=inscr_num&order=asc&offset=0&count=2000", address)
+    }
+
+    // @dev The BRC-20 event only names the wallet that inscribed the transfer, not the one that holds it now
+    fn holder_endpoint(&self, inscription_id: &str) -> Option<String> {
+        Some(format!("inscription/single_info_id?inscription_id={}", inscription_id))
+    }
+
+    fn transform_name(&self) -> Option<&'static str> {
+        Some("transform_request")
+    }
+
+    fn response_fields(&self, endpoint: IndexerEndpoint) -> &'static [&'static str] {
+        match endpoint {
+            IndexerEndpoint::Inscription => &[
+                "/data/*/event_type",
+                "/data/*/event/tick",
+                "/data/*/event/amount",
+                "/data/*/satpoint",
+            ],
+            IndexerEndpoint::Balance => &["/data/*/ticker", "/data/*/overall_balance", "/data/*/available_balance"],
+            IndexerEndpoint::Utxos => &["/data/*/inscription_id", "/data/*/satpoint"],
+            IndexerEndpoint::Holder => &["/data/wallet", "/data/satpoint"],
+        }
+    }
+
+    fn parse_inscription(&self, body: &str) -> Result<InscriptionInfo, String> {
+        let json = parse_json(body)?;
+        let events = json
+            .pointer("/data")
+            .and_then(Value::as_array)
+            .ok_or("expected '/data' to be an array")?;
+        // @dev e.g. `transfer-inscribe` or `mint-inscribe`; a transfer inscription that was sent has a
+        // `transfer-transfer` event in the spending transaction, not in the reveal transaction
+        let (event, op) = events
+            .iter()
+            .find_map(|event| {
+                let op = event.pointer("/event_type").and_then(Value::as_str)?.strip_suffix("-inscribe")?;
+                Some((event, op))
+            })
+            .ok_or("no inscribe event found")?;
+
+        Ok(InscriptionInfo {
+            // @dev Set from the holder endpoint: the source wallet is the one that inscribed the transfer
+            owner_address: String::new(),
+            ticker: required_str(event, "/event/tick")?,
+            op: op.to_string(),
+            amt: scale_down(&required_str(event, "/event/amount")?, BIS_AMOUNT_DECIMALS)?,
+            satpoint: optional_str(event, "/satpoint"),
+        })
+    }
+
+    fn parse_balances(&self, body: &str) -> Result<Vec<Brc20Balance>, String> {
+        let json = parse_json(body)?;
+        let balances = json
+            .pointer("/data")
+            .and_then(Value::as_array)
+            .ok_or("expected '/data' to be an array")?;
+
+        balances
+            .iter()
+            .map(|balance| {
+                let overall = required_str(balance, "/overall_balance")?;
+                let available = required_str(balance, "/available_balance")?;
+                let transferable = overall
+                    .parse::<u128>()
+                    .ok()
+                    .zip(available.parse::<u128>().ok())
+                    .and_then(|(overall, available)| overall.checked_sub(available))
+                    .ok_or(format!("invalid balances ({}, {})", overall, available))?;
+
+                Ok(Brc20Balance {
+                    ticker: required_str(balance, "/ticker")?,
+                    overall_balance: scale_down(&overall, BIS_AMOUNT_DECIMALS)?,
+                    available_balance: scale_down(&available, BIS_AMOUNT_DECIMALS)?,
+                    transferable_balance: scale_down(&transferable.to_string(), BIS_AMOUNT_DECIMALS)?,
+                })
+            })
+            .collect()
+    }
+
+    fn parse_utxos(&self, body: &str) -> Result<Vec<IndexedUtxo>, String> {
+        let json = parse_json(body)?;
+        let inscriptions = json
+            .pointer("/data")
+            .and_then(Value::as_array)
+            .ok_or("expected '/data' to be an array")?;
+
+        // @dev A UTXO can carry several inscriptions
+        let mut utxos: Vec<IndexedUtxo> = vec![];
+        for inscription in inscriptions {
+            let satpoint = required_str(inscription, "/satpoint")?;
+            let (txid, vout) = satpoint_outpoint(&satpoint).ok_or(format!("invalid satpoint '{}'", satpoint))?;
+            let outpoint = OutpointKey::new(&txid, vout);
+            let inscription_id = required_str(inscription, "/inscription_id")?;
+            match utxos.iter_mut().find(|utxo| utxo.outpoint == outpoint) {
+                Some(utxo) => utxo.assets.inscription_ids.push(inscription_id),
+                None => utxos.push(IndexedUtxo {
+                    outpoint,
+                    assets: UtxoAssets {
+                        inscription_ids: vec![inscription_id],
+                        rune_ids: vec![],
+                    },
+                }),
+            }
+        }
+        Ok(utxos)
+    }
+
+    fn parse_holder(&self, body: &str) -> Result<(String, Option<String>), String> {
+        let json = parse_json(body)?;
+        Ok((required_str(&json, "/data/wallet")?, optional_str(&json, "/data/satpoint")))
+    }
+}
+
+fn parse_json(body: &str) -> Result<Value, String> {
+    serde_json::from_str(body).map_err(|err| format!("{}", err))
+}
+
+fn unisat_data(json: &Value) -> Result<&Value, String> {
+    json.pointer("/data").ok_or("missing '/data' object".to_string())
+}
+
+fn parse_unisat_inscription(data: &Value) -> Result<InscriptionInfo, String> {
+    Ok(InscriptionInfo {
+        owner_address: required_str(data, "/utxo/address")?,
+        ticker: required_str(data, "/brc20/tick")?,
+        op: required_str(data, "/brc20/op")?,
+        amt: required_str(data, "/brc20/amt")?,
+        satpoint: optional_str(data, "/location"),
+    })
+}
+
+fn parse_unisat_balances(data: &Value) -> Result<Vec<Brc20Balance>, String> {
+    let balances = data
+        .pointer("/detail")
+        .and_then(Value::as_array)
+        .ok_or("expected user balance '/detail' to be an array")?;
+
+    balances
+        .iter()
+        .map(|balance| {
+            Ok(Brc20Balance {
+                ticker: required_str(balance, "/ticker")?,
+                overall_balance: required_str(balance, "/overallBalance")?,
+                available_balance: required_str(balance, "/availableBalance")?,
+                transferable_balance: required_str(balance, "/transferableBalance")?,
+            })
+        })
+        .collect()
+}
+
+fn parse_unisat_utxos(data: &Value) -> Result<Vec<IndexedUtxo>, String> {
+    let utxos = data
+        .pointer("/utxo")
+        .and_then(Value::as_array)
+        .ok_or("expected '/utxo' to be an array")?;
+
+    utxos
+        .iter()
+        .map(|utxo| {
+            let vout = utxo
+                .pointer("/vout")
+                .and_then(Value::as_u64)
+                .and_then(|vout| u32::try_from(vout).ok())
+                .ok_or("missing '/vout' field")?;
+            Ok(IndexedUtxo {
+                outpoint: OutpointKey::new(&required_str(utxo, "/txid")?, vout),
+                assets: UtxoAssets {
+                    inscription_ids: strs(utxo, "/inscriptions", "/inscriptionId")?,
+                    rune_ids: strs(utxo, "/runes", "/runeid")?,
+                },
+            })
+        })
+        .collect()
+}
+
+/// The `field` of every element of the array at `pointer`, which may be missing.
+fn strs(json: &Value, pointer: &str, field: &str) -> Result<Vec<String>, String> {
+    match json.pointer(pointer) {
+        None | Some(Value::Null) => Ok(vec![]),
+        Some(Value::Array(items)) => items.iter().map(|item| required_str(item, field)).collect(),
+        Some(_) => Err(format!("expected '{}' to be an array", pointer)),
+    }
+}
+
+fn required_str(json: &Value, pointer: &str) -> Result<String, String> {
+    optional_str(json, pointer).ok_or(format!("missing '{}' field", pointer))
+}
+
+fn optional_str(json: &Value, pointer: &str) -> Option<String> {
+    json.pointer(pointer).and_then(Value::as_str).map(str::to_string)
+}
+
+/// Formats an integer amount with the given number of decimals, e.g. ("1500", 3) -> "1.5".
+fn scale_down(raw: &str, decimals: usize) -> Result<String, String> {
+    if raw.is_empty() || !raw.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(format!("invalid integer amount '{}'", raw));
+    }
+    let padded = format!("{:0>width$}", raw, width = decimals + 1);
+    let (integer, fraction) = padded.split_at(padded.len() - decimals);
+    let integer = integer.trim_start_matches('0');
+    let integer = if integer.is_empty() { "0" } else { integer };
+    let fraction = fraction.trim_end_matches('0');
+
+    if fraction.is_empty() {
+        Ok(integer.to_string())
+    } else {
+        Ok(format!("{}.{}", integer, fraction))
+    }
+}
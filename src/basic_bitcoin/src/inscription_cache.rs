@@ -0,0 +1,225 @@
+// @notice Cache of confirmed inscription results
+// Once the UTXO of a transfer inscription is confirmed, its owner and amount no longer change
+// until the UTXO is spent, so later verifications are served without an HTTPS outcall.
+// Only results that passed the inscription quorum are cached, and the cache is cleared when the quorum changes.
+// Entries are removed when the UTXO is spent, and the least recently used one is evicted when the cache is full.
+// Inscriptions that were already consumed are not cached: their UTXO is spent by the operation that consumed them,
+// so confirming it would cost a `bitcoin_get_utxos` call for an entry that is never read.
+
+use ic_canister_log::log;
+use ic_cdk::api::management_canister::bitcoin::{BitcoinNetwork, GetUtxosResponse};
+use ic_ckbtc_minter_tyron::logs::P1;
+use ic_stable_structures::{BoundedStorable, Storable};
+use std::future::Future;
+
+use crate::{
+    accounting::CyclesPayer,
+    bitcoin_api,
+    indexer::inscription_id,
+    provider::{INSCRIPTION_CACHE, INSCRIPTION_CACHE_LRU, PROVIDERS},
+    registry::get_consumed_inscription,
+    types::{CachedInscription, InscriptionCacheKey, InscriptionCacheUse, InscriptionInfo, ServiceProvider, SyronError},
+    BTC_MAINNET_CHAIN_ID, BTC_TESTNET_CHAIN_ID, INSCRIPTION_CACHE_MAX_ENTRIES, INSCRIPTION_CACHE_MIN_CONFIRMATIONS,
+};
+
+/// The chain whose UTXOs the canister can see through the bitcoin API.
+pub fn get_network_chain_id(network: BitcoinNetwork) -> Option<u64> {
+    match network {
+        BitcoinNetwork::Mainnet => Some(BTC_MAINNET_CHAIN_ID),
+        BitcoinNetwork::Testnet => Some(BTC_TESTNET_CHAIN_ID),
+        BitcoinNetwork::Regtest => None,
+    }
+}
+
+pub fn get_service_chain_id(service: &ServiceProvider) -> Option<u64> {
+    match service {
+        ServiceProvider::Chain(chain_id) => Some(*chain_id),
+        ServiceProvider::Provider(provider_id) => {
+            PROVIDERS.with(|providers| providers.borrow().get(provider_id).map(|provider| provider.chain_id))
+        }
+    }
+}
+
+pub fn get_cached_inscription(chain_id: u64, inscription_id: &str, now: u64) -> Option<InscriptionInfo> {
+    INSCRIPTION_CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        let key = InscriptionCacheKey::new(chain_id, inscription_id);
+        let mut entry = cache.get(&key)?;
+        reindex(&key, Some(entry.last_used_at), Some(now));
+        entry.last_used_at = now;
+        let inscription = entry.inscription.clone();
+        cache.insert(key, entry);
+        Some(inscription)
+    })
+}
+
+/// Moves the key in the LRU index from its previous use to the new one.
+fn reindex(key: &InscriptionCacheKey, previous: Option<u64>, last_used_at: Option<u64>) {
+    INSCRIPTION_CACHE_LRU.with(|lru| {
+        let mut lru = lru.borrow_mut();
+        if let Some(last_used_at) = previous {
+            lru.remove(&InscriptionCacheUse { last_used_at, key: key.clone() });
+        }
+        if let Some(last_used_at) = last_used_at {
+            lru.insert(InscriptionCacheUse { last_used_at, key: key.clone() }, ());
+        }
+    });
+}
+
+/// Removes the entry and its use from the LRU index.
+fn remove_entry(key: &InscriptionCacheKey) -> Option<CachedInscription> {
+    let entry = INSCRIPTION_CACHE.with(|cache| cache.borrow_mut().remove(key))?;
+    reindex(key, Some(entry.last_used_at), None);
+    Some(entry)
+}
+
+pub fn list_cached_inscriptions(offset: u64, limit: u64) -> Vec<(InscriptionCacheKey, CachedInscription)> {
+    INSCRIPTION_CACHE.with(|cache| {
+        cache
+            .borrow()
+            .iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect()
+    })
+}
+
+/// Caches the inscription confirmed at the given height, returning false if it is too large to be stored.
+pub fn cache_inscription(
+    chain_id: u64,
+    inscription_id: &str,
+    inscription: InscriptionInfo,
+    confirmed_height: u32,
+    now: u64
+) -> bool {
+    let key = InscriptionCacheKey::new(chain_id, inscription_id);
+    let entry = CachedInscription {
+        inscription,
+        confirmed_height,
+        confirmed_at: now,
+        last_used_at: now,
+    };
+    // @dev The indexer response is not trusted to fit in the stable map
+    if key.to_bytes().len() > InscriptionCacheKey::MAX_SIZE as usize
+        || entry.to_bytes().len() > CachedInscription::MAX_SIZE as usize
+    {
+        return false;
+    }
+
+    let is_full = INSCRIPTION_CACHE.with(|cache| {
+        let cache = cache.borrow();
+        !cache.contains_key(&key) && cache.len() >= INSCRIPTION_CACHE_MAX_ENTRIES
+    });
+    if is_full {
+        evict_least_recently_used();
+    }
+    let previous = INSCRIPTION_CACHE.with(|cache| cache.borrow_mut().insert(key.clone(), entry));
+    reindex(&key, previous.map(|previous| previous.last_used_at), Some(now));
+    true
+}
+
+pub fn evict_least_recently_used() -> Option<InscriptionCacheKey> {
+    let key = INSCRIPTION_CACHE_LRU
+        .with(|lru| lru.borrow().iter().next().map(|(used, _)| used.key))
+        // @dev Entries cached before the LRU index are evicted first
+        .or_else(|| INSCRIPTION_CACHE.with(|cache| cache.borrow().iter().next().map(|(key, _)| key)))?;
+    remove_entry(&key);
+    Some(key)
+}
+
+/// Removes every cached inscription, returning the number of entries removed.
+pub fn clear_inscription_cache() -> u64 {
+    let keys: Vec<InscriptionCacheKey> = INSCRIPTION_CACHE.with(|cache| cache.borrow().iter().map(|(key, _)| key).collect());
+    for key in &keys {
+        remove_entry(key);
+    }
+    keys.len() as u64
+}
+
+/// Removes the inscription from the cache of every chain, returning the number of entries removed.
+pub fn invalidate_inscription(inscription_id: &str) -> u64 {
+    let range = InscriptionCacheKey::new(u64::MIN, inscription_id)..=InscriptionCacheKey::new(u64::MAX, inscription_id);
+    let keys: Vec<InscriptionCacheKey> = INSCRIPTION_CACHE.with(|cache| cache.borrow().range(range).map(|(key, _)| key).collect());
+    for key in &keys {
+        remove_entry(key);
+    }
+    keys.len() as u64
+}
+
+/// Invalidates the inscription revealed by `txid` once its UTXO was spent by the canister or found spent.
+pub fn invalidate_spent<T>(txid: &str, result: &Result<T, SyronError>) {
+    if matches!(result, Ok(_) | Err(SyronError::UtxoNotFound { .. })) {
+        invalidate_inscription(&inscription_id(txid));
+    }
+}
+
+/// The txid and output index of a `txid:vout:offset` satpoint.
+pub fn satpoint_outpoint(satpoint: &str) -> Option<(String, u32)> {
+    let mut parts = satpoint.trim().split(':');
+    let txid = parts.next()?;
+    let vout = parts.next()?.parse().ok()?;
+    if txid.len() != 64 || !txid.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    Some((txid.to_ascii_lowercase(), vout))
+}
+
+/// The height of the UTXO at the outpoint if it is unspent with at least `min_confirmations`.
+pub fn get_confirmed_height(utxos: &GetUtxosResponse, txid: &str, vout: u32, min_confirmations: u32) -> Option<u32> {
+    let utxo = utxos.utxos.iter().find(|utxo| {
+        // @dev The bitcoin API returns the txid bytes in reverse order
+        let txid_bytes: Vec<u8> = utxo.outpoint.txid.iter().rev().copied().collect();
+        utxo.outpoint.vout == vout && hex::encode(txid_bytes) == txid
+    })?;
+    let confirmations = utxos.tip_height.checked_sub(utxo.height)? + 1;
+    (confirmations >= min_confirmations).then_some(utxo.height)
+}
+
+/// Returns the cached inscription of the service's chain, or fetches it with the quorum. The result is cached when the
+/// canister pays, the inscription is not consumed yet and its UTXO is confirmed on the canister's network, which costs
+/// one `bitcoin_get_utxos` call per uncached result. Mint, redemption and liquidation claim the inscription before
+/// verifying it, so they only read the cache: their own transfer spends the UTXO and evicts the entry right away.
+/// The cache is populated by `get_inscription` and `verifyTransferInscription`, e.g. when a wallet checks its transfer.
+pub async fn get_or_fetch_inscription<F>(
+    service: &ServiceProvider,
+    txid: &str,
+    payer: CyclesPayer,
+    fetch: F
+) -> Result<InscriptionInfo, SyronError>
+where
+    F: Future<Output = Result<InscriptionInfo, SyronError>>,
+{
+    let id = inscription_id(txid);
+    let chain_id = get_service_chain_id(service);
+    if let Some(inscription) = chain_id.and_then(|chain_id| get_cached_inscription(chain_id, &id, ic_cdk::api::time())) {
+        return Ok(inscription);
+    }
+
+    let inscription = fetch.await?;
+    let network = crate::NETWORK.with(|n| n.get());
+    match chain_id {
+        Some(chain_id)
+            if payer == CyclesPayer::Canister
+                && Some(chain_id) == get_network_chain_id(network)
+                && get_consumed_inscription(&id).is_none() =>
+        {
+            cache_if_confirmed(network, chain_id, &id, &inscription).await
+        }
+        _ => {}
+    }
+    Ok(inscription)
+}
+
+async fn cache_if_confirmed(network: BitcoinNetwork, chain_id: u64, inscription_id: &str, inscription: &InscriptionInfo) {
+    let Some((txid, vout)) = inscription.satpoint.as_deref().and_then(satpoint_outpoint) else {
+        return;
+    };
+    match bitcoin_api::try_get_utxos(network, inscription.owner_address.clone()).await {
+        Ok(utxos) => {
+            if let Some(height) = get_confirmed_height(&utxos, &txid, vout, INSCRIPTION_CACHE_MIN_CONFIRMATIONS) {
+                cache_inscription(chain_id, inscription_id, inscription.clone(), height, ic_cdk::api::time());
+            }
+        }
+        Err(reason) => log!(P1, "Failed to confirm inscription {}: {}", inscription_id, reason),
+    }
+}
@@ -0,0 +1,218 @@
+// @notice Operation journal
+// Mint, redemption and liquidation are multi-step: the transfer inscription is verified, a bitcoin transaction
+// is broadcast and then the Syron ledgers are updated. Each step is recorded in stable memory so that
+// the reconciler can finish (or compensate) operations interrupted by an error, a trap or an upgrade.
+// A liquidation broadcasts two transactions: the liquidator's payment, and then the debtor's collateral.
+
+use std::time::Duration;
+
+use ic_canister_log::log;
+use ic_ckbtc_minter_tyron::logs::P1;
+use ic_ckbtc_minter_tyron::updates::{
+    get_btc_address::{GetBoxAddressArgs, SyronOperation},
+    update_balance::{syron_update, update_ssi_balance},
+};
+
+use crate::{
+    provider::JOURNAL,
+    registry::{release_inscription, release_on_error},
+    send_collateral,
+    types::{InscriptionOperation, JournalEntry, JournalStep, LiquidationCollateral, SyronError},
+    JOURNAL_MAX_ATTEMPTS, JOURNAL_STALE_AFTER_NS, RECONCILE_INTERVAL_SECS,
+};
+
+pub fn open_entry(operation: InscriptionOperation, ssi: &str, txid: &str, now: u64) -> u64 {
+    JOURNAL.with(|journal| {
+        let mut journal = journal.borrow_mut();
+        // @dev Entries are never removed, so ids are sequential
+        let id = journal.len();
+        journal.insert(id, JournalEntry {
+            id,
+            operation,
+            ssi: ssi.to_string(),
+            txid: txid.to_string(),
+            step: JournalStep::Started,
+            btc_txid: None,
+            ledger_amount: 0,
+            attempts: 0,
+            last_error: None,
+            created_at: now,
+            updated_at: now,
+            collateral: None,
+        });
+        id
+    })
+}
+
+fn update_entry(id: u64, now: u64, update: impl FnOnce(&mut JournalEntry)) {
+    JOURNAL.with(|journal| {
+        let mut journal = journal.borrow_mut();
+        if let Some(mut entry) = journal.get(&id) {
+            update(&mut entry);
+            entry.updated_at = now;
+            journal.insert(id, entry);
+        }
+    });
+}
+
+pub fn record_step(id: u64, step: JournalStep, now: u64) {
+    update_entry(id, now, |entry| entry.step = step);
+}
+
+/// Records the broadcast transaction and the amount still to be debited from the ledger.
+pub fn record_broadcast(id: u64, btc_txid: &str, ledger_amount: u64, now: u64) {
+    update_entry(id, now, |entry| {
+        entry.step = JournalStep::TransactionBroadcast;
+        entry.btc_txid = Some(btc_txid.to_string());
+        entry.ledger_amount = ledger_amount;
+    });
+}
+
+/// Records the liquidator's payment and the collateral transfer that follows it.
+pub fn record_payment(id: u64, collateral: LiquidationCollateral, ledger_amount: u64, now: u64) {
+    update_entry(id, now, |entry| {
+        entry.step = JournalStep::PaymentBroadcast;
+        entry.collateral = Some(collateral);
+        entry.ledger_amount = ledger_amount;
+    });
+}
+
+pub fn record_error(id: u64, error: &SyronError, now: u64) {
+    update_entry(id, now, |entry| entry.last_error = Some(format!("{}", error)));
+}
+
+/// Closes the entry and releases its inscription if the operation failed before broadcasting its transaction.
+pub fn compensate_on_error<T>(id: u64, txid: &str, result: Result<T, SyronError>, now: u64) -> Result<T, SyronError> {
+    if let Err(err) = &result {
+        update_entry(id, now, |entry| {
+            entry.step = JournalStep::Compensated;
+            entry.last_error = Some(format!("{}", err));
+        });
+    }
+    release_on_error(txid, result)
+}
+
+pub fn get_journal_entry(id: u64) -> Option<JournalEntry> {
+    JOURNAL.with(|journal| journal.borrow().get(&id))
+}
+
+pub fn list_journal_entries(offset: u64, limit: u64) -> Vec<JournalEntry> {
+    JOURNAL.with(|journal| {
+        journal
+            .borrow()
+            .iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .map(|(_, entry)| entry)
+            .collect()
+    })
+}
+
+/// Incomplete entries that have not progressed since `JOURNAL_STALE_AFTER_NS`, so they are no longer in flight.
+pub fn stale_entries(now: u64) -> Vec<JournalEntry> {
+    JOURNAL.with(|journal| {
+        journal
+            .borrow()
+            .iter()
+            .map(|(_, entry)| entry)
+            .filter(|entry| !entry.step.is_final() && entry.updated_at.saturating_add(JOURNAL_STALE_AFTER_NS) <= now)
+            .collect()
+    })
+}
+
+/// Decides what the reconciler does with a stale entry.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Reconciliation {
+    // Nothing was signed yet: release the inscription.
+    Compensate,
+    // The liquidator paid and the collateral transfer failed: retry the collateral transfer.
+    TransferCollateral,
+    // The transaction was broadcast: retry the ledger update.
+    // A liquidation is not retried, its ledger update depends on the liquidator's arguments.
+    UpdateLedger,
+    // The transaction may or may not have been broadcast, or too many attempts failed.
+    GiveUp,
+}
+
+pub fn plan_reconciliation(entry: &JournalEntry) -> Reconciliation {
+    match entry.step {
+        JournalStep::Started => Reconciliation::Compensate,
+        // @dev Without a recorded failure the collateral transfer may have been broadcast before a trap
+        JournalStep::PaymentBroadcast
+            if entry.collateral.is_some() && entry.last_error.is_some() && entry.attempts < JOURNAL_MAX_ATTEMPTS =>
+        {
+            Reconciliation::TransferCollateral
+        }
+        JournalStep::TransactionBroadcast
+            if entry.operation != InscriptionOperation::Liquidation && entry.attempts < JOURNAL_MAX_ATTEMPTS =>
+        {
+            Reconciliation::UpdateLedger
+        }
+        _ => Reconciliation::GiveUp,
+    }
+}
+
+pub fn start_reconciler() {
+    ic_cdk_timers::set_timer_interval(Duration::from_secs(RECONCILE_INTERVAL_SECS), || {
+        ic_cdk::spawn(reconcile())
+    });
+}
+
+pub async fn reconcile() {
+    for entry in stale_entries(ic_cdk::api::time()) {
+        match plan_reconciliation(&entry) {
+            Reconciliation::Compensate => {
+                release_inscription(&entry.txid);
+                record_step(entry.id, JournalStep::Compensated, ic_cdk::api::time());
+            }
+            Reconciliation::TransferCollateral => {
+                // @dev Clear the error before awaiting so that an attempt interrupted by a trap is not retried
+                update_entry(entry.id, ic_cdk::api::time(), |entry| {
+                    entry.attempts += 1;
+                    entry.last_error = None;
+                });
+                match transfer_collateral(&entry).await {
+                    Ok(tx_id) => record_broadcast(entry.id, &tx_id, entry.ledger_amount, ic_cdk::api::time()),
+                    Err(err) => record_error(entry.id, &err, ic_cdk::api::time()),
+                }
+            }
+            Reconciliation::UpdateLedger => {
+                // @dev Mark the attempt before awaiting so that the next tick does not retry concurrently
+                update_entry(entry.id, ic_cdk::api::time(), |entry| entry.attempts += 1);
+                match update_ledger(&entry).await {
+                    Ok(()) => record_step(entry.id, JournalStep::LedgerUpdated, ic_cdk::api::time()),
+                    Err(err) => record_error(entry.id, &err, ic_cdk::api::time()),
+                }
+            }
+            Reconciliation::GiveUp => {
+                log!(P1, "Journal entry {} needs manual book-keeping: {:?}", entry.id, entry);
+                record_step(entry.id, JournalStep::Failed, ic_cdk::api::time());
+            }
+        }
+    }
+}
+
+async fn transfer_collateral(entry: &JournalEntry) -> Result<String, SyronError> {
+    let collateral = entry.collateral.as_ref().ok_or(SyronError::InvalidOperation)?;
+    send_collateral(&entry.ssi, collateral).await
+}
+
+/// The ledger update that follows the broadcast transaction of the entry.
+async fn update_ledger(entry: &JournalEntry) -> Result<(), SyronError> {
+    match entry.operation {
+        // @doc 2 is the nonce of the balance subaccount, and 3 the BRC-20 subaccount.
+        InscriptionOperation::Mint => syron_update(&entry.ssi, 2, 3, entry.ledger_amount)
+            .await
+            .map(|_| ())
+            .map_err(SyronError::from),
+        InscriptionOperation::Redemption => update_ssi_balance(GetBoxAddressArgs {
+            ssi: entry.ssi.clone(),
+            op: SyronOperation::RedeemBitcoin,
+        })
+        .await
+        .map(|_| ())
+        .map_err(SyronError::from),
+        // @dev Never planned, see `plan_reconciliation`
+        InscriptionOperation::Liquidation => Err(SyronError::InvalidOperation),
+    }
+}
@@ -1,16 +1,41 @@
+mod accounting;
+mod amount;
+mod auth;
+mod bip322;
 mod bitcoin_api;
 mod bitcoin_wallet;
+mod classifier;
+mod coin_selection;
 mod ecdsa_api;
+mod envelope;
+mod fee;
+mod guard;
+mod health;
+mod proof;
 mod constants;
+mod credentials;
 mod types;
 mod provider;
 mod http;
+mod indexer;
+mod inscription_cache;
+mod journal;
+mod quorum;
+mod registry;
+mod response_size;
+mod siwb;
+mod spv;
+mod validate;
+mod vsize;
 mod tests;
 
 pub use crate::constants::*;
 pub use crate::types::*;
 pub use crate::provider::*;
 pub use crate::http::*;
+pub use crate::journal::*;
+pub use crate::quorum::*;
+pub use crate::registry::*;
 
 use bitcoin::Network;
 use bitcoin::OutPoint;
@@ -18,7 +43,7 @@ use candid::Principal;
 use ic_cdk::api::management_canister::http_request::HttpResponse;
 use ic_cdk::api::management_canister::http_request::TransformArgs;
 use ic_cdk::{api::management_canister::bitcoin::{
-    BitcoinNetwork, GetUtxosResponse, MillisatoshiPerByte,
+    BitcoinNetwork, GetUtxosResponse, MillisatoshiPerByte, Utxo,
 }, query};
 use ic_cdk_macros::{init, post_upgrade, pre_upgrade, update};
 use ic_ckbtc_minter_tyron::address::get_ssi_derivation_path;
@@ -30,7 +55,6 @@ use ic_ckbtc_minter_tyron::updates::update_balance::syron_payment;
 use ic_ckbtc_minter_tyron::updates::update_balance::syron_update;
 use ic_ckbtc_minter_tyron::updates::update_balance::CollateralizedAccount;
 use icrc_ledger_types::icrc1::account::Account;
-use serde_json::Value;
 use std::cell::{Cell, RefCell};
 
 use ic_ckbtc_minter_tyron::{
@@ -42,7 +66,7 @@ use ic_ckbtc_minter_tyron::{
     storage::record_event,
     tasks::{schedule_now, TaskType},
     updates::{
-        self, get_btc_address::{self, GetBoxAddressArgs, SyronOperation}, get_withdrawal_account::compute_subaccount, update_balance::{UpdateBalanceError, UtxoStatus, get_collateralized_account}
+        self, get_btc_address::{self, GetBoxAddressArgs, SyronOperation}, get_withdrawal_account::compute_subaccount, update_balance::{UtxoStatus, get_collateralized_account}
     },
     MinterInfo
 };
@@ -50,6 +74,12 @@ use ic_ckbtc_minter_tyron::{
 use icrc_ledger_types::icrc1::account::Subaccount;
 
 use candid::candid_method;
+use ic_canister_log::log;
+use ic_ckbtc_minter_tyron::logs::P1;
+use auth::{do_authorize, do_deauthorize, is_manager, require_manage_or_controller, require_register_provider};
+use accounting::{get_rpc_payer, CyclesPayer};
+use guard::OperationGuard;
+use proof::{authorize, get_proof_threshold, Authorization};
 
 struct TransferResult {
     tx_id: String,
@@ -58,81 +88,64 @@ struct TransferResult {
 
 async fn syron_transfer(
     txid: String,
-    provider: u64,
-    cycles_cost: u128,
+    service: ServiceProvider,
     key_name: String,
     origin_derivation_path: Vec<Vec<u8>>,
     origin_address: String,
     dst_address: &str,
     requested_amt: u64,
-    fee: u64
-) -> Result<TransferResult, UpdateBalanceError> {
+    fee: u64,
+    journal_id: Option<u64>
+) -> Result<TransferResult, SyronError> {
     // @dev Check BRC-20 transfer inscription.
-    let outcall = call_indexer_inscription(provider, txid.clone(), cycles_cost).await?;
-
-    let outcall_json: Value = serde_json::from_str(&outcall).unwrap();
-
-    // Access the "amt" field in the "brc20" object
-    let receiver_address: String = outcall_json.pointer("/utxo/address")
-        .and_then(Value::as_str)
-        .unwrap_or("")
-        .to_string();
-
-    if receiver_address != origin_address {
-        return Err(UpdateBalanceError::GenericError{
-            error_code: 303,
-            error_message: format!("The inscription receiver address ({}) must be equal to the origin of the transfer ({})", receiver_address, origin_address),
+    let inscription = verify_inscription(service, txid.clone(), CyclesPayer::Canister).await?;
+    check_syron_transfer(&inscription)?;
+
+    // @dev The inscription receiver address must be equal to the origin of the transfer
+    if inscription.owner_address != origin_address {
+        return Err(SyronError::InscriptionMismatch {
+            expected: origin_address,
+            found: inscription.owner_address,
         });
     }
 
-    // Access the "amt" field in the "brc20" object
-    let syron_inscription: String = outcall_json.pointer("/brc20/amt")
-        .and_then(Value::as_str)
-        .unwrap_or("")
-        .to_string();
-
-    // The Syron inscribed amount must be less than the limit or throw UpdateBalanceError::GenericError
-    let syron_f64: f64 = syron_inscription.parse().unwrap_or(0.0);
-    let syron_u64: u64 = (syron_f64 * 100_000_000 as f64) as u64;
+    // The Syron inscribed amount cannot exceed the requested amount or throw SyronError::InscribedAmountOutOfRange
+    let syron_u64 = parse_inscribed_amount(&inscription.amt)?;
 
     if syron_u64 > requested_amt {
-        return Err(UpdateBalanceError::GenericError{
-            error_code: 304,
-            error_message: format!("The inscribed amount ({}) cannot exceed the withdrawal amount you requested ({}).", syron_f64, requested_amt/100_000_000),
+        return Err(SyronError::InscribedAmountOutOfRange {
+            inscribed: syron_u64,
+            min: 0,
+            max: requested_amt,
         });
     }
 
+    if let Some(id) = journal_id {
+        record_step(id, JournalStep::InscriptionVerified, ic_cdk::api::time());
+    }
+
     // @dev Send SYRON to the destination address
 
     let btc_network = NETWORK.with(|n| n.get());
 
-    // Get fee percentiles from previous transactions to estimate our own fee.
-    let fee_percentiles = bitcoin_api::get_current_fee_percentiles(btc_network).await;
-
-    // @dev Gas in satoshis per byte 
-    let  fee_per_byte_ = if fee_percentiles.is_empty() {
-        // There are no fee percentiles. This case can only happen on a regtest
-        // network where there are no non-coinbase transactions. In this case,
-        // we use a default of 5000 millisatoshis/byte (i.e. 5 satoshi/byte)
-        5000
-    } else {
-        // Choose the 50th percentile for sending fees.
-        fee_percentiles[50]
-    };
+    // @dev Gas in millisatoshis per vbyte, 5 satoshis per vbyte on regtest
+    let fee_per_byte_ = bitcoin_wallet::get_fee_per_vbyte(btc_network, 5000).await;
     
     // @dev Calculate fee_per_byte as the higher value between fee and fee_per_byte_
     let fee_per_byte = std::cmp::max(fee, fee_per_byte_);
 
-    let tx_id = bitcoin_wallet::syron_p2wpkh(
+    let transfer = bitcoin_wallet::syron_p2wpkh(
         btc_network,
         key_name,
         origin_derivation_path,
         origin_address,
         &dst_address,
-        txid,
+        txid.clone(),
         fee_per_byte
     )
-    .await?;
+    .await;
+    inscription_cache::invalidate_spent(&txid, &transfer);
+    let tx_id = transfer?;
 
     Ok(TransferResult{
         tx_id,
@@ -141,36 +154,62 @@ async fn syron_transfer(
 }
 
 /// Mint SYRON USD using P2WPKH - the transaction id must correspond to the required transfer inscription
-pub async fn mint(ssi: String, txid: String, cycles_cost: u128, provider: u64, amount: u64, fee: u64) -> Result<String, UpdateBalanceError> {
-    
+pub async fn mint(ssi: String, txid: String, provider: u64, amount: u64, fee: u64) -> Result<String, SyronError> {
+    // @dev Claim the transfer inscription before any await so that it cannot be honored twice
+    claim_inscription(&txid, InscriptionOperation::Mint, &ssi, amount, ic_cdk::api::time())?;
+    let journal_id = open_entry(InscriptionOperation::Mint, &ssi, &txid, ic_cdk::api::time());
+
+    // @dev The claim is released if the inscription is not transferred
+    let withdrawal = withdraw_inscription(&ssi, txid.clone(), provider, amount, fee, journal_id).await;
+    let (balance, transfer) = compensate_on_error(journal_id, &txid, withdrawal, ic_cdk::api::time())?;
+    settle_inscription(&txid, transfer.inscribed_amt);
+
+    // Update Syron USD Ledger
+    // @dev Compute the new balance amount as the current balance less the SYRON inscription
+    let new_balance = balance.checked_sub(transfer.inscribed_amt).unwrap_or(0);
+
+    // do not consider any new balance below 2 cents @review amt
+    // withdraw full balance @doc 2 is the nonce of the balance subaccount, and 3 the BRC-20 subaccount.
+    let debit = if new_balance < 2_000_000 { balance } else { transfer.inscribed_amt };
+    record_broadcast(journal_id, &transfer.tx_id, debit, ic_cdk::api::time());
+
+    match syron_update(&ssi, 2, 3, debit).await {
+        Ok(_) => {
+            println!("Successful withdrawal of the following balance: {:?}", debit);
+            record_step(journal_id, JournalStep::LedgerUpdated, ic_cdk::api::time());
+            Ok(transfer.tx_id)
+        }
+        Err(err) => {
+            // @dev The journal entry stays at TransactionBroadcast so the reconciler retries the ledger update
+            let err = SyronError::from(err);
+            record_error(journal_id, &err, ic_cdk::api::time());
+            Err(err)
+        }
+    }
+}
+
+/// Checks the SYRON balance and sends the transfer inscription to the user's wallet, returning the balance read.
+async fn withdraw_inscription(ssi: &str, txid: String, provider: u64, amount: u64, fee: u64, journal_id: u64) -> Result<(u64, TransferResult), SyronError> {
     // @dev Read SYRON available balance (nonce #2)
-    let balance = balance_of(SyronLedger::SUSD, &ssi, 2).await.unwrap();
+    let balance = read_balance(SyronLedger::SUSD, ssi, 2).await?;
     
     // amount cannot be higher than the balance
     if amount > balance {
-        return Err(UpdateBalanceError::GenericError{
-            error_code: 301,
-            error_message: "Insufficient balance".to_string(),
+        return Err(SyronError::InsufficientBalance {
+            available: balance,
+            requested: amount,
         });
     }
     
     // amount cannot be lower than 20 cents
-    if amount < 20_000_000 {
-        return Err(UpdateBalanceError::GenericError{
-            error_code: 301,
-            error_message: "Amount is below the minimum".to_string(),
+    if amount < MIN_WITHDRAWAL_AMOUNT {
+        return Err(SyronError::AmountBelowMinimum {
+            minimum: MIN_WITHDRAWAL_AMOUNT,
+            requested: amount,
         });
     }
     
-    let key_name = KEY_NAME.with(|kn| kn.borrow().to_string());
-
-    // if empty, throw error
-    if key_name.is_empty() {
-        return Err(UpdateBalanceError::GenericError{
-            error_code: 302,
-            error_message: "Key name is empty".to_string(),
-        });
-    }
+    let key_name = get_key_name()?;
     
     // @dev Get Syron Bitcoin address (The receiver of this transfer inscription must be equal to the Syron address)
     let minter_derivation_path = DERIVATION_PATH.with(|d| d.clone());
@@ -183,50 +222,41 @@ pub async fn mint(ssi: String, txid: String, cycles_cost: u128, provider: u64, a
     // @dev Send SUSD to the user's wallet (SSI)
     let transfer = syron_transfer(
         txid,
-        provider,
-        cycles_cost,
+        ServiceProvider::Provider(provider),
         key_name,
         minter_derivation_path,
         syron_address,
-        &ssi,
+        ssi,
         amount,
-        fee
-    ).await;
+        fee,
+        Some(journal_id)
+    ).await?;
 
-    match transfer {
-        Ok(transfer) => {
-            // Update Syron USD Ledger
-            // @dev Compute the new balance amount as the current balance less the SYRON inscription
-            let new_balance = balance.checked_sub(transfer.inscribed_amt).unwrap_or(0);
-
-            // do not consider any new balance below 2 cents @review amt
-            if new_balance < 2_000_000 {
-                // withdraw full balance @doc 2 is the nonce of the balance subaccount, and 3 the BRC-20 subaccount.
-                match syron_update(&ssi, 2, 3, balance).await {
-                    Ok(_) => {
-                        println!("Successful withdrawal of the full balance: {:?}", balance);
-                        return Ok(transfer.tx_id);
-                    }
-                    Err(err) => {
-                        println!("Double spending risk warning: {:?}", err);
-                        return Err(err) // @review save data in records to run book-keeping task by the system again
-                    }
-                }
-            } else {
-                match syron_update(&ssi, 2, 3, transfer.inscribed_amt).await {
-                    Ok(_) => {
-                        println!("Successful withdrawal of the following balance: {:?}", transfer.inscribed_amt);
-                        return Ok(transfer.tx_id);
-                    }
-                    Err(err) => {
-                        println!("Double spending risk warning: {:?}", err);
-                        return Err(err) // @review save data in records to run book-keeping task by the system again
-                    }
-                }
-            }
-        }
-        Err(err) => return Err(err) 
+    Ok((balance, transfer))
+}
+
+fn parse_inscribed_amount(amt: &str) -> Result<u64, SyronError> {
+    amount::parse_brc20_amount(amt).map_err(|error| SyronError::InvalidAmount {
+        amt: amt.to_string(),
+        error,
+    })
+}
+
+/// Reads the balance of the given SSI subaccount in the Syron ledgers.
+async fn read_balance(ledger: SyronLedger, ssi: &str, nonce: u64) -> Result<u64, SyronError> {
+    balance_of(ledger, ssi, nonce).await.map_err(|err| SyronError::LedgerUnavailable {
+        reason: format!("{:?}", err),
+    })
+}
+
+fn get_key_name() -> Result<String, SyronError> {
+    let key_name = KEY_NAME.with(|kn| kn.borrow().to_string());
+
+    // if empty, throw error
+    if key_name.is_empty() {
+        return Err(SyronError::KeyNameEmpty);
     }
+    Ok(key_name)
 }
 
 fn check_postcondition<T>(t: T) -> T {
@@ -284,6 +314,9 @@ pub fn init(network: BitcoinNetwork, args: MinterArg) {
         }
     }
 
+    // @dev Timers do not survive upgrades; post_upgrade starts the reconciler again through init
+    start_reconciler();
+
     init_service_provider()
 }
 
@@ -299,6 +332,17 @@ fn post_upgrade(minter_arg: MinterArg) {
         .expect("Failed to read network from stable memory.")
         .0;
 
+    let migrated = credentials::migrate_credentials();
+    if migrated > 0 {
+        log!(P1, "[credentials] Moved the credentials of {} providers to the credential store", migrated);
+    }
+
+    // @dev Before `init` adds the missing default providers
+    let migrated = provider::migrate_provider_hostnames();
+    if migrated > 0 {
+        log!(P1, "[providers] Trimmed the hostnames of {} providers", migrated);
+    }
+
     //@review 
     init(network, minter_arg);
 }
@@ -357,13 +401,113 @@ fn get_minter_info() -> MinterInfo {
     })
 }
 
-#[update(name = "addServiceProvider")]// @review (mainnet),, guard = "require_add_provider")]
+#[update(name = "addServiceProvider", guard = "require_register_provider")]
 #[candid_method(rename = "addServiceProvider")]
-fn add_service_provider(args: RegisterProviderArgs) -> u64 {
+fn add_service_provider(args: RegisterProviderArgs) -> Result<u64, ValidationError> {
     register_provider(args)
 }
 
-#[query(name = "getServiceProviderMap")]// @review (mainnet), guard = "require_manage_or_controller")]
+#[update(name = "setAllowedHostnames", guard = "require_manage_or_controller")]
+#[candid_method(rename = "setAllowedHostnames")]
+fn set_provider_hostnames(hostnames: Option<Vec<String>>) -> Result<(), ValidationError> {
+    log!(P1, "[{}] Setting allowed provider hostnames: {:?}", ic_cdk::caller(), hostnames);
+    validate::set_allowed_hostnames(hostnames)
+}
+
+#[query(name = "getAllowedHostnames")]
+#[candid_method(query, rename = "getAllowedHostnames")]
+fn get_provider_hostnames() -> Vec<String> {
+    validate::get_allowed_hostnames()
+}
+
+#[query(name = "getProviders")]
+#[candid_method(query, rename = "getProviders")]
+fn get_service_providers() -> Vec<ProviderView> {
+    get_providers()
+}
+
+#[update(name = "updateProvider")]
+#[candid_method(rename = "updateProvider")]
+fn update_service_provider(args: UpdateProviderArgs) -> Result<(), ProviderError> {
+    let caller = ic_cdk::caller();
+    if let Some(headers) = &args.credential_headers {
+        let names: Vec<&str> = headers.iter().map(|header| header.name.as_str()).collect();
+        log!(P1, "[credentials] {} sets provider {} credentials: {:?}", caller, args.provider_id, names);
+    }
+    log!(P1, "[{}] Updating provider: {}", caller, args.provider_id);
+    update_provider(caller, is_manager(&caller), args)
+}
+
+#[update(name = "unregisterProvider")]
+#[candid_method(rename = "unregisterProvider")]
+fn unregister_service_provider(provider_id: u64) -> Result<(), ProviderError> {
+    let caller = ic_cdk::caller();
+    log!(P1, "[{}] Unregistering provider: {}", caller, provider_id);
+    unregister_provider(caller, is_manager(&caller), provider_id)
+}
+
+#[update(name = "setPrimaryProvider", guard = "require_manage_or_controller")]
+#[candid_method(rename = "setPrimaryProvider")]
+fn set_primary_service_provider(provider_id: u64) -> Result<(), ProviderError> {
+    log!(P1, "[{}] Setting primary provider: {}", ic_cdk::caller(), provider_id);
+    set_primary_provider(provider_id)
+}
+
+#[update(name = "manageProvider", guard = "require_manage_or_controller")]
+#[candid_method(rename = "manageProvider")]
+fn manage_service_provider(args: ManageProviderArgs) -> Result<(), ProviderError> {
+    log!(P1, "[{}] Managing provider: {} (service: {:?})", ic_cdk::caller(), args.provider_id, args.service);
+    manage_provider(args)
+}
+
+#[update(name = "withdrawAccumulatedCycles")]
+#[candid_method(rename = "withdrawAccumulatedCycles")]
+async fn withdraw_accumulated_cycles(provider_id: u64, canister_id: Principal) -> Result<u128, ProviderError> {
+    let caller = ic_cdk::caller();
+    let withdrawal = accounting::withdraw_cycles_owed(caller, provider_id, canister_id).await;
+    log!(P1, "[{}] Withdrawing the cycles owed to provider {} to {}: {:?}", caller, provider_id, canister_id, withdrawal);
+    withdrawal
+}
+
+#[query(name = "getProviderHealth")]
+#[candid_method(query, rename = "getProviderHealth")]
+fn get_provider_health() -> Vec<(u64, ProviderHealth)> {
+    health::list_provider_health()
+}
+
+#[update(name = "setMaxResponseBytesCap", guard = "require_manage_or_controller")]
+#[candid_method(rename = "setMaxResponseBytesCap")]
+fn set_max_response_bytes_cap(cap: Option<u64>) -> Result<(), SyronError> {
+    response_size::set_max_response_bytes_cap(cap)
+}
+
+#[query(name = "getResponseSizes")]
+#[candid_method(query, rename = "getResponseSizes")]
+fn get_response_sizes() -> Vec<(u64, IndexerEndpoint, ResponseSizeStats)> {
+    response_size::list_response_sizes()
+}
+
+#[query(name = "getAuthorized", guard = "require_manage_or_controller")]
+#[candid_method(query, rename = "getAuthorized")]
+fn get_authorized(auth: Auth) -> Vec<Principal> {
+    auth::get_authorized(auth)
+}
+
+#[update(name = "authorize", guard = "require_manage_or_controller")]
+#[candid_method(rename = "authorize")]
+fn authorize_principal(principal: Principal, auth: Auth) -> bool {
+    log!(P1, "[auth] {} authorizes {} for {:?}", ic_cdk::caller(), principal, auth);
+    do_authorize(principal, auth)
+}
+
+#[update(name = "deauthorize", guard = "require_manage_or_controller")]
+#[candid_method(rename = "deauthorize")]
+fn deauthorize_principal(principal: Principal, auth: Auth) -> bool {
+    log!(P1, "[auth] {} deauthorizes {} for {:?}", ic_cdk::caller(), principal, auth);
+    do_deauthorize(principal, auth)
+}
+
+#[query(name = "getServiceProviderMap", guard = "require_manage_or_controller")]
 #[candid_method(query, rename = "getServiceProviderMap")]
 fn get_service_provider_map() -> Vec<(ServiceProvider, u64)> {
     SERVICE_PROVIDER_MAP.with(|map| {
@@ -374,122 +518,250 @@ fn get_service_provider_map() -> Vec<(ServiceProvider, u64)> {
     })
 }
 
+#[update(name = "setInscriptionQuorum", guard = "require_manage_or_controller")]
+#[candid_method(rename = "setInscriptionQuorum")]
+fn set_inscription_quorum(quorum: Option<QuorumConfig>) -> Result<(), SyronError> {
+    set_quorum_config(quorum)
+}
+
+#[update(name = "setSignatureThreshold", guard = "require_manage_or_controller")]
+#[candid_method(rename = "setSignatureThreshold")]
+fn set_signature_threshold(threshold: Option<u64>) {
+    proof::set_proof_threshold(threshold)
+}
+
+#[query(name = "getSignatureThreshold")]
+#[candid_method(query, rename = "getSignatureThreshold")]
+fn get_signature_threshold() -> Option<u64> {
+    get_proof_threshold()
+}
+
+#[query(name = "getOperationMessage")]
+#[candid_method(query, rename = "getOperationMessage")]
+fn get_operation_message(operation: String, ssi: String, target: Option<String>, max_amount: u64, nonce: u64, expiry: u64) -> String {
+    proof::operation_message(&operation, &ssi, target.as_deref(), max_amount, nonce, expiry)
+}
+
+#[query(name = "getLastProofNonce")]
+#[candid_method(query, rename = "getLastProofNonce")]
+fn get_last_proof_nonce(ssi: String) -> Option<u64> {
+    proof::get_last_nonce(&ssi)
+}
+
+#[query(name = "getInscriptionQuorum")]
+#[candid_method(query, rename = "getInscriptionQuorum")]
+fn get_inscription_quorum() -> Option<QuorumConfig> {
+    get_quorum_config()
+}
+
+#[query(name = "getJournalEntry")]
+#[candid_method(query, rename = "getJournalEntry")]
+fn get_journal_entry_by_id(id: u64) -> Option<JournalEntry> {
+    get_journal_entry(id)
+}
+
+#[query(name = "getJournalEntries")]
+#[candid_method(query, rename = "getJournalEntries")]
+fn get_journal_entries(offset: u64, limit: u64) -> Vec<JournalEntry> {
+    list_journal_entries(offset, limit)
+}
+
+#[query(name = "getConsumedInscription")]
+#[candid_method(query, rename = "getConsumedInscription")]
+fn get_consumed_inscription_by_id(inscription_id: String) -> Option<ConsumedInscription> {
+    get_consumed_inscription(&inscription_id)
+}
+
+#[query(name = "getConsumedInscriptions")]
+#[candid_method(query, rename = "getConsumedInscriptions")]
+fn get_consumed_inscriptions(offset: u64, limit: u64) -> Vec<(String, ConsumedInscription)> {
+    list_consumed_inscriptions(offset, limit)
+}
+
+#[query(name = "getCachedInscriptions")]
+#[candid_method(query, rename = "getCachedInscriptions")]
+fn get_cached_inscriptions(offset: u64, limit: u64) -> Vec<(InscriptionCacheKey, CachedInscription)> {
+    inscription_cache::list_cached_inscriptions(offset, limit)
+}
+
+#[update(name = "invalidateCachedInscription", guard = "require_manage_or_controller")]
+#[candid_method(rename = "invalidateCachedInscription")]
+fn invalidate_cached_inscription(inscription_id: String) -> u64 {
+    log!(P1, "[cache] {} invalidates inscription {}", ic_cdk::caller(), inscription_id);
+    inscription_cache::invalidate_inscription(&inscription_id)
+}
+
+#[update(name = "tagUtxo", guard = "require_manage_or_controller")]
+#[candid_method(rename = "tagUtxo")]
+fn tag_utxo(txid: String, vout: u32, assets: UtxoAssets) -> bool {
+    log!(P1, "[utxos] {} tags {}:{} with {:?}", ic_cdk::caller(), txid, vout, assets);
+    classifier::tag_utxo(OutpointKey::new(&txid, vout), assets)
+}
+
+#[query(name = "getUtxoTags")]
+#[candid_method(query, rename = "getUtxoTags")]
+fn get_utxo_tags(offset: u64, limit: u64) -> Vec<(OutpointKey, UtxoAssets)> {
+    classifier::list_utxo_tags(offset, limit)
+}
+
+// @dev The canister pays the indexer call
+#[update(name = "getClassifiedUtxos", guard = "require_manage_or_controller")]
+#[candid_method(rename = "getClassifiedUtxos")]
+async fn get_classified_utxos(address: String) -> Result<Vec<(Utxo, UtxoClass)>, SyronError> {
+    let network = NETWORK.with(|n| n.get());
+    let utxos = classifier::get_classified_utxos(network, &address).await?;
+    Ok(utxos.0.into_iter().map(|classified| (classified.utxo, classified.class)).collect())
+}
+
 #[update]
-pub async fn get_inscription(txid: String, cycles_cost: u64, provider: u64) -> Result<String, UpdateBalanceError> {
-    call_indexer_inscription(provider, txid.clone(), cycles_cost as u128).await
+pub async fn get_inscription(txid: String, provider: u64) -> Result<InscriptionInfo, SyronError> {
+    // @dev Verified like the funds-moving endpoints, so that a quorum-verified result warms their cache
+    verify_inscription(ServiceProvider::Provider(provider), txid, get_rpc_payer(&ic_cdk::caller())).await
 }
 
 #[update]
-pub async fn get_indexed_balance(id: String) -> Result<String, UpdateBalanceError> {
-    call_indexer_balance(id, 0, 72_000_000).await //@mainnet
+pub async fn get_indexed_balance(id: String) -> Result<Vec<Brc20Balance>, SyronError> {
+    get_service_balance(id, ServiceProvider::Chain(BTC_MAINNET_CHAIN_ID), get_rpc_payer(&ic_cdk::caller())).await //@mainnet
 }
 
+/// Transforms the responses of every indexer; the fields to keep are given by the transform context.
 #[query(hidden = true)]
 fn transform_request(args: TransformArgs) -> HttpResponse {
-    do_transform_request(args)
+    transform_response(args)
 }
 
+// @dev Compatibility aliases of `transform_request`, for the outcalls that still name the former per-indexer transforms
 #[query(hidden = true)]
 fn transform_unisat_request(args: TransformArgs) -> HttpResponse {
-    do_transform_unisat_request(args)
+    transform_response(args)
 }
 
 #[query(hidden = true)]
 fn transform_bis_request(args: TransformArgs) -> HttpResponse {
-    do_transform_bis_request(args)
+    transform_response(args)
+}
+
+/// Proves with the block headers of the canister's network that the transaction has at least `min_confirmations`.
+#[update(name = "verifyTransaction")]
+#[candid_method(rename = "verifyTransaction")]
+async fn verify_transaction(proof: MerkleProof, min_confirmations: u32) -> Result<u32, SyronError> {
+    spv::verify_transaction(NETWORK.with(|n| n.get()), proof, min_confirmations).await
+}
+
+/// Verifies the transfer inscription from its raw reveal transaction, checking that the indexer reports the same content.
+#[update(name = "verifyTransferInscription")]
+#[candid_method(rename = "verifyTransferInscription")]
+async fn verify_transfer_inscription(txid: String, raw_tx: Vec<u8>, provider: u64) -> Result<InscriptionInfo, SyronError> {
+    verify_inscription_witness(ServiceProvider::Provider(provider), txid, &raw_tx, get_rpc_payer(&ic_cdk::caller())).await
 }
 
 #[update]
 async fn get_box_address(args: GetBoxAddressArgs) -> String {
-    // check_anonymous_caller();
     get_btc_address::get_box_address(args).await
 }
 
 #[update]
-async fn update_ssi_balance(args: GetBoxAddressArgs) -> Result<Vec<UtxoStatus>, UpdateBalanceError> {
-    // check_anonymous_caller();
-    check_postcondition(updates::update_balance::update_ssi_balance(args).await)
+async fn update_ssi_balance(args: GetBoxAddressArgs) -> Result<Vec<UtxoStatus>, SyronError> {
+    // @dev Permissionless: it only credits the SSI with its own confirmed deposits
+    check_postcondition(updates::update_balance::update_ssi_balance(args).await.map_err(SyronError::from))
 }
 
 #[update]
-pub async fn withdraw_susd(args: GetBoxAddressArgs, txid: String, cycles_cost: u64, provider: u64, fee: u64) -> Result<String, UpdateBalanceError> {
+pub async fn withdraw_susd(args: GetBoxAddressArgs, txid: String, provider: u64, fee: u64, proof: Option<OwnershipProof>) -> Result<String, SyronError> {
     // @review (mainnet) automate provider config per network
     
     // @dev Verify args.op = GetSyron or throw erorr
     if args.op != SyronOperation::GetSyron {
-        return Err(UpdateBalanceError::GenericError{
-            error_code: 300,
-            error_message: "Invalid operation".to_string(),
-        });
+        return Err(SyronError::InvalidOperation);
     }
 
+    let authorization = authorize("withdraw_susd", &args.ssi, None, proof).await?;
+    let _guard = OperationGuard::ssi(&args.ssi)?;
+
     // @dev Update Balance (the user's SDB MUST have BTC deposit confirmed)
     let _ = updates::update_balance::update_ssi_balance(args.clone()).await; //?;  @review (error) only propagate error if != NoNewUtxos
 
     // @dev Read SYRON available balance (nonce #2)
-    let balance = balance_of(SyronLedger::SUSD, &args.ssi, 2).await.unwrap(); //@review the inscribed amt might be less than the balance
+    let balance = read_balance(SyronLedger::SUSD, &args.ssi, 2).await?; //@review the inscribed amt might be less than the balance
 
-    mint(args.ssi, txid, cycles_cost as u128, provider, balance, fee).await
+    authorization.check_amount(balance, None)?;
+
+    mint(args.ssi, txid, provider, balance, fee).await
 }
 
 #[update]
-pub async fn syron_withdrawal(args: GetBoxAddressArgs, txid: String, cycles_cost: u64, provider: u64, amount: u64, fee: u64) -> Result<String, UpdateBalanceError> {
+pub async fn syron_withdrawal(args: GetBoxAddressArgs, txid: String, provider: u64, amount: u64, fee: u64, proof: Option<OwnershipProof>) -> Result<String, SyronError> {
     // @dev Verify args.op = GetSyron or throw erorr
     if args.op != SyronOperation::GetSyron {
-        return Err(UpdateBalanceError::GenericError{
-            error_code: 300,
-            error_message: "Invalid operation".to_string(),
-        });
+        return Err(SyronError::InvalidOperation);
     }
 
-    mint(args.ssi, txid, cycles_cost as u128, provider, amount, fee).await
+    authorize("syron_withdrawal", &args.ssi, None, proof).await?.check_amount(amount, None)?;
+    let _guard = OperationGuard::ssi(&args.ssi)?;
+
+    mint(args.ssi, txid, provider, amount, fee).await
 }
 
 #[update]
-async fn redeem_btc(args: GetBoxAddressArgs, txid: String) -> Result<String, UpdateBalanceError> {
+async fn redeem_btc(args: GetBoxAddressArgs, txid: String, proof: Option<OwnershipProof>) -> Result<String, SyronError> {
     // @dev
 
     // 1. Verify args.op = RedeemBitcoin or throw erorr
     if args.op != SyronOperation::RedeemBitcoin {
-        return Err(UpdateBalanceError::GenericError{
-            error_code: 400,
-            error_message: "Invalid operation".to_string(),
-        });
+        return Err(SyronError::InvalidOperation);
     }
 
-    // 2. Get the Syron ledger's SUSD record of the user's SDB (subaccount with nonce 1) = SUSD[1]
     let ssi = (&args.ssi).to_string();
+    let authorization = authorize("redeem_btc", &ssi, None, proof).await?;
+    let _guard = OperationGuard::ssi(&ssi)?;
+
+    // @dev Claim the transfer inscription before any await so that it cannot be honored twice
+    claim_inscription(&txid, InscriptionOperation::Redemption, &ssi, 0, ic_cdk::api::time())?;
+    let journal_id = open_entry(InscriptionOperation::Redemption, &ssi, &txid, ic_cdk::api::time());
+
+    // @dev The claim is released if the bitcoin is not transferred
+    let burn = burn_inscription(&ssi, &args, txid.clone(), journal_id, &authorization).await;
+    let (tx_id, inscribed_amt) = compensate_on_error(journal_id, &txid, burn, ic_cdk::api::time())?;
+    settle_inscription(&txid, inscribed_amt);
+
+    let txid_bytes = tx_id.iter().rev().map(|n| *n as u8).collect::<Vec<u8>>();
+    let tx_id = hex::encode(txid_bytes);
+    record_broadcast(journal_id, &tx_id, inscribed_amt, ic_cdk::api::time());
+
+    // 8. Update Syron ledgers of debtor @review (error)
+    // @dev On failure the journal entry stays at TransactionBroadcast so the reconciler retries the update
+    if let Err(err) = updates::update_balance::update_ssi_balance(args).await {
+        let err = SyronError::from(err);
+        record_error(journal_id, &err, ic_cdk::api::time());
+        return Err(err);
+    }
+    record_step(journal_id, JournalStep::LedgerUpdated, ic_cdk::api::time());
 
-    let loan = balance_of(SyronLedger::SUSD, &ssi, 1).await.map_err(|_| UpdateBalanceError::GenericError {
-        error_code: 401,
-        error_message: "Failed to get loan balance".to_string(),
-    })?;
+    Ok(tx_id)
+}
+
+/// Verifies the repayment inscription and transfers the collateral from the SDB to the user's wallet.
+async fn burn_inscription(ssi: &str, args: &GetBoxAddressArgs, txid: String, journal_id: u64, authorization: &Authorization) -> Result<([u8; 32], u64), SyronError> {
+    // 2. Get the Syron ledger's SUSD record of the user's SDB (subaccount with nonce 1) = SUSD[1]
+    let loan = read_balance(SyronLedger::SUSD, ssi, 1).await?;
     // if the loan balance is 0, throw an error
     if loan == 0 {
-        return Err(UpdateBalanceError::GenericError {
-            error_code: 402,
-            error_message: "Your loan balance is zero, which is not allowed for redemptions.".to_string(),
-        });
+        return Err(SyronError::ZeroLoan);
     }
 
+    // @dev High-value redemptions require an ownership proof
+    authorization.check_amount(loan, get_proof_threshold())?;
+
     // 3. Check SUSD balance of the safety deposit box with the Tyron indexer
     let sdb = get_btc_address::get_box_address(args.clone()).await;
-    let syron_u64: u64 = match get_syron_balance(sdb.clone()).await {
-        Some(balance) if balance > 0 => balance,
-        _ => {
-            return Err(UpdateBalanceError::GenericError {
-                error_code: 403,
-                error_message: "Invalid balance from indexer".to_string(),
-            });
-        }
-    };
+    let syron_u64: u64 = get_syron_balance(sdb.clone()).await?;
 
-    // 4. Deposit must be at least the loan amount SUSD[1] or throw UpdateBalanceError
+    // 4. Deposit must be at least the loan amount SUSD[1] or throw SyronError::InsufficientBalance
     let limit = 2_000_000; // @governance
-    if syron_u64 < loan - limit {
-        return Err(UpdateBalanceError::GenericError{
-            error_code: 404,
-            error_message: "Insufficient SUSD deposited balance to redeem bitcoin".to_string(),
+    if syron_u64 == 0 || syron_u64 < loan.saturating_sub(limit) {
+        return Err(SyronError::InsufficientBalance {
+            available: syron_u64,
+            requested: loan.saturating_sub(limit),
         });
     }
     // if syron_u64 > loan {
@@ -500,37 +772,26 @@ async fn redeem_btc(args: GetBoxAddressArgs, txid: String) -> Result<String, Upd
     // }
 
     // 5. Check BRC-20 transfer inscription @mainnet
-    let outcall = call_indexer_inscription(0, txid.clone(), 72_000_000).await?;
-
-    let outcall_json: Value = serde_json::from_str(&outcall).unwrap();
-
-    // Access the "amt" field in the "brc20" object
-    let receiver_address: String = outcall_json.pointer("/utxo/address")
-        .and_then(Value::as_str)
-        .unwrap_or("")
-        .to_string();
-
-    if receiver_address != sdb {
-        return Err(UpdateBalanceError::GenericError{
-            error_code: 405,
-            error_message: format!("The inscription receiver address ({}) must be equal to your SDB ({})", receiver_address, sdb),
+    let inscription = verify_inscription(ServiceProvider::Chain(BTC_MAINNET_CHAIN_ID), txid.clone(), CyclesPayer::Canister).await?;
+    check_syron_transfer(&inscription)?;
+
+    // @dev The inscription receiver address must be equal to the user's SDB
+    if inscription.owner_address != sdb {
+        return Err(SyronError::InscriptionMismatch {
+            expected: sdb,
+            found: inscription.owner_address,
         });
     }
 
-    // Access the "amt" field in the "brc20" object
-    let syron_inscription: String = outcall_json.pointer("/brc20/amt")
-    .and_then(Value::as_str)
-    .unwrap_or("")
-    .to_string();
+    // The Syron inscribed amount must be equal to the loan or throw SyronError::InscribedAmountOutOfRange
+    let syron_u64_i = parse_inscribed_amount(&inscription.amt)?;
 
-    // The Syron inscribed amount must be equal to the loan or throw UpdateBalanceError::GenericError
-    let syron_f64: f64 = syron_inscription.parse().unwrap_or(0.0);
-    let syron_u64_i: u64 = (syron_f64 * 100_000_000 as f64) as u64;
-
-    if syron_u64_i < loan - limit || syron_u64_i > loan + limit || syron_u64_i > syron_u64 {
-        return Err(UpdateBalanceError::GenericError{
-            error_code: 406,
-            error_message: "Incorrect inscribed amount of stablecoin to repay the loan.".to_string(),
+    let max = std::cmp::min(loan + limit, syron_u64);
+    if syron_u64_i < loan.saturating_sub(limit) || syron_u64_i > max {
+        return Err(SyronError::InscribedAmountOutOfRange {
+            inscribed: syron_u64_i,
+            min: loan.saturating_sub(limit),
+            max,
         });
     }
 
@@ -540,16 +801,11 @@ async fn redeem_btc(args: GetBoxAddressArgs, txid: String) -> Result<String, Upd
     //         error_message: "Insufficient inscribed amount of stablecoin".to_string(),
     //     });
     // }
+
+    record_step(journal_id, JournalStep::InscriptionVerified, ic_cdk::api::time());
     
     // 6. Get Syron Bitcoin address
-    let key_name = KEY_NAME.with(|kn| kn.borrow().to_string());
-    // if empty, throw error
-    if key_name.is_empty() {
-        return Err(UpdateBalanceError::GenericError{
-            error_code: 407, // @review (key) error code
-            error_message: "Key name is empty".to_string(),
-        });
-    }
+    let key_name = get_key_name()?;
     
     let syron_derivation_path = DERIVATION_PATH.with(|d| d.clone());
     
@@ -560,34 +816,29 @@ async fn redeem_btc(args: GetBoxAddressArgs, txid: String) -> Result<String, Upd
 
     // 7. Transfer bitcoin from SDB to wallet
     let btc_network = NETWORK.with(|n| n.get());
-    let amount = balance_of(SyronLedger::BTC, &ssi, 1).await.unwrap();
+    let amount = read_balance(SyronLedger::BTC, ssi, 1).await?;
 
-    let tx_id = bitcoin_wallet::burn_p2wpkh(
+    let burn = bitcoin_wallet::burn_p2wpkh(
         amount,
-        &ssi,
+        ssi,
         btc_network,
         key_name,
         sdb,
-        &ssi,
+        ssi,
         &syron_address,
-        txid
-    ).await?;
-
-    // 8. Update Syron ledgers of debtor @review (error)
-    updates::update_balance::update_ssi_balance(args).await?;
+        txid.clone()
+    ).await;
+    inscription_cache::invalidate_spent(&txid, &burn);
+    let tx_id = burn?;
 
-    let txid_bytes = tx_id.iter().rev().map(|n| *n as u8).collect::<Vec<u8>>();
-    Ok(hex::encode(txid_bytes))
+    Ok((tx_id, syron_u64_i))
 }
 
 #[update]
-async fn redemption_gas(args: GetBoxAddressArgs) -> Result<u64, UpdateBalanceError> {
+async fn redemption_gas(args: GetBoxAddressArgs) -> Result<u64, SyronError> {
     // @dev Verify args.op = RedeemBitcoin or throw erorr
     if args.op != SyronOperation::RedeemBitcoin {
-        return Err(UpdateBalanceError::GenericError{
-            error_code: 500,
-            error_message: "Invalid operation".to_string(),
-        });
+        return Err(SyronError::InvalidOperation);
     }
 
     let ssi = (&args.ssi).to_string();
@@ -595,174 +846,198 @@ async fn redemption_gas(args: GetBoxAddressArgs) -> Result<u64, UpdateBalanceErr
 
     let syron_derivation_path = DERIVATION_PATH.with(|d| d.clone());
     
-    let key_name = KEY_NAME.with(|kn| kn.borrow().to_string());
-
-    // if empty, throw error
-    if key_name.is_empty() {
-        return Err(UpdateBalanceError::GenericError{
-            error_code: 501,
-            error_message: "Key name is empty".to_string(),
-        });
-    }
+    let key_name = get_key_name()?;
 
     let own_public_key =
         ecdsa_api::ecdsa_public_key(key_name.clone(), syron_derivation_path.clone()).await;
     
     let syron_address = public_key_to_p2wpkh(&own_public_key);
 
-    let amount = balance_of(SyronLedger::BTC, &ssi, 1).await.unwrap();
+    let amount = read_balance(SyronLedger::BTC, &ssi, 1).await?;
     let btc_network = NETWORK.with(|n| n.get());
     
     let gas = bitcoin_wallet::gas_p2wpkh(
         amount,
-        &ssi,
         btc_network,
         sdb,
         &ssi,
         &syron_address
     )
-    .await;
+    .await?;
 
     Ok(gas)
 }
 
 #[update]
-async fn get_account(ssi: String, dummy: bool) -> Result<CollateralizedAccount, UpdateBalanceError> {
-    check_postcondition(get_collateralized_account(&ssi, dummy).await)
+async fn get_account(ssi: String, dummy: bool) -> Result<CollateralizedAccount, SyronError> {
+    check_postcondition(get_collateralized_account(&ssi, dummy).await.map_err(SyronError::from))
 }
 
 #[update]
 // @review the order of UTXOs is important to transfer the proper inscription
-async fn liquidate(args: GetBoxAddressArgs, id: String, txid: String, fee: u64) -> Result<Vec<String>, UpdateBalanceError> {
+async fn liquidate(args: GetBoxAddressArgs, id: String, txid: String, fee: u64, proof: Option<OwnershipProof>) -> Result<Vec<String>, SyronError> {
+    let ssi: &str = &args.ssi;
+
+    // @dev The caller must be the liquidator, who pays from their SDB and receives the collateral; a proof names the debtor
+    let authorization = authorize("liquidate", &id, Some(ssi), proof).await?;
+    let _guard = OperationGuard::liquidation(ssi, &id)?;
+
+    // @dev Claim the transfer inscription before any await so that it cannot be honored twice
+    claim_inscription(&txid, InscriptionOperation::Liquidation, &id, 0, ic_cdk::api::time())?;
+    let journal_id = open_entry(InscriptionOperation::Liquidation, ssi, &txid, ic_cdk::api::time());
+
+    // @dev The claim is released if the liquidator's payment is not transferred
+    let payment = pay_liquidation(&args, &id, txid.clone(), fee, journal_id, &authorization).await;
+    let (btc_1, sdb_debtor, payment) = compensate_on_error(journal_id, &txid, payment, ic_cdk::api::time())?;
+    settle_inscription(&txid, payment.inscribed_amt);
+
+    // @dev The payment was broadcast: the reconciler retries the collateral transfer if it fails
+    let collateral = LiquidationCollateral {
+        payment_txid: payment.tx_id.clone(),
+        liquidator: id.clone(),
+        sdb: sdb_debtor,
+        amount: btc_1,
+    };
+    record_payment(journal_id, collateral.clone(), payment.inscribed_amt, ic_cdk::api::time());
+
+    let mut res: Vec<String> = Vec::new();
+    res.push(payment.tx_id);
+
+    let tx_id = match send_collateral(ssi, &collateral).await {
+        Ok(tx_id) => tx_id,
+        Err(err) => {
+            record_error(journal_id, &err, ic_cdk::api::time());
+            return Err(err);
+        }
+    };
+    record_broadcast(journal_id, &tx_id, payment.inscribed_amt, ic_cdk::api::time());
+    res.push(tx_id);
+
+    // 5. Update Syron ledgers (debtor)
+    if let Err(err) = updates::update_balance::update_ssi_balance(args).await {
+        let err = SyronError::from(err);
+        record_error(journal_id, &err, ic_cdk::api::time());
+        return Err(err);
+    }
+    record_step(journal_id, JournalStep::LedgerUpdated, ic_cdk::api::time());
+
+    Ok(res)
+}
+
+/// Transfers the debtor's collateral from their SDB to the liquidator, returning the transaction id.
+pub async fn send_collateral(ssi: &str, collateral: &LiquidationCollateral) -> Result<String, SyronError> {
+    let network = NETWORK.with(|n| n.get());
+    let key_name = get_key_name()?;
+
+    let tx_id = bitcoin_wallet::liquidate_p2wpkh(
+        collateral.amount,
+        ssi,
+        network,
+        key_name,
+        collateral.sdb.clone(),
+        &collateral.liquidator
+    )
+    .await?;
+
+    let txid_bytes = tx_id.iter().rev().map(|n| *n as u8).collect::<Vec<u8>>();
+    Ok(hex::encode(txid_bytes))
+}
+
+/// Verifies the liquidation and transfers the liquidator's payment inscription to the minter.
+/// Returns the debtor's BTC[1], the debtor's SDB and the payment.
+async fn pay_liquidation(args: &GetBoxAddressArgs, id: &str, txid: String, fee: u64, journal_id: u64, authorization: &Authorization) -> Result<(u64, String, TransferResult), SyronError> {
     let ssi: &str = &args.ssi;
     
     // @dev 1. Verify collateral ratio is below 12,000 basis points or throw error
     let collateralized_account = get_collateralized_account(ssi, true).await?;
 
-    if collateralized_account.collateral_ratio > 12_000 {
-        return Err(UpdateBalanceError::GenericError{
-            error_code: 500,
-            error_message: "Collateral ratio is above 1,2".to_string(),
+    if collateralized_account.collateral_ratio > LIQUIDATION_THRESHOLD {
+        return Err(SyronError::CollateralRatioAboveThreshold {
+            ratio: collateralized_account.collateral_ratio,
+            threshold: LIQUIDATION_THRESHOLD,
         });
     }
 
     let btc_1 = collateralized_account.btc_1;
     let susd_1 = collateralized_account.susd_1;
 
+    // @dev High-value liquidations require an ownership proof
+    authorization.check_amount(susd_1, get_proof_threshold())?;
+
     let sdb_debtor = get_btc_address::get_box_address(args.clone()).await;
 
     let liquidator = GetBoxAddressArgs {
-        ssi: id.clone(),
+        ssi: id.to_string(),
         op: get_btc_address::SyronOperation::Liquidation,
     };
 
     let sdb_liquidator = get_btc_address::get_box_address(liquidator).await;
 
     // 2. Check the liquidator's SUSD balance in their safety deposit box with the Tyron indexer
-    let syron_u64: u64 = match get_syron_balance(sdb_liquidator.clone()).await {
-        Some(balance) => balance,
-        None => {
-            return Err(UpdateBalanceError::GenericError{
-                error_code: 501,
-                error_message: "Invalid balance".to_string(),
-            });
-        }
-    };
+    let syron_u64: u64 = get_syron_balance(sdb_liquidator.clone()).await?;
 
-    // 3. Liquidator's balance must be at least >= debtor's SUSD[1] OR throw UpdateBalanceError
+    // 3. Liquidator's balance must be at least >= debtor's SUSD[1] OR throw SyronError::InsufficientBalance
     if syron_u64 <= susd_1 {
-        return Err(UpdateBalanceError::GenericError{
-            error_code: 502,
-            error_message: "Insufficient SUSD balance in the liquidator's account to liquidate the debtor.".to_string(),
+        return Err(SyronError::InsufficientBalance {
+            available: syron_u64,
+            requested: susd_1,
         });
     }
 
     // 4. Transfer syron from liquidator's SDB to minter and bitcoin from debtor's SDB to the user's wallet (liquidator)
-    let mut res: Vec<String> = Vec::new();
-
-    let provider = 0; // @review (mainnet) provider ID
-    let cycles_cost = 72_000_000;
-
-    let key_name = KEY_NAME.with(|kn| kn.borrow().to_string());
+    let key_name = get_key_name()?;
      
     let minter_derivation_path = DERIVATION_PATH.with(|d| d.clone());
     let dst_address = bitcoin_wallet::get_p2wpkh_address(key_name.clone(), minter_derivation_path).await;
 
-    let sdb_subaccount = compute_subaccount(1, &id);
+    let sdb_subaccount = compute_subaccount(1, id);
     let account = Account {
         owner: ic_cdk::id(),
         subaccount: Some(sdb_subaccount)
     };
-    let origin_derivation_path: Vec<Vec<u8>> = get_ssi_derivation_path(&account, &id).into_iter().map(|index| index.0).collect();
+    let origin_derivation_path: Vec<Vec<u8>> = get_ssi_derivation_path(&account, id).into_iter().map(|index| index.0).collect();
 
+    // @dev The primary provider of the chain verifies the payment inscription @mainnet
     let payment = syron_transfer(
         txid,
-        provider,
-        cycles_cost,
-        key_name.clone(),
+        ServiceProvider::Chain(BTC_MAINNET_CHAIN_ID),
+        key_name,
         origin_derivation_path,
         sdb_liquidator,
         &dst_address,
         susd_1,
         //@review update balance from syron deposits to make sure that the liquidator has enough to pay
-        fee
+        fee,
+        Some(journal_id)
     ).await?;
-    res.push(payment.tx_id);
-    
-    let network = NETWORK.with(|n| n.get());
-    
-    let tx_id = bitcoin_wallet::liquidate_p2wpkh(
-        btc_1,
-        ssi,
-        network,
-        key_name,
-        sdb_debtor,
-        &id
-    )
-    .await;
-
-    let txid_bytes = tx_id.iter().rev().map(|n| *n as u8).collect::<Vec<u8>>();
-    res.push(hex::encode(txid_bytes));
-
-    // 5. Update Syron ledgers (debtor)
-    updates::update_balance::update_ssi_balance(args).await?;
 
-    Ok(res)
-}
-
-fn check_anonymous_caller() {
-    if ic_cdk::caller() == Principal::anonymous() {
-        panic!("anonymous caller not allowed")
-    }
+    Ok((btc_1, sdb_debtor, payment))
 }
 
 #[update]
-pub async fn send_syron(args: GetBoxAddressArgs, recipient: String, amount: u64) -> Result<Vec<u64>, UpdateBalanceError> {
-    check_anonymous_caller();
-
+pub async fn send_syron(args: GetBoxAddressArgs, recipient: String, amount: u64, proof: Option<OwnershipProof>) -> Result<Vec<u64>, SyronError> {
     // @dev Verify args.op = Payment or throw erorr
     if args.op != SyronOperation::Payment {
-        return Err(UpdateBalanceError::GenericError{
-            error_code: 600,
-            error_message: "Invalid operation".to_string(),
-        });
+        return Err(SyronError::InvalidOperation);
     }
 
     let ssi = args.ssi;
+    authorize("send_syron", &ssi, Some(&recipient), proof).await?.check_amount(amount, None)?;
+    let _guard = OperationGuard::ssi(&ssi)?;
 
     // @dev Read SYRON available balance (nonce #2)
-    let balance = balance_of(SyronLedger::SUSD, &ssi, 2).await.unwrap();
+    let balance = read_balance(SyronLedger::SUSD, &ssi, 2).await?;
 
     // amount cannot be higher than the balance
     if amount > balance {
-        return Err(UpdateBalanceError::GenericError{
-            error_code: 601,
-            error_message: "Insufficient balance".to_string(),
+        return Err(SyronError::InsufficientBalance {
+            available: balance,
+            requested: amount,
         });
     }
 
     match syron_payment(&ssi, &recipient, amount).await {
         Ok(res) => Ok(res),
-        Err(err) => Err(err)
+        Err(err) => Err(err.into())
     }
 }
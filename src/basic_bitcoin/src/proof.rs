@@ -0,0 +1,120 @@
+// @notice Ownership proofs
+// Instead of signing in with SIWB, users can authorize an operation by signing (BIP-322) a canonical
+// message with their SSI. Operators can also require such a fresh signature above an amount threshold.
+
+use crate::{
+    bip322,
+    provider::{METADATA, PROOF_NONCES},
+    siwb::authorize_caller,
+    types::{OwnershipProof, StorableSsi, SyronError},
+    PROOF_MAX_VALIDITY_NS,
+};
+
+/// The message that the SSI signs to authorize an operation. The target is the other party of the operation, e.g. the
+/// recipient of a payment, so that a proof seen before its nonce is used cannot be redirected.
+pub fn operation_message(
+    operation: &str,
+    ssi: &str,
+    target: Option<&str>,
+    max_amount: u64,
+    nonce: u64,
+    expiry: u64
+) -> String {
+    let target = target.map(|target| format!("Target: {}\n", target)).unwrap_or_default();
+    format!(
+        "Syron operation: {}\nSSI: {}\n{}Amount: {}\nNonce: {}\nExpiry: {}",
+        operation, ssi, target, max_amount, nonce, expiry
+    )
+}
+
+pub fn get_last_nonce(ssi: &str) -> Option<u64> {
+    PROOF_NONCES.with(|nonces| nonces.borrow().get(&StorableSsi::new(ssi)))
+}
+
+pub fn get_proof_threshold() -> Option<u64> {
+    METADATA.with(|m| m.borrow().get().signature_threshold)
+}
+
+pub fn set_proof_threshold(threshold: Option<u64>) {
+    METADATA.with(|m| {
+        let mut metadata = m.borrow().get().clone();
+        metadata.signature_threshold = threshold;
+        m.borrow_mut().set(metadata).unwrap();
+    });
+}
+
+/// Verifies the proof and consumes its nonce.
+pub fn verify_proof(
+    operation: &str,
+    ssi: &str,
+    target: Option<&str>,
+    proof: &OwnershipProof,
+    now: u64
+) -> Result<(), SyronError> {
+    if now > proof.expiry {
+        return Err(SyronError::SignatureExpired { expiry: proof.expiry });
+    }
+    if proof.expiry - now > PROOF_MAX_VALIDITY_NS {
+        return Err(SyronError::InvalidSignature {
+            reason: "the expiry is too far in the future".to_string(),
+        });
+    }
+    if let Some(last) = get_last_nonce(ssi) {
+        if proof.nonce <= last {
+            return Err(SyronError::NonceAlreadyUsed { nonce: proof.nonce, last });
+        }
+    }
+
+    let message = operation_message(operation, ssi, target, proof.max_amount, proof.nonce, proof.expiry);
+    bip322::verify(ssi, &message, &proof.signature).map_err(|reason| SyronError::InvalidSignature { reason })?;
+
+    PROOF_NONCES.with(|nonces| nonces.borrow_mut().insert(StorableSsi::new(ssi), proof.nonce));
+    Ok(())
+}
+
+/// How the caller was authorized to act for the SSI.
+#[derive(Debug)]
+pub struct Authorization {
+    // The amount signed by the SSI, if authorized with an ownership proof.
+    proven_amount: Option<u64>,
+}
+
+impl Authorization {
+    pub fn with_proof(max_amount: u64) -> Self {
+        Self { proven_amount: Some(max_amount) }
+    }
+
+    pub fn with_siwb() -> Self {
+        Self { proven_amount: None }
+    }
+
+    /// Fails if the amount exceeds the signed amount, or is above the threshold without an ownership proof.
+    pub fn check_amount(&self, amount: u64, threshold: Option<u64>) -> Result<(), SyronError> {
+        match (self.proven_amount, threshold) {
+            (Some(proven), _) if amount > proven => Err(SyronError::InvalidSignature {
+                reason: format!("the amount {} exceeds the signed amount {}", amount, proven),
+            }),
+            (None, Some(threshold)) if amount > threshold => Err(SyronError::SignatureRequired { amount, threshold }),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Authorizes the operation with the ownership proof if given, or else with SIWB.
+pub async fn authorize(
+    operation: &str,
+    ssi: &str,
+    target: Option<&str>,
+    proof: Option<OwnershipProof>
+) -> Result<Authorization, SyronError> {
+    match proof {
+        Some(proof) => {
+            verify_proof(operation, ssi, target, &proof, ic_cdk::api::time())?;
+            Ok(Authorization::with_proof(proof.max_amount))
+        }
+        None => {
+            authorize_caller(ssi).await?;
+            Ok(Authorization::with_siwb())
+        }
+    }
+}
@@ -3,7 +3,7 @@
 // but a Candid dependency issue prevents direct import into Tyron.
 // I'm also making it more blockchain agnostic.
 
-use ic_cdk::api::management_canister::http_request::HttpHeader;
+use candid::Principal;
 
 use crate::{constants::{BIS_CREDENTIAL_PATH, BIS_SIGNET_HOSTNAME, BIS_TESTNET_HOSTNAME, BTC_SIGNET_CHAIN_ID, BTC_TESTNET_CHAIN_ID}, types::{Provider, RegisterProviderArgs, ServiceProvider, StorableServiceProvider}, BIS_MAINNET_HOSTNAME, BTC_MAINNET_CHAIN_ID, TYRON_CREDENTIAL_PATH, TYRON_MAINNET_HOSTNAME, TYRON_TESTNET_HOSTNAME, UNISAT_MAINNET_HOSTNAME, UNISAT_TESTNET_HOSTNAME };
 
@@ -14,8 +14,14 @@ use ic_stable_structures::DefaultMemoryImpl;
 use ic_stable_structures::VectorMemory;
 use ic_stable_structures::{Cell, StableBTreeMap};
 use std::cell::RefCell;
+use std::collections::BTreeMap;
 
 use crate::types::*;
+use crate::auth::do_deauthorize;
+use crate::credentials::{remove_credentials, store_credentials};
+use crate::validate::validate_provider;
+use ic_canister_log::log;
+use ic_ckbtc_minter_tyron::logs::P1;
 
 #[cfg(not(target_arch = "wasm32"))]
 type Memory = VirtualMemory<VectorMemory>;
@@ -27,6 +33,7 @@ thread_local! {
     // Unstable static data: this is reset when the canister is upgraded.
     // pub static UNSTABLE_METRICS: RefCell<Metrics> = RefCell::new(Metrics::default());
     // pub static UNSTABLE_SUBNET_SIZE: RefCell<u32> = RefCell::new(NODES_IN_FIDUCIARY_SUBNET);
+    pub static UNSTABLE_RESPONSE_SIZES: RefCell<BTreeMap<(u64, IndexerEndpoint), ResponseSizeStats>> = RefCell::new(BTreeMap::new());
 
     // Stable static data: this is preserved when the canister is upgraded.
     #[cfg(not(target_arch = "wasm32"))]
@@ -45,31 +52,77 @@ thread_local! {
     
     pub static SERVICE_PROVIDER_MAP: RefCell<StableBTreeMap<StorableServiceProvider, u64, Memory>> = RefCell::new(
         StableBTreeMap::init(MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(3)))));
+    pub static CONSUMED_INSCRIPTIONS: RefCell<StableBTreeMap<StorableInscriptionId, ConsumedInscription, Memory>> = RefCell::new(
+        StableBTreeMap::init(MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(4)))));
+    pub static JOURNAL: RefCell<StableBTreeMap<u64, JournalEntry, Memory>> = RefCell::new(
+        StableBTreeMap::init(MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(5)))));
+    pub static PROOF_NONCES: RefCell<StableBTreeMap<StorableSsi, u64, Memory>> = RefCell::new(
+        StableBTreeMap::init(MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(6)))));
+    pub static CREDENTIALS: RefCell<StableBTreeMap<u64, ProviderCredentials, Memory>> = RefCell::new(
+        StableBTreeMap::init(MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(7)))));
+    pub static PROVIDER_HEALTH: RefCell<StableBTreeMap<u64, ProviderHealth, Memory>> = RefCell::new(
+        StableBTreeMap::init(MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(8)))));
+    pub static INSCRIPTION_CACHE: RefCell<StableBTreeMap<InscriptionCacheKey, CachedInscription, Memory>> = RefCell::new(
+        StableBTreeMap::init(MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(9)))));
+    pub static UTXO_TAGS: RefCell<StableBTreeMap<OutpointKey, UtxoAssets, Memory>> = RefCell::new(
+        StableBTreeMap::init(MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(10)))));
+    pub static INSCRIPTION_CACHE_LRU: RefCell<StableBTreeMap<InscriptionCacheUse, (), Memory>> = RefCell::new(
+        StableBTreeMap::init(MEMORY_MANAGER.with(|m| m.borrow().get(MemoryId::new(11)))));
 }
 
+/// Adds the default providers that are missing, on init and on every upgrade. The defaults are trusted: they are not
+/// checked against the hostname allowlist, which may have changed since they were added.
 pub fn init_service_provider() {
-    for provider in get_default_providers() {
-        register_provider(provider);
+    for provider in get_missing_default_providers() {
+        let provider_id = add_provider(ic_cdk::caller(), provider);
+        log!(P1, "Adding default provider: {:?}", provider_id);
     }
     for (service, hostname) in get_default_service_provider_hostnames() {
         let provider = find_provider(|p| {
             Some(p.chain_id) == get_known_chain_id(&service) && p.hostname == hostname
-        })
-        .unwrap_or_else(|| {
-            panic!(
-                "Missing default provider for service {:?} with hostname {:?}",
-                service, hostname
-            )
         });
-        set_service_provider(&service, &provider);
+        if let Some(provider) = provider {
+            if let Err(err) = set_service_provider(&service, &provider) {
+                log!(P1, "Failed to route {:?} to provider {}: {:?}", service, provider.provider_id, err);
+            }
+        }
+    }
+}
+
+/// Trims the trailing "/" of the hostnames stored before they were bare hostnames, e.g. "btc.tyrondao.org/",
+/// so that the stored defaults are found by `get_missing_default_providers` and pass the hostname allowlist.
+pub fn migrate_provider_hostnames() -> usize {
+    let providers: Vec<Provider> = PROVIDERS.with(|providers| {
+        providers
+            .borrow()
+            .iter()
+            .map(|(_, provider)| provider)
+            .filter(|provider| provider.hostname.ends_with('/'))
+            .collect()
+    });
+    let migrated = providers.len();
+    for mut provider in providers {
+        provider.hostname = provider.hostname.trim_end_matches('/').to_string();
+        PROVIDERS.with(|p| p.borrow_mut().insert(provider.provider_id, provider));
     }
+    migrated
+}
+
+/// The default providers without a provider of the same chain and hostname.
+pub fn get_missing_default_providers() -> Vec<RegisterProviderArgs> {
+    get_default_providers()
+        .into_iter()
+        .filter(|provider| find_provider(|p| p.chain_id == provider.chain_id && p.hostname == provider.hostname).is_none())
+        .collect()
 }
 
+// @dev The defaults ship without API keys: owners set them with `updateProvider`, which keeps them in the credential store
 pub fn get_default_providers() -> Vec<RegisterProviderArgs> {
     vec![
         //@provider
         RegisterProviderArgs {
             chain_id: BTC_MAINNET_CHAIN_ID,
+            kind: IndexerKind::Tyron,
             hostname: TYRON_MAINNET_HOSTNAME.to_string(),
             credential_path: TYRON_CREDENTIAL_PATH.to_string(),
             credential_headers: None,
@@ -78,6 +131,7 @@ pub fn get_default_providers() -> Vec<RegisterProviderArgs> {
         },
         RegisterProviderArgs {
             chain_id: BTC_TESTNET_CHAIN_ID,
+            kind: IndexerKind::Tyron,
             hostname: TYRON_TESTNET_HOSTNAME.to_string(),
             credential_path: TYRON_CREDENTIAL_PATH.to_string(),
             credential_headers: None,
@@ -86,50 +140,43 @@ pub fn get_default_providers() -> Vec<RegisterProviderArgs> {
         },
         RegisterProviderArgs {
             chain_id: BTC_MAINNET_CHAIN_ID,
+            kind: IndexerKind::UniSat,
             hostname: UNISAT_MAINNET_HOSTNAME.to_string(),
             credential_path: "".to_string(),
-            credential_headers: Some(vec![HttpHeader{
-                name: "Authorization".to_string(),
-                value: "Bearer @api".to_string()
-            }]),
+            credential_headers: None,
             cycles_per_call: 0,
             cycles_per_message_byte: 0,
         },
         RegisterProviderArgs {
             chain_id: BTC_TESTNET_CHAIN_ID,
+            kind: IndexerKind::UniSat,
             hostname: UNISAT_TESTNET_HOSTNAME.to_string(),
             credential_path: "".to_string(),
-            credential_headers: Some(vec![HttpHeader{
-                name: "Authorization".to_string(),
-                value: "Bearer @api".to_string()
-            }]),
+            credential_headers: None,
             cycles_per_call: 0,
             cycles_per_message_byte: 0,
         },
         RegisterProviderArgs {
             chain_id: BTC_MAINNET_CHAIN_ID,
+            kind: IndexerKind::BestInSlot,
             hostname: BIS_MAINNET_HOSTNAME.to_string(),
             credential_path: BIS_CREDENTIAL_PATH.to_string(),
-            credential_headers: Some(vec![HttpHeader{
-                name: "x-api-key".to_string(),
-                value: "@api better to include provider as update operation".to_string()
-            }]),
+            credential_headers: None,
             cycles_per_call: 0,
             cycles_per_message_byte: 0,
         },
         RegisterProviderArgs {
             chain_id: BTC_TESTNET_CHAIN_ID,
+            kind: IndexerKind::BestInSlot,
             hostname: BIS_TESTNET_HOSTNAME.to_string(),
             credential_path: BIS_CREDENTIAL_PATH.to_string(),
-            credential_headers: Some(vec![HttpHeader{
-                name: "x-api-key".to_string(),
-                value: "@api".to_string()
-            }]),
+            credential_headers: None,
             cycles_per_call: 0,
             cycles_per_message_byte: 0,
         },
         RegisterProviderArgs {
             chain_id: BTC_SIGNET_CHAIN_ID,
+            kind: IndexerKind::BestInSlot,
             hostname: BIS_SIGNET_HOSTNAME.to_string(),
             credential_path: BIS_CREDENTIAL_PATH.to_string(),
             credential_headers: None,
@@ -139,11 +186,22 @@ pub fn get_default_providers() -> Vec<RegisterProviderArgs> {
     ]
 }
 
-pub fn register_provider(args: RegisterProviderArgs) -> u64 {
-    // @review (mainnet)
-    // validate_hostname(&args.hostname).unwrap();
-    // validate_credential_path(&args.credential_path).unwrap();
-    
+pub fn register_provider(args: RegisterProviderArgs) -> Result<u64, ValidationError> {
+    validate_provider(
+        &args.hostname,
+        &args.credential_path,
+        args.credential_headers.as_deref().unwrap_or_default()
+    )?;
+
+    // @dev The RegisterProvider permission is used once
+    let caller = ic_cdk::caller();
+    do_deauthorize(caller, Auth::RegisterProvider);
+    let provider_id = add_provider(caller, args);
+    log!(P1, "[{}] Registering provider: {:?}", caller, provider_id);
+    Ok(provider_id)
+}
+
+fn add_provider(caller: Principal, args: RegisterProviderArgs) -> u64 {
     let provider_id = METADATA.with(|m| {
         let mut metadata = m.borrow().get().clone();
         let id = metadata.next_provider_id;
@@ -152,20 +210,22 @@ pub fn register_provider(args: RegisterProviderArgs) -> u64 {
         id
     });
 
-    // @review (auth) 
-    // do_deauthorize(caller, Auth::RegisterProvider);
-    // log!(INFO, "[{}] Registering provider: {:?}", caller, provider_id);
-    
+    let credential_headers = store_credentials(provider_id, args.credential_headers.unwrap_or_default());
+    if !credential_headers.is_empty() {
+        let names: Vec<&str> = credential_headers.iter().map(|header| header.name.as_str()).collect();
+        log!(P1, "[credentials] {} sets provider {} credentials: {:?}", caller, provider_id, names);
+    }
     PROVIDERS.with(|providers| {
         providers.borrow_mut().insert(
             provider_id,
             Provider {
                 provider_id,
-                owner: ic_cdk::caller(),
+                owner: caller,
                 chain_id: args.chain_id,
+                kind: Some(args.kind),
                 hostname: args.hostname,
                 credential_path: args.credential_path,
-                credential_headers: args.credential_headers.unwrap_or_default(),
+                credential_headers,
                 cycles_per_call: args.cycles_per_call,
                 cycles_per_message_byte: args.cycles_per_message_byte,
                 cycles_owed: 0,
@@ -210,6 +270,16 @@ pub fn get_default_service_provider_hostnames() -> Vec<(ServiceProvider, &'stati
     ]
 }
 
+pub fn get_providers() -> Vec<ProviderView> {
+    PROVIDERS.with(|providers| {
+        providers
+            .borrow()
+            .iter()
+            .map(|(_, provider)| ProviderView::from(&provider))
+            .collect()
+    })
+}
+
 pub fn find_provider(f: impl Fn(&Provider) -> bool) -> Option<Provider> {
     PROVIDERS.with(|providers| {
         let providers = providers.borrow();
@@ -232,26 +302,31 @@ pub fn get_known_chain_id(service: &ServiceProvider) -> Option<u64> {
     }
 }
 
-pub fn set_service_provider(service: &ServiceProvider, provider: &Provider) {
+pub fn set_service_provider(service: &ServiceProvider, provider: &Provider) -> Result<(), ProviderError> {
     // log!(
     //     INFO,
     //     "Changing service {:?} to use provider: {}",
     //     service,
     //     provider.provider_id
     // );
-    if let Some(chain_id) = get_known_chain_id(service) {
-        if chain_id != provider.chain_id {
-            ic_cdk::trap(&format!(
-                "Mismatch between service and provider chain ids ({} != {})",
-                chain_id, provider.chain_id
-            ))
-        }
-    }
+    check_service_chain(service, provider.chain_id)?;
     SERVICE_PROVIDER_MAP.with(|mappings| {
         mappings
             .borrow_mut()
             .insert(StorableServiceProvider::new(service), provider.provider_id);
     });
+    Ok(())
+}
+
+/// A chain service can only be routed to a provider of the same chain.
+fn check_service_chain(service: &ServiceProvider, provider_chain_id: u64) -> Result<(), ProviderError> {
+    match get_known_chain_id(service) {
+        Some(service_chain_id) if service_chain_id != provider_chain_id => Err(ProviderError::ChainMismatch {
+            service_chain_id,
+            provider_chain_id,
+        }),
+        _ => Ok(()),
+    }
 }
 
 pub fn resolve_service_provider(service: ServiceProvider) -> Result<ResolvedServiceProvider, ProviderError> {
@@ -275,3 +350,117 @@ pub fn resolve_service_provider(service: ServiceProvider) -> Result<ResolvedServ
         }),
     })
 }
+
+/// Owners update and unregister their own providers; managers (`Manage` principals and controllers) any provider.
+fn check_provider_access(caller: &Principal, is_manager: bool, provider: &Provider) -> Result<(), ProviderError> {
+    if is_manager || provider.owner == *caller {
+        Ok(())
+    } else {
+        Err(ProviderError::NoPermission)
+    }
+}
+
+fn get_managed_provider(caller: &Principal, is_manager: bool, provider_id: u64) -> Result<Provider, ProviderError> {
+    let provider = PROVIDERS
+        .with(|providers| providers.borrow().get(&provider_id))
+        .ok_or(ProviderError::ProviderNotFound)?;
+    check_provider_access(caller, is_manager, &provider)?;
+    Ok(provider)
+}
+
+/// Changes the provider details, e.g. to rotate its credentials.
+pub fn update_provider(caller: Principal, is_manager: bool, args: UpdateProviderArgs) -> Result<(), ProviderError> {
+    let mut provider = get_managed_provider(&caller, is_manager, args.provider_id)?;
+    validate_provider(
+        args.hostname.as_ref().unwrap_or(&provider.hostname),
+        args.credential_path.as_ref().unwrap_or(&provider.credential_path),
+        args.credential_headers.as_deref().unwrap_or_default()
+    )?;
+    if let Some(hostname) = args.hostname {
+        provider.hostname = hostname;
+    }
+    if let Some(path) = args.credential_path {
+        provider.credential_path = path;
+    }
+    if let Some(headers) = args.credential_headers {
+        provider.credential_headers = store_credentials(args.provider_id, headers);
+    }
+    if let Some(cycles_per_call) = args.cycles_per_call {
+        provider.cycles_per_call = cycles_per_call;
+    }
+    if let Some(cycles_per_message_byte) = args.cycles_per_message_byte {
+        provider.cycles_per_message_byte = cycles_per_message_byte;
+    }
+    PROVIDERS.with(|providers| providers.borrow_mut().insert(args.provider_id, provider));
+    Ok(())
+}
+
+/// Removes the provider and the services routed to it.
+pub fn unregister_provider(caller: Principal, is_manager: bool, provider_id: u64) -> Result<(), ProviderError> {
+    get_managed_provider(&caller, is_manager, provider_id)?;
+    PROVIDERS.with(|providers| providers.borrow_mut().remove(&provider_id));
+    remove_credentials(provider_id);
+    SERVICE_PROVIDER_MAP.with(|mappings| {
+        let mut mappings = mappings.borrow_mut();
+        let services: Vec<StorableServiceProvider> = mappings
+            .iter()
+            .filter(|(_, id)| *id == provider_id)
+            .map(|(service, _)| service)
+            .collect();
+        for service in services {
+            mappings.remove(&service);
+        }
+    });
+    Ok(())
+}
+
+/// Makes the provider the primary one of its chain; other providers of the chain are no longer primary.
+/// Only managers route services: the primary provider answers the verifications of the funds-moving endpoints.
+pub fn set_primary_provider(provider_id: u64) -> Result<(), ProviderError> {
+    let provider = PROVIDERS
+        .with(|providers| providers.borrow().get(&provider_id))
+        .ok_or(ProviderError::ProviderNotFound)?;
+    PROVIDERS.with(|providers| {
+        let mut providers = providers.borrow_mut();
+        let chain: Vec<Provider> = providers
+            .iter()
+            .map(|(_, p)| p)
+            .filter(|p| p.chain_id == provider.chain_id)
+            .collect();
+        for mut p in chain {
+            p.primary = p.provider_id == provider_id;
+            providers.insert(p.provider_id, p);
+        }
+    });
+    Ok(())
+}
+
+/// Changes the administrative details of the provider and rewires `args.service` to it. Managers only, see `set_primary_provider`.
+pub fn manage_provider(args: ManageProviderArgs) -> Result<(), ProviderError> {
+    let mut provider = PROVIDERS
+        .with(|providers| providers.borrow().get(&args.provider_id))
+        .ok_or(ProviderError::ProviderNotFound)?;
+    // @dev Nothing is written if the service cannot be routed to the provider
+    if let Some(service) = &args.service {
+        check_service_chain(service, args.chain_id.unwrap_or(provider.chain_id))?;
+    }
+    if let Some(chain_id) = args.chain_id {
+        // @dev A provider moving to another chain is not primary there unless requested
+        if chain_id != provider.chain_id {
+            provider.primary = false;
+        }
+        provider.chain_id = chain_id;
+    }
+    let primary = args.primary;
+    if primary == Some(false) {
+        provider.primary = false;
+    }
+    PROVIDERS.with(|providers| providers.borrow_mut().insert(args.provider_id, provider.clone()));
+    if primary == Some(true) {
+        set_primary_provider(args.provider_id)?;
+    }
+    if let Some(service) = args.service {
+        set_service_provider(&service, &provider)?;
+    }
+    Ok(())
+}
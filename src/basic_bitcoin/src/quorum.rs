@@ -0,0 +1,176 @@
+// @notice N-of-M verification of transfer inscriptions
+// Before moving funds, several indexers can be asked about the same inscription;
+// the result is only trusted when enough of them agree on the receiver, ticker, operation and amount.
+
+use crate::{
+    accounting::CyclesPayer,
+    amount::parse_brc20_amount,
+    envelope::{check_indexed_transfer, verify_reveal_transaction},
+    http::{call_indexer_inscription, get_service_inscription},
+    inscription_cache::{clear_inscription_cache, get_or_fetch_inscription},
+    provider::{METADATA, PROVIDERS},
+    types::{InscriptionInfo, ProviderObservation, QuorumConfig, QuorumReport, ServiceProvider, SyronError},
+    SYRON_TICKER,
+};
+
+pub fn get_quorum_config() -> Option<QuorumConfig> {
+    METADATA.with(|m| m.borrow().get().inscription_quorum.clone())
+}
+
+pub fn set_quorum_config(quorum: Option<QuorumConfig>) -> Result<(), SyronError> {
+    if let Some(quorum) = &quorum {
+        validate_quorum(quorum).map_err(|reason| SyronError::InvalidQuorum { reason })?;
+    }
+    METADATA.with(|m| {
+        let mut metadata = m.borrow().get().clone();
+        metadata.inscription_quorum = quorum;
+        m.borrow_mut().set(metadata).unwrap();
+    });
+    // @dev The cached results were verified by the previous quorum
+    clear_inscription_cache();
+    Ok(())
+}
+
+pub fn validate_quorum(quorum: &QuorumConfig) -> Result<(), String> {
+    if quorum.min_agreement == 0 || quorum.min_agreement as usize > quorum.providers.len() {
+        return Err(format!(
+            "Invalid quorum: {} of {} providers",
+            quorum.min_agreement,
+            quorum.providers.len()
+        ));
+    }
+    for (index, provider_id) in quorum.providers.iter().enumerate() {
+        if quorum.providers[..index].contains(provider_id) {
+            return Err(format!("Duplicate provider in quorum: {}", provider_id));
+        }
+        if !PROVIDERS.with(|providers| providers.borrow().contains_key(provider_id)) {
+            return Err(format!("Provider not found: {}", provider_id));
+        }
+    }
+    Ok(())
+}
+
+/// Verifies the transfer inscription with the configured quorum, or with the given service when no quorum is set.
+/// Only results that passed the quorum are served from (and stored in) the inscription cache of the service's chain,
+/// so that a single provider's answer never stands in for a later quorum verification.
+pub async fn verify_inscription(
+    service: ServiceProvider,
+    txid: String,
+    payer: CyclesPayer
+) -> Result<InscriptionInfo, SyronError> {
+    match get_quorum_config() {
+        None => get_service_inscription(service, txid, payer).await,
+        Some(quorum) => {
+            let fetch = verify_inscription_quorum(&quorum, txid.clone(), payer);
+            get_or_fetch_inscription(&service, &txid, payer, fetch).await
+        }
+    }
+}
+
+/// Verifies the transfer inscription from the witness of its reveal transaction; the indexers only
+/// confirm that the transfer is valid, and must report the content that is inscribed.
+pub async fn verify_inscription_witness(
+    service: ServiceProvider,
+    txid: String,
+    raw_tx: &[u8],
+    payer: CyclesPayer
+) -> Result<InscriptionInfo, SyronError> {
+    let transfer = verify_reveal_transaction(&txid, raw_tx, SYRON_TICKER)?;
+    let inscription = verify_inscription(service, txid, payer).await?;
+    check_indexed_transfer(&transfer, &inscription)?;
+    Ok(inscription)
+}
+
+/// Checks that the inscription is a SYRON transfer; any other BRC-20 transfer of the same amount is rejected.
+pub fn check_syron_transfer(inscription: &InscriptionInfo) -> Result<(), SyronError> {
+    let unexpected = |field: &str, expected: &str, found: &str| {
+        Err(SyronError::UnexpectedInscription {
+            field: field.to_string(),
+            expected: expected.to_string(),
+            found: found.to_string(),
+        })
+    };
+    if !inscription.ticker.trim().eq_ignore_ascii_case(SYRON_TICKER) {
+        return unexpected("ticker", SYRON_TICKER, &inscription.ticker);
+    }
+    if inscription.op.trim() != "transfer" {
+        return unexpected("op", "transfer", &inscription.op);
+    }
+    Ok(())
+}
+
+pub async fn verify_inscription_quorum(
+    quorum: &QuorumConfig,
+    txid: String,
+    payer: CyclesPayer
+) -> Result<InscriptionInfo, SyronError> {
+    let mut observations = Vec::with_capacity(quorum.providers.len());
+    for provider_id in &quorum.providers {
+        let observation = match call_indexer_inscription(*provider_id, txid.clone(), payer).await {
+            Ok(inscription) => ProviderObservation {
+                provider_id: *provider_id,
+                inscription: Some(inscription),
+                error: None,
+            },
+            Err(err) => ProviderObservation {
+                provider_id: *provider_id,
+                inscription: None,
+                error: Some(format!("{}", err)),
+            },
+        };
+        observations.push(observation);
+    }
+    tally_quorum(observations, quorum.min_agreement).map_err(SyronError::QuorumNotReached)
+}
+
+/// Returns the inscription reported by at least `min_agreement` providers, or a report
+/// of every observation if no group of agreeing providers (or more than one) reaches it.
+pub fn tally_quorum(
+    observations: Vec<ProviderObservation>,
+    min_agreement: u32
+) -> Result<InscriptionInfo, QuorumReport> {
+    let (agreeing, winner) = {
+        let mut groups: Vec<(InscriptionKey, Vec<&InscriptionInfo>)> = vec![];
+        for inscription in observations.iter().filter_map(|o| o.inscription.as_ref()) {
+            let key = InscriptionKey::new(inscription);
+            match groups.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, group)) => group.push(inscription),
+                None => groups.push((key, vec![inscription])),
+            }
+        }
+
+        let agreeing = groups.iter().map(|(_, group)| group.len()).max().unwrap_or(0) as u32;
+        let mut quorums = groups.iter().filter(|(_, group)| group.len() as u32 >= min_agreement);
+        let winner = match (quorums.next(), quorums.next()) {
+            (Some((_, group)), None) => Some(group[0].clone()),
+            _ => None,
+        };
+        (agreeing, winner)
+    };
+
+    winner.ok_or(QuorumReport {
+        required: min_agreement,
+        agreeing,
+        observations,
+    })
+}
+
+/// The fields that providers must agree on.
+#[derive(PartialEq, Eq)]
+struct InscriptionKey {
+    owner_address: String,
+    ticker: String,
+    op: String,
+    amt: Result<u64, String>,
+}
+
+impl InscriptionKey {
+    fn new(inscription: &InscriptionInfo) -> Self {
+        Self {
+            owner_address: inscription.owner_address.trim().to_string(),
+            ticker: inscription.ticker.trim().to_lowercase(),
+            op: inscription.op.trim().to_string(),
+            amt: parse_brc20_amount(&inscription.amt).map_err(|_| inscription.amt.trim().to_string()),
+        }
+    }
+}
@@ -0,0 +1,80 @@
+// @notice Registry of consumed transfer inscriptions
+// A transfer inscription can only be honored once. It is claimed synchronously, before the
+// operation's first await, so concurrent calls presenting the same inscription are rejected.
+
+use crate::{
+    indexer::inscription_id,
+    provider::CONSUMED_INSCRIPTIONS,
+    types::{ConsumedInscription, InscriptionOperation, StorableInscriptionId, SyronError},
+};
+
+/// Records the inscription revealed by `txid` as consumed, or fails if it was already consumed.
+pub fn claim_inscription(
+    txid: &str,
+    operation: InscriptionOperation,
+    ssi: &str,
+    amount: u64,
+    timestamp: u64
+) -> Result<(), SyronError> {
+    let id = inscription_id(txid);
+    CONSUMED_INSCRIPTIONS.with(|registry| {
+        let mut registry = registry.borrow_mut();
+        let key = StorableInscriptionId::new(&id);
+        if let Some(consumed) = registry.get(&key) {
+            return Err(SyronError::InscriptionAlreadyConsumed {
+                inscription_id: id,
+                operation: consumed.operation,
+            });
+        }
+        registry.insert(key, ConsumedInscription {
+            operation,
+            ssi: ssi.to_string(),
+            amount,
+            timestamp,
+        });
+        Ok(())
+    })
+}
+
+/// Updates the consumed amount once the inscribed amount is known.
+pub fn settle_inscription(txid: &str, amount: u64) {
+    let key = StorableInscriptionId::new(&inscription_id(txid));
+    CONSUMED_INSCRIPTIONS.with(|registry| {
+        let mut registry = registry.borrow_mut();
+        if let Some(mut consumed) = registry.get(&key) {
+            consumed.amount = amount;
+            registry.insert(key, consumed);
+        }
+    });
+}
+
+/// Frees the inscription again; only valid while its UTXO has not been spent.
+pub fn release_inscription(txid: &str) {
+    let key = StorableInscriptionId::new(&inscription_id(txid));
+    CONSUMED_INSCRIPTIONS.with(|registry| registry.borrow_mut().remove(&key));
+}
+
+/// Releases the inscription claimed for `txid` if the operation failed before broadcasting its transfer.
+pub fn release_on_error<T>(txid: &str, result: Result<T, SyronError>) -> Result<T, SyronError> {
+    if result.is_err() {
+        release_inscription(txid);
+    }
+    result
+}
+
+pub fn get_consumed_inscription(inscription_id: &str) -> Option<ConsumedInscription> {
+    let key = StorableInscriptionId::new(inscription_id);
+    CONSUMED_INSCRIPTIONS.with(|registry| registry.borrow().get(&key))
+}
+
+pub fn list_consumed_inscriptions(offset: u64, limit: u64) -> Vec<(String, ConsumedInscription)> {
+    CONSUMED_INSCRIPTIONS.with(|registry| {
+        registry
+            .borrow()
+            .iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .map(|(key, consumed)| (key.to_string(), consumed))
+            .collect()
+    })
+}
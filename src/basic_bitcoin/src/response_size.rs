@@ -0,0 +1,99 @@
+// @notice Response size limits of the indexer calls
+// Responses larger than `max_response_bytes` are rejected by the IC, e.g. the balances of an address
+// holding many BRC-20 tickers. The limit escalates up to the configured cap, and the size that
+// succeeded is reused for the next calls of the same provider and endpoint type until the canister
+// is upgraded. After `RESPONSE_BYTES_DECAY_CALLS` successful calls, the limit shrinks back toward
+// `DEFAULT_MAX_RESPONSE_BYTES`, so that a single large response does not raise the cost of every later call.
+
+use ic_canister_log::log;
+use ic_ckbtc_minter_tyron::logs::P1;
+
+use crate::{
+    provider::{METADATA, UNSTABLE_RESPONSE_SIZES},
+    types::{IndexerEndpoint, ResponseSizeStats, SyronError},
+    DEFAULT_MAX_RESPONSE_BYTES, MAX_RESPONSE_BYTES_CAP, RESPONSE_BYTES_DECAY_CALLS, RESPONSE_BYTES_GROWTH_FACTOR,
+};
+
+pub fn get_max_response_bytes_cap() -> u64 {
+    METADATA
+        .with(|m| m.borrow().get().max_response_bytes_cap)
+        .unwrap_or(MAX_RESPONSE_BYTES_CAP)
+}
+
+pub fn set_max_response_bytes_cap(cap: Option<u64>) -> Result<(), SyronError> {
+    if let Some(cap) = cap {
+        if !(DEFAULT_MAX_RESPONSE_BYTES..=MAX_RESPONSE_BYTES_CAP).contains(&cap) {
+            return Err(SyronError::InvalidResponseBytesCap {
+                cap,
+                min: DEFAULT_MAX_RESPONSE_BYTES,
+                max: MAX_RESPONSE_BYTES_CAP,
+            });
+        }
+    }
+    METADATA.with(|m| {
+        let mut metadata = m.borrow().get().clone();
+        metadata.max_response_bytes_cap = cap;
+        m.borrow_mut().set(metadata).unwrap();
+    });
+    Ok(())
+}
+
+pub fn get_cached_response_bytes(provider: u64, endpoint_type: IndexerEndpoint) -> u64 {
+    UNSTABLE_RESPONSE_SIZES.with(|sizes| {
+        sizes
+            .borrow()
+            .get(&(provider, endpoint_type))
+            .map(|stats| stats.max_response_bytes)
+            .filter(|size| *size > 0)
+            .unwrap_or(DEFAULT_MAX_RESPONSE_BYTES)
+    })
+}
+
+/// Records the escalations of a call, and caches the size limit if the call succeeded.
+pub fn record_response_size(
+    provider: u64,
+    endpoint_type: IndexerEndpoint,
+    succeeded: Option<u64>,
+    escalations: u64,
+    extra_cycles: u128
+) {
+    if escalations > 0 {
+        log!(
+            P1,
+            "Provider {} {:?} response size escalated {} times for {} extra cycles",
+            provider, endpoint_type, escalations, extra_cycles
+        );
+    }
+    UNSTABLE_RESPONSE_SIZES.with(|sizes| {
+        let mut sizes = sizes.borrow_mut();
+        let stats = sizes.entry((provider, endpoint_type)).or_default();
+        if let Some(max_response_bytes) = succeeded {
+            if max_response_bytes != stats.max_response_bytes {
+                stats.max_response_bytes = max_response_bytes;
+                stats.successes = 0;
+            }
+            stats.successes += 1;
+            if stats.successes >= RESPONSE_BYTES_DECAY_CALLS {
+                stats.max_response_bytes = decayed_max_response_bytes(stats.max_response_bytes);
+                stats.successes = 0;
+            }
+        }
+        stats.escalations += escalations;
+        stats.extra_cycles += extra_cycles;
+    });
+}
+
+/// The previous response size limit, down to `DEFAULT_MAX_RESPONSE_BYTES`.
+pub fn decayed_max_response_bytes(current: u64) -> u64 {
+    (current / RESPONSE_BYTES_GROWTH_FACTOR).max(DEFAULT_MAX_RESPONSE_BYTES)
+}
+
+pub fn list_response_sizes() -> Vec<(u64, IndexerEndpoint, ResponseSizeStats)> {
+    UNSTABLE_RESPONSE_SIZES.with(|sizes| {
+        sizes
+            .borrow()
+            .iter()
+            .map(|((provider, endpoint_type), stats)| (*provider, *endpoint_type, stats.clone()))
+            .collect()
+    })
+}
@@ -0,0 +1,53 @@
+// @notice Caller authorization with Sign-In-With-Bitcoin (SIWB)
+// Users sign in with their Bitcoin wallet on the SIWB canister, which links their SSI address to
+// a principal. State-changing endpoints only act on an SSI for the principal linked to it.
+
+use candid::Principal;
+use ic_ckbtc_minter_tyron::state::read_state;
+use serde_bytes::ByteBuf;
+
+use crate::types::SyronError;
+
+/// Returns the principal that the SIWB canister links to the given Bitcoin address.
+pub async fn get_linked_principal(ssi: &str) -> Result<Option<Principal>, SyronError> {
+    let siwb_id = read_state(|s| s.siwb_id);
+    let (linked,): (Result<ByteBuf, String>,) = ic_cdk::call(siwb_id, "get_principal", (ssi.to_string(),))
+        .await
+        .map_err(|(code, message)| SyronError::CallFailed {
+            method: "get_principal".to_string(),
+            reason: format!("{:?}: {}", code, message),
+        })?;
+
+    // @dev The SIWB canister returns an error if the address never signed in
+    match linked {
+        Ok(bytes) => Principal::try_from_slice(&bytes)
+            .map(Some)
+            .map_err(|err| SyronError::CallFailed {
+                method: "get_principal".to_string(),
+                reason: format!("{}", err),
+            }),
+        Err(_) => Ok(None),
+    }
+}
+
+/// Fails unless the caller is the principal linked to the given SSI.
+pub async fn authorize_caller(ssi: &str) -> Result<(), SyronError> {
+    let caller = ic_cdk::caller();
+    let unauthorized = SyronError::Unauthorized {
+        caller,
+        ssi: ssi.to_string(),
+    };
+    if caller == Principal::anonymous() {
+        return Err(unauthorized);
+    }
+
+    if is_linked(caller, get_linked_principal(ssi).await?) {
+        Ok(())
+    } else {
+        Err(unauthorized)
+    }
+}
+
+pub fn is_linked(caller: Principal, linked: Option<Principal>) -> bool {
+    caller != Principal::anonymous() && linked == Some(caller)
+}
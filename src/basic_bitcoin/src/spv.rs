@@ -0,0 +1,167 @@
+// @notice SPV verification of bitcoin transactions
+// A transaction is proven to be in the canonical chain by a Merkle branch from its txid to the merkle root
+// of a block header. The headers come from the Bitcoin API of the management canister; their proof of work
+// and links are checked again before the confirmations are counted.
+// Inner nodes of the Merkle tree are also 32-byte hashes, so the txid must be of a known transaction
+// (e.g. a decoded reveal transaction) rather than any hash supplied by the caller.
+
+use bitcoin::{
+    consensus::{deserialize, params::Params},
+    hashes::{sha256d, Hash},
+    BlockHeader, Network,
+};
+use ic_cdk::api::management_canister::bitcoin::BitcoinNetwork;
+
+use crate::{
+    bitcoin_api,
+    types::{MerkleProof, SpvError, SyronError},
+    SPV_MAX_CONFIRMATIONS,
+};
+
+// The transaction index is a u32, so a branch cannot be deeper.
+const MAX_MERKLE_DEPTH: usize = 32;
+
+const BLOCK_HEADER_SIZE: usize = 80;
+
+pub fn get_bitcoin_network(network: BitcoinNetwork) -> Network {
+    match network {
+        BitcoinNetwork::Mainnet => Network::Bitcoin,
+        BitcoinNetwork::Testnet => Network::Testnet,
+        BitcoinNetwork::Regtest => Network::Regtest,
+    }
+}
+
+/// Parses a hash in display (byte-reversed) hex into its internal byte order.
+pub fn parse_hash(hash: &str) -> Result<[u8; 32], SpvError> {
+    let mut bytes: [u8; 32] = hex::decode(hash.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| SpvError::InvalidHash(hash.to_string()))?;
+    bytes.reverse();
+    Ok(bytes)
+}
+
+/// Hashes the txid up the branch; the bits of the index tell on which side each sibling is.
+pub fn compute_merkle_root(txid: [u8; 32], index: u32, branch: &[[u8; 32]]) -> Result<[u8; 32], SpvError> {
+    if branch.len() > MAX_MERKLE_DEPTH || (index as u64) >> branch.len() != 0 {
+        return Err(SpvError::InvalidMerkleIndex {
+            index,
+            depth: branch.len() as u32,
+        });
+    }
+    let mut hash = txid;
+    let mut position = index;
+    for sibling in branch {
+        let mut node = [0u8; 64];
+        let (left, right) = if position & 1 == 0 { (&hash, sibling) } else { (sibling, &hash) };
+        node[..32].copy_from_slice(left);
+        node[32..].copy_from_slice(right);
+        hash = sha256d::Hash::hash(&node).into_inner();
+        position >>= 1;
+    }
+    Ok(hash)
+}
+
+pub fn verify_merkle_proof(proof: &MerkleProof, merkle_root: [u8; 32]) -> Result<(), SpvError> {
+    let branch = proof
+        .branch
+        .iter()
+        .map(|hash| parse_hash(hash))
+        .collect::<Result<Vec<_>, _>>()?;
+    if compute_merkle_root(parse_hash(&proof.txid)?, proof.index, &branch)? != merkle_root {
+        return Err(SpvError::MerkleRootMismatch {
+            height: proof.block_height,
+        });
+    }
+    Ok(())
+}
+
+/// Parses the headers starting at `start_height`.
+pub fn parse_headers(start_height: u32, headers: &[Vec<u8>]) -> Result<Vec<BlockHeader>, SpvError> {
+    headers
+        .iter()
+        .zip(start_height..)
+        .map(|(header, height)| {
+            if header.len() != BLOCK_HEADER_SIZE {
+                return Err(SpvError::InvalidHeader {
+                    height,
+                    reason: format!("expected {} bytes, found {}", BLOCK_HEADER_SIZE, header.len()),
+                });
+            }
+            deserialize(header).map_err(|err| SpvError::InvalidHeader {
+                height,
+                reason: err.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Checks that every header meets its target, within the limit of the network, and extends the previous one.
+pub fn verify_header_chain(start_height: u32, headers: &[BlockHeader], network: Network) -> Result<(), SpvError> {
+    let pow_limit = Params::new(network).pow_limit;
+    for (index, (header, height)) in headers.iter().zip(start_height..).enumerate() {
+        let target = header.target();
+        if target > pow_limit || header.validate_pow(&target).is_err() {
+            return Err(SpvError::InvalidProofOfWork { height });
+        }
+        if index > 0 && header.prev_blockhash != headers[index - 1].block_hash() {
+            return Err(SpvError::BrokenChain { height });
+        }
+    }
+    Ok(())
+}
+
+/// Verifies the proof against the headers starting at its block and returns the confirmations of the transaction.
+/// The block and the `min_confirmations - 1` blocks on top of it must be among the headers.
+pub fn verify_inclusion(
+    proof: &MerkleProof,
+    headers: &[Vec<u8>],
+    tip_height: u32,
+    min_confirmations: u32,
+    network: Network
+) -> Result<u32, SpvError> {
+    let confirmations = tip_height
+        .checked_sub(proof.block_height)
+        .map_or(0, |depth| depth + 1);
+    if confirmations < min_confirmations {
+        return Err(SpvError::InsufficientConfirmations {
+            required: min_confirmations,
+            found: confirmations,
+        });
+    }
+
+    let expected = min_confirmations.max(1);
+    if (headers.len() as u32) < expected {
+        return Err(SpvError::MissingHeaders {
+            expected,
+            found: headers.len() as u32,
+        });
+    }
+    let headers = parse_headers(proof.block_height, &headers[..expected as usize])?;
+    verify_header_chain(proof.block_height, &headers, network)?;
+    verify_merkle_proof(proof, headers[0].merkle_root.into_inner())?;
+    Ok(confirmations)
+}
+
+/// Proves that the transaction is in the canonical chain with at least `min_confirmations`.
+pub async fn verify_transaction(
+    network: BitcoinNetwork,
+    proof: MerkleProof,
+    min_confirmations: u32
+) -> Result<u32, SyronError> {
+    if min_confirmations > SPV_MAX_CONFIRMATIONS {
+        return Err(SyronError::InvalidSpvProof(SpvError::TooManyConfirmations {
+            max: SPV_MAX_CONFIRMATIONS,
+        }));
+    }
+    let end_height = proof.block_height.saturating_add(min_confirmations.max(1) - 1);
+    let response = bitcoin_api::get_block_headers(network, proof.block_height, Some(end_height)).await?;
+    verify_inclusion(
+        &proof,
+        &response.block_headers,
+        response.tip_height,
+        min_confirmations,
+        get_bitcoin_network(network),
+    )
+    .map_err(SyronError::InvalidSpvProof)
+}
@@ -0,0 +1,44 @@
+#![cfg(test)]
+// @notice Fixtures shared by the tests
+
+use candid::Principal;
+
+use crate::provider::PROVIDERS;
+use crate::types::{IndexerKind, Provider};
+
+/// A xorshift generator, so that the random cases are the same on every run.
+pub struct Rng(pub u64);
+
+impl Rng {
+    pub fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    pub fn range(&mut self, low: u64, high: u64) -> u64 {
+        low + self.next() % (high - low + 1)
+    }
+}
+
+/// A provider stored with the current fields; tests change the fields they depend on.
+pub fn provider(provider_id: u64, owner: Principal, chain_id: u64) -> Provider {
+    Provider {
+        provider_id,
+        owner,
+        chain_id,
+        kind: Some(IndexerKind::UniSat),
+        hostname: "open-api.unisat.io".to_string(),
+        credential_path: "".to_string(),
+        credential_headers: vec![],
+        cycles_per_call: 0,
+        cycles_per_message_byte: 0,
+        cycles_owed: 0,
+        primary: false,
+    }
+}
+
+pub fn insert_provider(provider: Provider) {
+    PROVIDERS.with(|providers| providers.borrow_mut().insert(provider.provider_id, provider));
+}
@@ -1 +1,25 @@
-mod test_collateralized_account;
\ No newline at end of file
+mod fixtures;
+mod test_collateralized_account;
+mod test_quorum;
+mod test_brc20_amount;
+mod test_replay;
+mod test_journal;
+mod test_operation_guard;
+mod test_siwb;
+mod test_bip322;
+mod test_ownership_proof;
+mod test_auth;
+mod test_provider_lifecycle;
+mod test_credentials;
+mod test_validation;
+mod test_cycles_accounting;
+mod test_provider_health;
+mod test_response_size;
+mod test_transform;
+mod test_inscription_cache;
+mod test_envelope;
+mod test_spv;
+mod test_vsize;
+mod test_fee_solver;
+mod test_coin_selection;
+mod test_utxo_classification;
\ No newline at end of file
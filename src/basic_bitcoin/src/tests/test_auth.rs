@@ -0,0 +1,72 @@
+#[cfg(test)]
+mod tests {
+    use candid::Principal;
+
+    use crate::auth::{
+        check_authorized, check_manage_or_controller, do_authorize, do_deauthorize, get_authorized, is_authorized,
+    };
+    use crate::types::Auth;
+
+    fn principal(id: u8) -> Principal {
+        Principal::from_slice(&[id; 29])
+    }
+
+    #[test]
+    fn test_unauthorized_callers_are_rejected() {
+        let caller = principal(1);
+        assert!(check_authorized(&caller, Auth::Manage).is_err());
+        assert!(check_authorized(&caller, Auth::RegisterProvider).is_err());
+
+        do_authorize(caller, Auth::RegisterProvider);
+        assert!(check_authorized(&caller, Auth::RegisterProvider).is_ok());
+        // @dev Permissions are independent of each other
+        assert!(check_authorized(&caller, Auth::Manage).is_err());
+        assert!(check_authorized(&principal(2), Auth::RegisterProvider).is_err());
+    }
+
+    #[test]
+    fn test_manager_guard() {
+        let caller = principal(8);
+        // @dev e.g. a former controller: nothing is granted when installing or upgrading the canister
+        assert!(check_manage_or_controller(&caller, false).is_err());
+        assert!(check_manage_or_controller(&caller, true).is_ok());
+
+        // @dev Registering providers does not allow managing them
+        do_authorize(caller, Auth::RegisterProvider);
+        assert!(check_manage_or_controller(&caller, false).is_err());
+
+        do_authorize(caller, Auth::Manage);
+        assert!(check_manage_or_controller(&caller, false).is_ok());
+        do_deauthorize(caller, Auth::Manage);
+        assert!(check_manage_or_controller(&caller, false).is_err());
+    }
+
+    #[test]
+    fn test_authorize_and_deauthorize() {
+        let manager = principal(3);
+        assert!(do_authorize(manager, Auth::Manage));
+        assert!(!do_authorize(manager, Auth::Manage));
+        assert!(do_authorize(manager, Auth::FreeRpc));
+        assert!(is_authorized(&manager, Auth::Manage));
+
+        assert!(do_deauthorize(manager, Auth::Manage));
+        assert!(!do_deauthorize(manager, Auth::Manage));
+        assert!(!is_authorized(&manager, Auth::Manage));
+        assert!(is_authorized(&manager, Auth::FreeRpc));
+
+        assert!(do_deauthorize(manager, Auth::FreeRpc));
+        assert!(!do_deauthorize(principal(4), Auth::FreeRpc));
+    }
+
+    #[test]
+    fn test_get_authorized() {
+        do_authorize(principal(5), Auth::PriorityRpc);
+        do_authorize(principal(6), Auth::PriorityRpc);
+        do_authorize(principal(7), Auth::FreeRpc);
+
+        let authorized = get_authorized(Auth::PriorityRpc);
+        assert!(authorized.contains(&principal(5)));
+        assert!(authorized.contains(&principal(6)));
+        assert!(!authorized.contains(&principal(7)));
+    }
+}
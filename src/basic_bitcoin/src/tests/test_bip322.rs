@@ -0,0 +1,110 @@
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bitcoin::{
+        consensus::serialize,
+        secp256k1::{Message, Secp256k1},
+        util::{misc::{signed_msg_hash, MessageSignature}, sighash::SighashCache},
+        Address, EcdsaSighashType, Network, PrivateKey, Script, Witness,
+    };
+
+    use crate::bip322::{message_hash, to_sign, to_spend, verify};
+
+    // @dev Test vectors from BIP-322.
+    const WIF: &str = "L3VFeEujGtevx9w18HD1fhRbCH67Az2dpCymeRE1SoPK6XQtaN2k";
+    const P2WPKH: &str = "bc1q9vza2e8x573nczrlzms0wvx3gsqjx7vavgkx0l";
+    const P2TR: &str = "bc1ppv609nr0vr25u07u95waq5lucwfm6tde4nydujnu8npg4q75mr5sxq8lt3";
+    const EMPTY_SIGNATURE: &str = "AkcwRAIgM2gBAQqvZX15ZiysmKmQpDrG83avLIT492QBzLnQIxYCIBaTpOaD20qRlEylyxFSeEA2ba9YOixpX8z46TSDtS40ASECx/EgAxlkQpQ9hYjgGu6EBCPMVPwVIVJqO4XCsMvViHI=";
+    const HELLO_SIGNATURE: &str = "AkcwRAIgZRfIY3p7/DoVTty6YZbWS71bc5Vct9p9Fia83eRmw2QCICK/ENGfwLtptFluMGs2KsqoNSk89pO7F29zJLUx9a/sASECx/EgAxlkQpQ9hYjgGu6EBCPMVPwVIVJqO4XCsMvViHI=";
+    const HELLO_SIGNATURE_2: &str = "AkgwRQIhAOzyynlqt93lOKJr+wmmxIens//zPzl9tqIOua93wO6MAiBi5n5EyAcPScOjf1lAqIUIQtr3zKNeavYabHyR8eGhowEhAsfxIAMZZEKUPYWI4BruhAQjzFT8FSFSajuFwrDL1Yhy";
+    const HELLO_TAPROOT_SIGNATURE: &str = "AUHd69PrJQEv+oKTfZ8l+WROBHuy9HKrbFCJu7U1iK2iiEy1vMU5EfMtjc+VSHM7aU0SDbak5IUZRVno2P5mjSafAQ==";
+
+    #[test]
+    fn test_message_hash() {
+        assert_eq!(
+            hex::encode(message_hash(b"")),
+            "c90c269c4f8fcbe6880f72a721ddfbf1914268a794cbb21cfafee13770ae19f1"
+        );
+        assert_eq!(
+            hex::encode(message_hash(b"Hello World")),
+            "f0eb03b1a75ac6d9847f55c624a99169b5dccba2a31f5b23bea77ba270de0a7a"
+        );
+    }
+
+    #[test]
+    fn test_p2wpkh_simple_signatures() {
+        assert_eq!(verify(P2WPKH, "", EMPTY_SIGNATURE), Ok(()));
+        assert_eq!(verify(P2WPKH, "Hello World", HELLO_SIGNATURE), Ok(()));
+        assert_eq!(verify(P2WPKH, "Hello World", HELLO_SIGNATURE_2), Ok(()));
+
+        assert!(verify(P2WPKH, "Hello World", EMPTY_SIGNATURE).is_err());
+        assert!(verify(P2WPKH, "", HELLO_SIGNATURE).is_err());
+    }
+
+    #[test]
+    fn test_p2tr_simple_signature() {
+        assert_eq!(verify(P2TR, "Hello World", HELLO_TAPROOT_SIGNATURE), Ok(()));
+        assert!(verify(P2TR, "Hello World!", HELLO_TAPROOT_SIGNATURE).is_err());
+        // A P2WPKH witness cannot sign for a taproot address.
+        assert!(verify(P2TR, "Hello World", HELLO_SIGNATURE).is_err());
+    }
+
+    #[test]
+    fn test_p2wpkh_full_signature() {
+        let script_pubkey = script_pubkey(P2WPKH);
+        let to_spend = to_spend(&script_pubkey, b"Hello World");
+        let witness: Witness = bitcoin::consensus::deserialize(&base64::decode(HELLO_SIGNATURE).unwrap()).unwrap();
+        let full = base64::encode(serialize(&to_sign(&to_spend, witness.clone())));
+        assert_eq!(verify(P2WPKH, "Hello World", &full), Ok(()));
+
+        // The full transaction must spend the message's to_spend output.
+        let other = to_spend_for("Hello World!");
+        let wrong = base64::encode(serialize(&to_sign(&other, witness)));
+        assert!(verify(P2WPKH, "Hello World", &wrong).is_err());
+    }
+
+    #[test]
+    fn test_signature_by_another_key_is_rejected() {
+        let secp = Secp256k1::new();
+        let private_key = PrivateKey::from_wif(WIF).unwrap();
+        let other_key = PrivateKey::new(bitcoin::secp256k1::SecretKey::from_slice(&[7; 32]).unwrap(), Network::Bitcoin);
+        let script_pubkey = script_pubkey(P2WPKH);
+
+        // @dev Sign the to_sign transaction for the P2WPKH address with a different key.
+        let to_spend = to_spend(&script_pubkey, b"Hello World");
+        let unsigned = to_sign(&to_spend, Witness::new());
+        let script_code = Script::new_p2pkh(&private_key.public_key(&secp).pubkey_hash());
+        let sighash = SighashCache::new(&unsigned)
+            .segwit_signature_hash(0, &script_code, 0, EcdsaSighashType::All)
+            .unwrap();
+        let signature = secp.sign_ecdsa(&Message::from_slice(&sighash[..]).unwrap(), &other_key.inner);
+        let mut der = signature.serialize_der().to_vec();
+        der.push(EcdsaSighashType::All as u8);
+        let witness = Witness::from_vec(vec![der, private_key.public_key(&secp).to_bytes()]);
+
+        assert!(verify(P2WPKH, "Hello World", &base64::encode(serialize(&witness))).is_err());
+    }
+
+    #[test]
+    fn test_legacy_p2pkh_signature() {
+        let secp = Secp256k1::new();
+        let private_key = PrivateKey::from_wif(WIF).unwrap();
+        let address = Address::p2pkh(&private_key.public_key(&secp), Network::Bitcoin).to_string();
+
+        let hash = signed_msg_hash("Hello World");
+        let signature = secp.sign_ecdsa_recoverable(&Message::from_slice(&hash[..]).unwrap(), &private_key.inner);
+        let signature = base64::encode(MessageSignature::new(signature, true).serialize());
+
+        assert_eq!(verify(&address, "Hello World", &signature), Ok(()));
+        assert!(verify(&address, "Hello World!", &signature).is_err());
+    }
+
+    fn script_pubkey(address: &str) -> Script {
+        Address::from_str(address).unwrap().script_pubkey()
+    }
+
+    fn to_spend_for(message: &str) -> bitcoin::Transaction {
+        to_spend(&script_pubkey(P2WPKH), message.as_bytes())
+    }
+}
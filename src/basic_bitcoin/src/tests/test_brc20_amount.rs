@@ -0,0 +1,97 @@
+#[cfg(test)]
+mod tests {
+    use crate::amount::parse_brc20_amount;
+    use crate::indexer::{BisIndexer, IndexerClient, TyronIndexer, UnisatIndexer};
+    use crate::types::AmountError;
+
+    // @dev UniSat inscription info response (testnet).
+    const UNISAT_INSCRIPTION: &str = r#"{"code":0,"msg":"ok","data":{"utxo":{"txid":"5b1d8dd5e9c4a2f6a0a3b59a1f6a1c5a3e1e7d0f0cbb6e6b8f6f2b8b4b8e0a11","vout":0,"satoshi":546,"scriptType":"0014","scriptPk":"0014d9ac233abc6336337e2c01f32c577e1a9016c9ac","codeType":8,"address":"tb1qmxkzxw4uvvmrxl3vq8ejc4m7r2gpvjdvk0kvm0","height":2812034,"idx":1821,"isOpInRBF":false,"inscriptions":[{"inscriptionId":"5b1d8dd5e9c4a2f6a0a3b59a1f6a1c5a3e1e7d0f0cbb6e6b8f6f2b8b4b8e0a11i0","inscriptionNumber":1184025,"isBRC20":true,"moved":false,"offset":0}]},"address":"tb1qmxkzxw4uvvmrxl3vq8ejc4m7r2gpvjdvk0kvm0","offset":0,"inscriptionIndex":0,"inscriptionNumber":1184025,"inscriptionId":"5b1d8dd5e9c4a2f6a0a3b59a1f6a1c5a3e1e7d0f0cbb6e6b8f6f2b8b4b8e0a11i0","contentType":"text/plain;charset=utf-8","contentLength":57,"contentBody":"","height":2812034,"timestamp":1715853262,"inSatoshi":546,"outSatoshi":0,"brc20":{"op":"transfer","tick":"SYRON","lim":"","amt":"15.5","decimal":""},"detail":null,"location":"5b1d8dd5e9c4a2f6a0a3b59a1f6a1c5a3e1e7d0f0cbb6e6b8f6f2b8b4b8e0a11:0:0"}}"#;
+
+    // @dev Tyron indexer balance response, which is the UniSat `data` object.
+    const TYRON_BALANCE: &str = r#"{"height":2812040,"total":2,"start":0,"detail":[{"ticker":"ordi","overallBalance":"1000","transferableBalance":"0","availableBalance":"1000"},{"ticker":"SYRON","overallBalance":"20000000.12345678","transferableBalance":"15.5","availableBalance":"19999984.62345678"}]}"#;
+
+    // @dev BestInSlot BRC-20 wallet balances response (18-decimal integers).
+    const BIS_BALANCE: &str = r#"{"data":[{"ticker":"syron","overall_balance":"15500000000000000000","available_balance":"0","block_height":2812040}],"block_height":2812041}"#;
+
+    #[test]
+    fn test_parse_integer_amounts() {
+        assert_eq!(parse_brc20_amount("0"), Ok(0));
+        assert_eq!(parse_brc20_amount("1"), Ok(100_000_000));
+        assert_eq!(parse_brc20_amount("007"), Ok(700_000_000));
+        assert_eq!(parse_brc20_amount("184467440737"), Ok(18_446_744_073_700_000_000));
+    }
+
+    #[test]
+    fn test_parse_fractional_amounts() {
+        assert_eq!(parse_brc20_amount("0.2"), Ok(20_000_000));
+        assert_eq!(parse_brc20_amount("15.5"), Ok(1_550_000_000));
+        assert_eq!(parse_brc20_amount("15.50"), Ok(1_550_000_000));
+        assert_eq!(parse_brc20_amount("0.00000001"), Ok(1));
+        assert_eq!(parse_brc20_amount("1.10000000"), Ok(110_000_000));
+    }
+
+    #[test]
+    fn test_parse_large_amounts_exactly() {
+        // `0.29 * 100_000_000 as f64` truncates to 28_999_999.
+        assert_eq!(parse_brc20_amount("0.29"), Ok(29_000_000));
+        assert_eq!(parse_brc20_amount("20000000.12345679"), Ok(2_000_000_012_345_679));
+        assert_eq!(parse_brc20_amount("184467440737.09551615"), Ok(u64::MAX));
+        assert_eq!(parse_brc20_amount("184467440737.09551616"), Err(AmountError::Overflow));
+        assert_eq!(parse_brc20_amount("99999999999999999999"), Err(AmountError::Overflow));
+    }
+
+    #[test]
+    fn test_reject_malformed_amounts() {
+        assert_eq!(parse_brc20_amount(""), Err(AmountError::Empty));
+        assert_eq!(parse_brc20_amount("-1"), Err(AmountError::Negative));
+        assert_eq!(parse_brc20_amount("-0.5"), Err(AmountError::Negative));
+        assert_eq!(parse_brc20_amount("1e8"), Err(AmountError::Exponent));
+        assert_eq!(parse_brc20_amount("1.5E-3"), Err(AmountError::Exponent));
+        assert_eq!(parse_brc20_amount("+1"), Err(AmountError::InvalidCharacter("+".to_string())));
+        assert_eq!(parse_brc20_amount(" 1"), Err(AmountError::InvalidCharacter(" ".to_string())));
+        assert_eq!(parse_brc20_amount("1,5"), Err(AmountError::InvalidCharacter(",".to_string())));
+        assert_eq!(parse_brc20_amount("abc"), Err(AmountError::InvalidCharacter("a".to_string())));
+        assert_eq!(parse_brc20_amount("."), Err(AmountError::Malformed));
+        assert_eq!(parse_brc20_amount(".5"), Err(AmountError::Malformed));
+        assert_eq!(parse_brc20_amount("5."), Err(AmountError::Malformed));
+        assert_eq!(parse_brc20_amount("1.2.3"), Err(AmountError::Malformed));
+    }
+
+    #[test]
+    fn test_reject_more_than_eight_decimals() {
+        assert_eq!(
+            parse_brc20_amount("0.000000001"),
+            Err(AmountError::TooManyDecimals { max: 8, found: 9 })
+        );
+        assert_eq!(
+            parse_brc20_amount("1.000000000000000000"),
+            Err(AmountError::TooManyDecimals { max: 8, found: 18 })
+        );
+    }
+
+    #[test]
+    fn test_unisat_inscription_amount() {
+        let inscription = UnisatIndexer.parse_inscription(UNISAT_INSCRIPTION).unwrap();
+        assert_eq!(inscription.ticker, "SYRON");
+        assert_eq!(inscription.op, "transfer");
+        assert_eq!(parse_brc20_amount(&inscription.amt), Ok(1_550_000_000));
+    }
+
+    #[test]
+    fn test_tyron_balance_amounts() {
+        let balances = TyronIndexer.parse_balances(TYRON_BALANCE).unwrap();
+        let syron = balances.iter().find(|b| b.ticker == "SYRON").unwrap();
+        assert_eq!(parse_brc20_amount(&syron.overall_balance), Ok(2_000_000_012_345_678));
+        assert_eq!(parse_brc20_amount(&syron.transferable_balance), Ok(1_550_000_000));
+        assert_eq!(parse_brc20_amount(&syron.available_balance), Ok(1_999_998_462_345_678));
+    }
+
+    #[test]
+    fn test_bis_balance_amounts() {
+        let balances = BisIndexer.parse_balances(BIS_BALANCE).unwrap();
+        assert_eq!(balances[0].overall_balance, "15.5");
+        assert_eq!(parse_brc20_amount(&balances[0].overall_balance), Ok(1_550_000_000));
+        assert_eq!(parse_brc20_amount(&balances[0].transferable_balance), Ok(1_550_000_000));
+        assert_eq!(parse_brc20_amount(&balances[0].available_balance), Ok(0));
+    }
+}
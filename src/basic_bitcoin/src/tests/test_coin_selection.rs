@@ -0,0 +1,231 @@
+#[cfg(test)]
+mod tests {
+    use ic_cdk::api::management_canister::bitcoin::{Outpoint, Utxo};
+    use ic_ckbtc_minter_tyron::address::BitcoinAddress;
+
+    use crate::coin_selection::{
+        select_coins, BranchAndBound, CoinSelector, LargestFirst, OldestFirst, PrivacyPreserving, Selection,
+        SelectionParams, DEFAULT_COIN_SELECTOR,
+    };
+    use crate::fee::solve_fee;
+    use crate::tests::fixtures::Rng;
+    use crate::types::SyronError;
+    use crate::vsize::InputType;
+
+    const CASES: usize = 1_000;
+
+    const DUST_THRESHOLD: u64 = 546;
+
+    fn utxo(index: u8, value: u64, height: u32) -> Utxo {
+        Utxo {
+            outpoint: Outpoint {
+                txid: vec![index; 32],
+                vout: 0,
+            },
+            value,
+            height,
+        }
+    }
+
+    fn selection_params(target: u64, fee_per_vbyte: u64) -> SelectionParams {
+        SelectionParams {
+            target,
+            fee_per_vbyte,
+            fixed_inputs: vec![],
+            fixed_outputs: vec![BitcoinAddress::P2trV1([1; 32])],
+            input_type: InputType::P2wpkh,
+            change_address: BitcoinAddress::P2wpkhV0([2; 20]),
+            dust_threshold: DUST_THRESHOLD,
+        }
+    }
+
+    /// Checks that the selection pays exactly the target, the fee and the change.
+    fn check(selection: &Selection, params: &SelectionParams) {
+        assert_eq!(selection.value(), params.target + selection.fee + selection.change);
+        let inputs = selection.utxos.len();
+        if selection.change > 0 {
+            assert!(selection.change > DUST_THRESHOLD);
+            assert_eq!(selection.fee, params.fee(inputs, true));
+        } else {
+            // @dev Change below the dust threshold, or worth less than it costs, goes to the fee
+            assert!(selection.fee >= params.fee(inputs, false));
+        }
+    }
+
+    /// Builds the transaction like the wallet builders: change above the dust threshold gets an output.
+    fn solve(selection: &Selection, params: &SelectionParams) -> Result<(bool, u64), SyronError> {
+        let value = selection.value();
+        let inputs = selection.utxos.len();
+        let mut iterations = 0;
+        let (change, fee) = solve_fee(
+            selection.fee,
+            |fee| {
+                iterations += 1;
+                let remaining = value.checked_sub(params.target + fee).ok_or(SyronError::InsufficientFunds {
+                    available: value,
+                    required: params.target + fee,
+                })?;
+                Ok(remaining > DUST_THRESHOLD)
+            },
+            |change| params.fee(inputs, *change),
+        )?;
+        // @dev Starting from the fee of the selection, the solver has nothing to settle
+        assert_eq!(iterations, 1);
+        Ok((change, fee))
+    }
+
+    #[test]
+    fn test_random_utxo_sets() {
+        let mut rng = Rng(0x434f_494e_5345_4c45);
+        // @dev Whether the strategy funds the transaction whenever the largest UTXOs do
+        let selectors: [(&dyn CoinSelector, bool); 5] = [
+            (&LargestFirst, true),
+            (&OldestFirst, true),
+            (&PrivacyPreserving, true),
+            (&BranchAndBound, false),
+            (&DEFAULT_COIN_SELECTOR, true),
+        ];
+        let mut changeless = 0;
+        for _ in 0..CASES {
+            let len = rng.range(1, 30) as usize;
+            let utxos: Vec<Utxo> = (0..len)
+                .map(|index| utxo(index as u8, rng.range(546, 200_000), rng.range(800_000, 850_000) as u32))
+                .collect();
+            let total: u64 = utxos.iter().map(|utxo| utxo.value).sum();
+            let params = selection_params(rng.range(0, total), rng.range(1_000, 100_000));
+
+            let largest_first = LargestFirst.select(&utxos, &params);
+            for (selector, complete) in selectors {
+                let selection = selector.select(&utxos, &params);
+                match &selection {
+                    Some(selection) => {
+                        check(selection, &params);
+                        assert_eq!(solve(selection, &params).unwrap(), (selection.change > 0, selection.fee));
+                        // @dev Every UTXO is selected at most once
+                        let mut txids: Vec<&Vec<u8>> = selection.utxos.iter().map(|utxo| &utxo.outpoint.txid).collect();
+                        txids.dedup();
+                        assert_eq!(txids.len(), selection.utxos.len());
+                    }
+                    // @dev If the largest UTXOs do not fund the transaction, no other UTXOs do
+                    None => assert!(largest_first.is_none() || !complete),
+                }
+            }
+
+            if let Some(largest_first) = &largest_first {
+                let oldest_first = OldestFirst.select(&utxos, &params).unwrap();
+                assert!(largest_first.utxos.len() <= oldest_first.utxos.len());
+                // @dev Without change, the strategies that add UTXOs in order only give up dust
+                for selection in [largest_first, &oldest_first] {
+                    let inputs = selection.utxos.len();
+                    assert!(selection.change > 0 || selection.fee <= params.fee(inputs, true) + DUST_THRESHOLD);
+                }
+            } else {
+                assert!(select_coins(&DEFAULT_COIN_SELECTOR, &utxos, &params).is_err());
+            }
+
+            if let Some(selection) = BranchAndBound.select(&utxos, &params) {
+                assert_eq!(selection.change, 0);
+                // @dev The excess over the effective values is less than what the change would cost
+                let effective_value = selection.value() - selection.utxos.len() as u64 * params.input_fee();
+                assert!(effective_value - params.target - params.fee(0, false) <= params.change_cost());
+                changeless += 1;
+            }
+        }
+        assert!(changeless > 0);
+    }
+
+    #[test]
+    fn test_branch_and_bound_without_change() {
+        let params = selection_params(50_000, 10_000);
+        // @dev A UTXO that pays the target and its own fee, with 100 satoshis to spare
+        let exact = 50_000 + params.fee(1, false) + 100;
+        let utxos = vec![utxo(0, 1_000_000, 1), utxo(1, exact, 2), utxo(2, 30_000, 3), utxo(3, 40_000, 4)];
+
+        let selection = BranchAndBound.select(&utxos, &params).unwrap();
+        assert_eq!(selection.utxos, vec![utxos[1].clone()]);
+        assert_eq!(selection.change, 0);
+        assert_eq!(selection.fee, params.fee(1, false) + 100);
+        assert_eq!(DEFAULT_COIN_SELECTOR.select(&utxos, &params), Some(selection.clone()));
+
+        // @dev Spending the largest UTXO pays for a change output, which costs more than the 100 satoshis
+        let largest_first = LargestFirst.select(&utxos, &params).unwrap();
+        assert_eq!(largest_first.utxos, vec![utxos[0].clone()]);
+        assert_eq!(largest_first.fee, params.fee(1, true));
+        assert_eq!(largest_first.change, 1_000_000 - 50_000 - params.fee(1, true));
+        assert!(selection.fee < largest_first.fee);
+    }
+
+    #[test]
+    fn test_strategies() {
+        let params = selection_params(70_000, 10_000);
+        let utxos = vec![
+            utxo(0, 20_000, 300),
+            utxo(1, 90_000, 200),
+            utxo(2, 60_000, 100),
+            utxo(3, 500_000, 400),
+            utxo(4, 150_000, 500),
+        ];
+
+        // @dev The largest UTXO funds the transaction on its own
+        let largest_first = LargestFirst.select(&utxos, &params).unwrap();
+        assert_eq!(largest_first.utxos, vec![utxos[3].clone()]);
+
+        // @dev The oldest UTXOs come first, until they cover the target and the fee
+        let oldest_first = OldestFirst.select(&utxos, &params).unwrap();
+        assert_eq!(oldest_first.utxos, vec![utxos[2].clone(), utxos[1].clone()]);
+        assert_eq!(oldest_first.change, 150_000 - 70_000 - params.fee(2, true));
+
+        // @dev The smallest UTXO that funds the transaction on its own
+        let privacy = PrivacyPreserving.select(&utxos, &params).unwrap();
+        assert_eq!(privacy.utxos, vec![utxos[1].clone()]);
+        assert_eq!(privacy.change, 90_000 - 70_000 - params.fee(1, true));
+
+        // @dev Without such a UTXO, as few UTXOs as possible
+        let params = selection_params(600_000, 10_000);
+        let privacy = PrivacyPreserving.select(&utxos, &params).unwrap();
+        assert_eq!(privacy.utxos, vec![utxos[3].clone(), utxos[4].clone()]);
+        assert_eq!(Some(privacy), LargestFirst.select(&utxos, &params));
+    }
+
+    #[test]
+    fn test_dust_change() {
+        let params = selection_params(50_000, 10_000);
+        // @dev The change would be 300 satoshis, below the dust threshold
+        let value = 50_000 + params.fee(1, true) + 300;
+        let utxos = vec![utxo(0, value, 1)];
+        for selector in [&LargestFirst as &dyn CoinSelector, &OldestFirst, &PrivacyPreserving] {
+            let selection = selector.select(&utxos, &params).unwrap();
+            assert_eq!(selection.change, 0);
+            assert_eq!(selection.fee, params.fee(1, true) + 300);
+            assert_eq!(solve(&selection, &params), Ok((false, selection.fee)));
+        }
+
+        // @dev Above it, the change gets an output
+        let utxos = vec![utxo(0, value + 300, 1)];
+        let selection = LargestFirst.select(&utxos, &params).unwrap();
+        assert_eq!(selection.change, 600);
+        assert_eq!(selection.fee, params.fee(1, true));
+    }
+
+    #[test]
+    fn test_insufficient_funds() {
+        let params = selection_params(100_000, 10_000);
+        let utxos = vec![utxo(0, 60_000, 1), utxo(1, 40_000, 2)];
+        for selector in [&LargestFirst as &dyn CoinSelector, &OldestFirst, &PrivacyPreserving, &BranchAndBound] {
+            assert_eq!(selector.select(&utxos, &params), None);
+        }
+        assert_eq!(
+            select_coins(&DEFAULT_COIN_SELECTOR, &utxos, &params),
+            Err(SyronError::InsufficientFunds {
+                available: 100_000,
+                required: 100_000 + params.fee(2, false),
+            })
+        );
+        assert!(select_coins(&DEFAULT_COIN_SELECTOR, &[], &params).is_err());
+
+        // @dev UTXOs worth less than the fee to spend them are not selected
+        let params = selection_params(0, 100_000);
+        let utxos = vec![utxo(0, params.input_fee(), 1)];
+        assert_eq!(BranchAndBound.select(&utxos, &params), None);
+    }
+}
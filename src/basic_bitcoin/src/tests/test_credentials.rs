@@ -0,0 +1,75 @@
+#[cfg(test)]
+mod tests {
+    use candid::Principal;
+    use ic_cdk::api::management_canister::http_request::HttpHeader;
+
+    use crate::credentials::{get_credentials, migrate_credentials, store_credentials, CREDENTIAL_MASK};
+    use crate::provider::{get_providers, PROVIDERS};
+    use crate::types::{IndexerKind, Provider, ProviderView};
+
+    fn api_key(value: &str) -> Vec<HttpHeader> {
+        vec![HttpHeader {
+            name: "x-api-key".to_string(),
+            value: value.to_string(),
+        }]
+    }
+
+    fn legacy_provider(provider_id: u64, credential_headers: Vec<HttpHeader>) -> Provider {
+        Provider {
+            provider_id,
+            owner: Principal::anonymous(),
+            chain_id: 0,
+            kind: Some(IndexerKind::BestInSlot),
+            hostname: "api.bestinslot.xyz".to_string(),
+            credential_path: "/v3".to_string(),
+            credential_headers,
+            cycles_per_call: 10,
+            cycles_per_message_byte: 1,
+            cycles_owed: 0,
+            primary: false,
+        }
+    }
+
+    #[test]
+    fn test_credentials_are_masked() {
+        let masked = store_credentials(2001, api_key("secret"));
+        assert_eq!(masked[0].name, "x-api-key");
+        assert_eq!(masked[0].value, CREDENTIAL_MASK);
+        assert_eq!(get_credentials(2001)[0].value, "secret");
+
+        // @dev Rotating replaces the value, and an empty set removes it
+        store_credentials(2001, api_key("rotated"));
+        assert_eq!(get_credentials(2001)[0].value, "rotated");
+        assert!(store_credentials(2001, vec![]).is_empty());
+        assert!(get_credentials(2001).is_empty());
+    }
+
+    #[test]
+    fn test_migrate_plaintext_credentials() {
+        PROVIDERS.with(|providers| providers.borrow_mut().insert(2002, legacy_provider(2002, api_key("legacy"))));
+
+        assert!(migrate_credentials() >= 1);
+        let provider = PROVIDERS.with(|providers| providers.borrow().get(&2002)).unwrap();
+        assert_eq!(provider.credential_headers[0].value, CREDENTIAL_MASK);
+        assert_eq!(get_credentials(2002)[0].value, "legacy");
+
+        // @dev Masked providers are not migrated again
+        migrate_credentials();
+        assert_eq!(get_credentials(2002)[0].value, "legacy");
+    }
+
+    #[test]
+    fn test_provider_view_has_no_credentials() {
+        PROVIDERS.with(|providers| providers.borrow_mut().insert(2003, legacy_provider(2003, vec![])));
+
+        let view = get_providers().into_iter().find(|view| view.provider_id == 2003).unwrap();
+        assert_eq!(view, ProviderView {
+            provider_id: 2003,
+            chain_id: 0,
+            hostname: "api.bestinslot.xyz".to_string(),
+            cycles_per_call: 10,
+            cycles_per_message_byte: 1,
+            primary: false,
+        });
+    }
+}
@@ -0,0 +1,95 @@
+#[cfg(test)]
+mod tests {
+    use candid::Principal;
+    use ic_cdk::api::management_canister::http_request::{CanisterHttpRequestArgument, HttpHeader, HttpMethod};
+
+    use crate::accounting::{
+        accrue_cycles_owed, check_attached_cycles, get_http_request_cost, get_provider_cost, get_request_size,
+        take_cycles_owed,
+    };
+    use crate::provider::PROVIDERS;
+    use crate::types::{IndexerKind, Provider, ProviderError};
+    use crate::MINIMUM_WITHDRAWAL_CYCLES;
+
+    fn provider(provider_id: u64, owner: Principal) -> Provider {
+        Provider {
+            provider_id,
+            owner,
+            chain_id: 0,
+            kind: Some(IndexerKind::UniSat),
+            hostname: "open-api.unisat.io".to_string(),
+            credential_path: "".to_string(),
+            credential_headers: vec![],
+            cycles_per_call: 1_000,
+            cycles_per_message_byte: 10,
+            cycles_owed: 0,
+            primary: false,
+        }
+    }
+
+    #[test]
+    fn test_http_request_cost() {
+        // @dev (3M + 60K * 13 + 400 * 1000 + 800 * 2048) * 13
+        assert_eq!(get_http_request_cost(1_000, 2_048), 75_639_200);
+        assert!(get_http_request_cost(1_001, 2_048) > get_http_request_cost(1_000, 2_048));
+    }
+
+    #[test]
+    fn test_provider_cost() {
+        let owner = Principal::anonymous();
+        assert_eq!(get_provider_cost(&provider(0, owner), 100), (1_000 + 10 * 100) * 13);
+        let free = Provider {
+            cycles_per_call: 0,
+            cycles_per_message_byte: 0,
+            ..provider(0, owner)
+        };
+        assert_eq!(get_provider_cost(&free, 100), 0);
+    }
+
+    #[test]
+    fn test_request_size() {
+        let request = CanisterHttpRequestArgument {
+            url: "https://open-api.unisat.io/v1".to_string(),
+            max_response_bytes: Some(2_048),
+            method: HttpMethod::GET,
+            headers: vec![HttpHeader {
+                name: "x-api-key".to_string(),
+                value: "key".to_string(),
+            }],
+            body: Some(vec![0; 10]),
+            transform: None,
+        };
+        assert_eq!(get_request_size(&request), 29 + 12 + 10);
+    }
+
+    #[test]
+    fn test_too_few_cycles() {
+        assert_eq!(check_attached_cycles(100, 100), Ok(()));
+        assert_eq!(
+            check_attached_cycles(99, 100),
+            Err(ProviderError::TooFewCycles { expected: 100, received: 99 })
+        );
+    }
+
+    #[test]
+    fn test_withdraw_cycles_owed() {
+        let owner = Principal::from_slice(&[31; 29]);
+        PROVIDERS.with(|providers| providers.borrow_mut().insert(3001, provider(3001, owner)));
+
+        accrue_cycles_owed(3001, MINIMUM_WITHDRAWAL_CYCLES - 1);
+        assert_eq!(
+            take_cycles_owed(&owner, 3001),
+            Err(ProviderError::TooFewCycles {
+                expected: MINIMUM_WITHDRAWAL_CYCLES,
+                received: MINIMUM_WITHDRAWAL_CYCLES - 1,
+            })
+        );
+
+        accrue_cycles_owed(3001, 1);
+        assert_eq!(take_cycles_owed(&Principal::anonymous(), 3001), Err(ProviderError::NoPermission));
+        assert_eq!(take_cycles_owed(&owner, 3001), Ok(MINIMUM_WITHDRAWAL_CYCLES));
+        // @dev The owed cycles are reset
+        assert!(take_cycles_owed(&owner, 3001).is_err());
+        assert_eq!(take_cycles_owed(&owner, 3002), Err(ProviderError::ProviderNotFound));
+    }
+}
@@ -0,0 +1,255 @@
+#[cfg(test)]
+mod tests {
+    use bitcoin::{
+        blockdata::{
+            opcodes::{self, all::{OP_CHECKSIG, OP_ENDIF, OP_IF}},
+            script::{Builder, Script},
+            witness::Witness,
+        },
+        consensus::serialize,
+        OutPoint, Transaction, TxIn, TxOut,
+    };
+
+    use crate::envelope::{check_indexed_transfer, get_tapscript, parse_envelope, verify_reveal_transaction};
+    use crate::types::{AmountError, EnvelopeError, InscriptionInfo, SyronError};
+    use crate::SYRON_TICKER;
+
+    const TRANSFER: &[u8] = br#"{"p":"brc-20","op":"transfer","tick":"SYRON","amt":"15.5"}"#;
+
+    /// A tapscript with the envelope after the signature check, like the ones built by the wallets.
+    fn tapscript(fields: &[(&[u8], &[u8])], body: &[&[u8]]) -> Script {
+        let mut builder = Builder::new()
+            .push_slice(&[0x11; 32])
+            .push_opcode(OP_CHECKSIG)
+            .push_opcode(opcodes::OP_FALSE)
+            .push_opcode(OP_IF)
+            .push_slice(b"ord");
+        for (tag, value) in fields {
+            builder = builder.push_slice(tag).push_slice(value);
+        }
+        builder = builder.push_slice(&[]);
+        for chunk in body {
+            builder = builder.push_slice(chunk);
+        }
+        builder.push_opcode(OP_ENDIF).into_script()
+    }
+
+    fn transfer_script(content_type: &[u8], body: &[u8]) -> Script {
+        tapscript(&[(&[1], content_type)], &[body])
+    }
+
+    fn reveal_transaction(witness: Witness) -> Transaction {
+        Transaction {
+            version: 2,
+            lock_time: 0,
+            input: vec![TxIn {
+                previous_output: OutPoint::null(),
+                script_sig: Script::new(),
+                sequence: 0xfffffffd,
+                witness,
+            }],
+            output: vec![TxOut {
+                value: 546,
+                script_pubkey: Script::new(),
+            }],
+        }
+    }
+
+    fn script_path_witness(tapscript: &Script) -> Witness {
+        let mut witness = Witness::new();
+        witness.push([0x22; 64]);
+        witness.push(tapscript.as_bytes());
+        witness.push([0xc1; 33]);
+        witness
+    }
+
+    fn verify(tapscript: &Script) -> Result<crate::types::Brc20Transfer, EnvelopeError> {
+        let transaction = reveal_transaction(script_path_witness(tapscript));
+        let txid = transaction.txid().to_string();
+        match verify_reveal_transaction(&txid, &serialize(&transaction), SYRON_TICKER) {
+            Ok(transfer) => Ok(transfer),
+            Err(SyronError::InvalidInscription { error, .. }) => Err(error),
+            Err(err) => panic!("unexpected error: {:?}", err),
+        }
+    }
+
+    #[test]
+    fn test_verify_transfer() {
+        let transaction = reveal_transaction(script_path_witness(&transfer_script(b"text/plain;charset=utf-8", TRANSFER)));
+        let txid = transaction.txid().to_string();
+
+        let transfer = verify_reveal_transaction(&txid, &serialize(&transaction), SYRON_TICKER).unwrap();
+        assert_eq!(transfer.inscription_id, format!("{}i0", txid));
+        assert_eq!(transfer.content_type, "text/plain;charset=utf-8");
+        assert_eq!(transfer.tick, "SYRON");
+        assert_eq!(transfer.amt, "15.5");
+        assert_eq!(transfer.amount, 1_550_000_000);
+    }
+
+    #[test]
+    fn test_body_in_chunks() {
+        // @dev Pushes are limited to 520 bytes, so larger bodies are split
+        let (head, tail) = TRANSFER.split_at(20);
+        let script = tapscript(&[(&[1], b"application/json")], &[head, tail]);
+        assert_eq!(verify(&script).unwrap().amount, 1_550_000_000);
+
+        // @dev The content type tag can be pushed with OP_1
+        let script = Builder::new()
+            .push_opcode(opcodes::OP_FALSE)
+            .push_opcode(OP_IF)
+            .push_slice(b"ord")
+            .push_int(1)
+            .push_slice(b"text/plain")
+            .push_slice(&[])
+            .push_slice(br#"{"p":"brc-20","op":"transfer","tick":"syron","amt":"1"}"#)
+            .push_opcode(OP_ENDIF)
+            .into_script();
+        assert_eq!(verify(&script).unwrap().tick, "syron");
+    }
+
+    #[test]
+    fn test_txid_mismatch() {
+        let transaction = reveal_transaction(script_path_witness(&transfer_script(b"text/plain", TRANSFER)));
+        let other = "0".repeat(64);
+        let result = verify_reveal_transaction(&other, &serialize(&transaction), SYRON_TICKER);
+        assert!(matches!(
+            result,
+            Err(SyronError::InvalidInscription { error: EnvelopeError::TxidMismatch { .. }, .. })
+        ));
+
+        let result = verify_reveal_transaction(&other, &[0x02, 0x00], SYRON_TICKER);
+        assert!(matches!(
+            result,
+            Err(SyronError::InvalidInscription { error: EnvelopeError::InvalidTransaction(_), .. })
+        ));
+    }
+
+    #[test]
+    fn test_envelope_not_found() {
+        // @dev A key-path spend has no tapscript
+        let mut witness = Witness::new();
+        witness.push([0x22; 64]);
+        let transaction = reveal_transaction(witness);
+        let txid = transaction.txid().to_string();
+        let result = verify_reveal_transaction(&txid, &serialize(&transaction), SYRON_TICKER);
+        assert!(matches!(
+            result,
+            Err(SyronError::InvalidInscription { error: EnvelopeError::EnvelopeNotFound, .. })
+        ));
+
+        // @dev The last element must be a control block
+        let mut witness = script_path_witness(&transfer_script(b"text/plain", TRANSFER)).to_vec();
+        witness.pop();
+        witness.push(vec![0x01; 33]);
+        assert_eq!(get_tapscript(&Witness::from_vec(witness)), None);
+
+        // @dev A script without the "ord" protocol id
+        let script = Builder::new()
+            .push_opcode(opcodes::OP_FALSE)
+            .push_opcode(OP_IF)
+            .push_slice(b"brc")
+            .push_opcode(OP_ENDIF)
+            .into_script();
+        assert_eq!(parse_envelope(&script), Ok(None));
+        assert_eq!(verify(&script), Err(EnvelopeError::EnvelopeNotFound));
+    }
+
+    #[test]
+    fn test_annex_is_skipped() {
+        let script = transfer_script(b"text/plain", TRANSFER);
+        let mut witness = script_path_witness(&script);
+        witness.push([0x50, 0x00]);
+        assert_eq!(get_tapscript(&witness), Some(script));
+    }
+
+    #[test]
+    fn test_malformed_envelopes() {
+        let unterminated = Builder::new()
+            .push_opcode(opcodes::OP_FALSE)
+            .push_opcode(OP_IF)
+            .push_slice(b"ord")
+            .push_slice(&[1])
+            .push_slice(b"text/plain")
+            .into_script();
+        assert!(matches!(verify(&unterminated), Err(EnvelopeError::MalformedEnvelope(_))));
+
+        let duplicate = tapscript(&[(&[1], b"text/plain"), (&[1], b"image/png")], &[TRANSFER]);
+        assert!(matches!(verify(&duplicate), Err(EnvelopeError::MalformedEnvelope(_))));
+
+        let unknown_even_field = tapscript(&[(&[1], b"text/plain"), (&[22], b"x")], &[TRANSFER]);
+        assert!(matches!(verify(&unknown_even_field), Err(EnvelopeError::MalformedEnvelope(_))));
+
+        let encoded = tapscript(&[(&[1], b"text/plain"), (&[9], b"br")], &[TRANSFER]);
+        assert!(matches!(verify(&encoded), Err(EnvelopeError::MalformedEnvelope(_))));
+
+        // @dev Odd fields are ignored, and the pointer is known
+        let fields: [(&[u8], &[u8]); 3] = [(&[1], b"text/plain"), (&[21], b"x"), (&[2], &[0])];
+        assert!(verify(&tapscript(&fields, &[TRANSFER])).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_brc20_content() {
+        assert_eq!(
+            verify(&transfer_script(b"image/png", TRANSFER)),
+            Err(EnvelopeError::UnsupportedContentType("image/png".to_string()))
+        );
+        assert!(matches!(verify(&transfer_script(b"text/plain", b"{\"p\":")), Err(EnvelopeError::InvalidJson(_))));
+
+        let body = br#"{"p":"brc-20","op":"mint","tick":"SYRON","amt":"1"}"#;
+        assert!(matches!(verify(&transfer_script(b"text/plain", body)), Err(EnvelopeError::NotBrc20Transfer(_))));
+
+        let body = br#"{"p":"brc20","op":"transfer","tick":"SYRON","amt":"1"}"#;
+        assert!(matches!(verify(&transfer_script(b"text/plain", body)), Err(EnvelopeError::NotBrc20Transfer(_))));
+
+        let body = br#"{"p":"brc-20","op":"transfer","tick":"ORDI","amt":"1"}"#;
+        assert_eq!(
+            verify(&transfer_script(b"text/plain", body)),
+            Err(EnvelopeError::TickerMismatch {
+                expected: "SYRON".to_string(),
+                found: "ORDI".to_string(),
+            })
+        );
+
+        // @dev BRC-20 amounts are strings
+        let body = br#"{"p":"brc-20","op":"transfer","tick":"SYRON","amt":15}"#;
+        assert!(matches!(verify(&transfer_script(b"text/plain", body)), Err(EnvelopeError::NotBrc20Transfer(_))));
+
+        let body = br#"{"p":"brc-20","op":"transfer","tick":"SYRON","amt":"1e3"}"#;
+        assert_eq!(
+            verify(&transfer_script(b"text/plain", body)),
+            Err(EnvelopeError::InvalidAmount(AmountError::Exponent))
+        );
+
+        let body = br#"{"p":"brc-20","op":"transfer","tick":"SYRON","amt":"0"}"#;
+        assert!(matches!(verify(&transfer_script(b"text/plain", body)), Err(EnvelopeError::NotBrc20Transfer(_))));
+    }
+
+    #[test]
+    fn test_check_indexed_transfer() {
+        let transfer = verify(&transfer_script(b"text/plain", TRANSFER)).unwrap();
+        let indexed = InscriptionInfo {
+            owner_address: "bc1qs9cg7lzt4xm9cqzdxzk8t7mzy3m6ud7cfd5ypp".to_string(),
+            ticker: "syron".to_string(),
+            op: "transfer".to_string(),
+            amt: "15.50".to_string(),
+            satpoint: None,
+        };
+        assert_eq!(check_indexed_transfer(&transfer, &indexed), Ok(()));
+
+        let over = InscriptionInfo { amt: "155".to_string(), ..indexed.clone() };
+        assert_eq!(
+            check_indexed_transfer(&transfer, &over),
+            Err(SyronError::InscriptionContentMismatch {
+                field: "amt".to_string(),
+                inscribed: "15.5".to_string(),
+                indexed: "155".to_string(),
+            })
+        );
+
+        let other_ticker = InscriptionInfo { ticker: "ORDI".to_string(), ..indexed.clone() };
+        assert!(matches!(
+            check_indexed_transfer(&transfer, &other_ticker),
+            Err(SyronError::InscriptionContentMismatch { .. })
+        ));
+    }
+}
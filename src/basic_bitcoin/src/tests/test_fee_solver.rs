@@ -0,0 +1,175 @@
+#[cfg(test)]
+mod tests {
+    use ic_ckbtc_minter_tyron::address::BitcoinAddress;
+
+    use crate::fee::solve_fee;
+    use crate::tests::fixtures::Rng;
+    use crate::types::SyronError;
+    use crate::vsize::{compute_fee, estimate_vsize, InputType};
+    use crate::MAX_FEE_ITERATIONS;
+
+    const CASES: usize = 2_000;
+
+    #[derive(Debug, PartialEq)]
+    struct Transaction {
+        inputs: usize,
+        input_value: u64,
+        change: u64,
+    }
+
+    /// Spends the UTXOs in order until the amount and the fee are covered, like the wallet builders.
+    fn build(utxos: &[u64], amount: u64, fee: u64) -> Result<Transaction, SyronError> {
+        let mut inputs = 0;
+        let mut input_value = 0;
+        for utxo in utxos {
+            inputs += 1;
+            input_value += utxo;
+            if input_value >= amount + fee {
+                break;
+            }
+        }
+        if input_value < amount + fee {
+            return Err(SyronError::InsufficientFunds {
+                available: input_value,
+                required: amount + fee,
+            });
+        }
+        Ok(Transaction {
+            inputs,
+            input_value,
+            change: input_value - amount - fee,
+        })
+    }
+
+    fn required_fee(transaction: &Transaction, fee_per_vbyte: u64) -> u64 {
+        let inputs = vec![InputType::P2wpkh; transaction.inputs];
+        let mut outputs = vec![BitcoinAddress::P2trV1([1; 32])];
+        if transaction.change > 0 {
+            outputs.push(BitcoinAddress::P2wpkhV0([2; 20]));
+        }
+        compute_fee(estimate_vsize(&inputs, &outputs), fee_per_vbyte)
+    }
+
+    struct Case {
+        utxos: Vec<u64>,
+        amount: u64,
+        fee_per_vbyte: u64,
+    }
+
+    fn random_case(rng: &mut Rng) -> Case {
+        let len = rng.range(1, 40) as usize;
+        // @dev Mostly small UTXOs, so that the fee often takes more inputs
+        let utxos: Vec<u64> = (0..len)
+            .map(|_| match rng.range(0, 3) {
+                0 => rng.range(546, 2_000),
+                1 => rng.range(2_000, 20_000),
+                _ => rng.range(20_000, 500_000),
+            })
+            .collect();
+        let total: u64 = utxos.iter().sum();
+        Case {
+            amount: rng.range(0, total),
+            fee_per_vbyte: rng.range(1_000, 200_000),
+            utxos,
+        }
+    }
+
+    #[test]
+    fn test_random_utxo_sets() {
+        let mut rng = Rng(0x5352_4f4e_4645_4553);
+        for _ in 0..CASES {
+            let case = random_case(&mut rng);
+            let mut fees = vec![];
+            let result = solve_fee(
+                0,
+                |fee| {
+                    fees.push(fee);
+                    build(&case.utxos, case.amount, fee)
+                },
+                |transaction| required_fee(transaction, case.fee_per_vbyte),
+            );
+
+            // @dev The fee strictly increases, so the solver ends within the cap
+            assert!(fees.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", fees);
+            assert!(fees.len() <= case.utxos.len() + 2, "{:?}", fees);
+
+            match result {
+                Ok((transaction, fee)) => {
+                    let required = required_fee(&transaction, case.fee_per_vbyte);
+                    assert!(required <= fee);
+                    assert_eq!(transaction.input_value, case.amount + fee + transaction.change);
+                    // @dev The fee is exact, unless the change was dropped by raising it
+                    if transaction.change > 0 {
+                        assert_eq!(required, fee);
+                    } else {
+                        assert!(fee - required <= compute_fee(31, case.fee_per_vbyte));
+                    }
+                    // @dev The same inputs give the same transaction
+                    let again = solve_fee(
+                        0,
+                        |fee| build(&case.utxos, case.amount, fee),
+                        |transaction| required_fee(transaction, case.fee_per_vbyte),
+                    );
+                    assert_eq!(again.unwrap(), (transaction, fee));
+                }
+                Err(SyronError::InsufficientFunds { available, required }) => {
+                    assert_eq!(available, case.utxos.iter().sum::<u64>());
+                    assert!(available < required);
+                }
+                Err(err) => panic!("unexpected error: {:?}", err),
+            }
+        }
+    }
+
+    #[test]
+    fn test_oscillating_transaction() {
+        // @dev A transaction that drops its change once the fee is high enough requires less than it pays;
+        // looking for the exact fee would alternate between the fees with and without change
+        let utxos = [10_000];
+        let fee_per_vbyte = 10_000;
+        let without_change = required_fee(&Transaction { inputs: 1, input_value: 10_000, change: 0 }, fee_per_vbyte);
+        let with_change = required_fee(&Transaction { inputs: 1, input_value: 10_000, change: 1 }, fee_per_vbyte);
+        let amount = 10_000 - with_change;
+
+        let (transaction, fee) = solve_fee(
+            0,
+            |fee| build(&utxos, amount, fee),
+            |transaction| required_fee(transaction, fee_per_vbyte),
+        )
+        .unwrap();
+        assert_eq!(transaction.change, 0);
+        assert_eq!(fee, with_change);
+        assert!(without_change < fee);
+    }
+
+    #[test]
+    fn test_not_converging() {
+        // @dev A fee that is never covered stops at the cap with a typed error
+        let mut calls = 0;
+        let result = solve_fee(
+            0,
+            |fee| {
+                calls += 1;
+                Ok(fee)
+            },
+            |fee| fee + 1,
+        );
+        assert_eq!(calls, MAX_FEE_ITERATIONS);
+        assert_eq!(
+            result,
+            Err(SyronError::FeeNotConverged {
+                iterations: MAX_FEE_ITERATIONS,
+                fee: MAX_FEE_ITERATIONS as u64,
+            })
+        );
+
+        // @dev Errors of the builder are returned as is
+        let result = solve_fee(
+            0,
+            |fee| if fee < 500 { Ok(fee) } else { Err(SyronError::InsufficientFunds { available: 400, required: fee }) },
+            |fee| fee + 300,
+        );
+        assert_eq!(result, Err(SyronError::InsufficientFunds { available: 400, required: 600 }));
+        assert_eq!(solve_fee(0, Ok, |_| 0), Ok((0, 0)));
+    }
+}
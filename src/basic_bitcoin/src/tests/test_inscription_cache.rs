@@ -0,0 +1,166 @@
+#[cfg(test)]
+mod tests {
+    use candid::Principal;
+    use ic_cdk::api::management_canister::bitcoin::{BitcoinNetwork, GetUtxosResponse, Outpoint, Utxo};
+
+    use crate::inscription_cache::{
+        cache_inscription, clear_inscription_cache, evict_least_recently_used, get_cached_inscription, get_confirmed_height,
+        get_network_chain_id, get_service_chain_id, invalidate_inscription, invalidate_spent,
+        list_cached_inscriptions, satpoint_outpoint,
+    };
+    use crate::provider::PROVIDERS;
+    use crate::types::{InscriptionCacheKey, InscriptionInfo, IndexerKind, Provider, ServiceProvider, SyronError};
+    use crate::{BTC_MAINNET_CHAIN_ID, BTC_TESTNET_CHAIN_ID};
+
+    const TXID: &str = "b61b0172d95e266c18aea0c624db987e971a5d6d4ebc2aaed85da4642d635735";
+
+    fn inscription(amt: &str) -> InscriptionInfo {
+        InscriptionInfo {
+            owner_address: "bc1qs9cg7lzt4xm9cqzdxzk8t7mzy3m6ud7cfd5ypp".to_string(),
+            ticker: "SYRON".to_string(),
+            op: "transfer".to_string(),
+            amt: amt.to_string(),
+            satpoint: Some(format!("{}:0:0", TXID)),
+        }
+    }
+
+    fn utxos(tip_height: u32, height: u32, vout: u32) -> GetUtxosResponse {
+        let mut txid = hex::decode(TXID).unwrap();
+        txid.reverse();
+        GetUtxosResponse {
+            utxos: vec![Utxo {
+                outpoint: Outpoint { txid, vout },
+                value: 546,
+                height,
+            }],
+            tip_block_hash: vec![],
+            tip_height,
+            next_page: None,
+        }
+    }
+
+    #[test]
+    fn test_satpoint_outpoint() {
+        assert_eq!(
+            satpoint_outpoint(&format!("{}:1:0", TXID.to_uppercase())),
+            Some((TXID.to_string(), 1))
+        );
+        assert_eq!(satpoint_outpoint(&format!("{}:0", TXID)), Some((TXID.to_string(), 0)));
+        assert_eq!(satpoint_outpoint(TXID), None);
+        assert_eq!(satpoint_outpoint("not-a-txid:0:0"), None);
+        assert_eq!(satpoint_outpoint(&format!("{}:x:0", TXID)), None);
+    }
+
+    #[test]
+    fn test_confirmed_height() {
+        // @dev The UTXO at height 100 has 6 confirmations at tip 105
+        assert_eq!(get_confirmed_height(&utxos(105, 100, 0), TXID, 0, 6), Some(100));
+        assert_eq!(get_confirmed_height(&utxos(104, 100, 0), TXID, 0, 6), None);
+        // @dev Another output of the transaction, or a spent UTXO
+        assert_eq!(get_confirmed_height(&utxos(105, 100, 1), TXID, 0, 6), None);
+        // @dev An unconfirmed UTXO has no height below the tip
+        assert_eq!(get_confirmed_height(&utxos(99, 100, 0), TXID, 0, 1), None);
+    }
+
+    #[test]
+    fn test_chain_ids() {
+        assert_eq!(get_network_chain_id(BitcoinNetwork::Mainnet), Some(BTC_MAINNET_CHAIN_ID));
+        assert_eq!(get_network_chain_id(BitcoinNetwork::Testnet), Some(BTC_TESTNET_CHAIN_ID));
+        assert_eq!(get_network_chain_id(BitcoinNetwork::Regtest), None);
+
+        PROVIDERS.with(|providers| {
+            providers.borrow_mut().insert(7, Provider {
+                provider_id: 7,
+                owner: Principal::anonymous(),
+                chain_id: BTC_TESTNET_CHAIN_ID,
+                kind: Some(IndexerKind::UniSat),
+                hostname: "open-api-testnet.unisat.io".to_string(),
+                credential_path: "v1/".to_string(),
+                credential_headers: vec![],
+                cycles_per_call: 0,
+                cycles_per_message_byte: 0,
+                cycles_owed: 0,
+                primary: false,
+            })
+        });
+        assert_eq!(get_service_chain_id(&ServiceProvider::Provider(7)), Some(BTC_TESTNET_CHAIN_ID));
+        assert_eq!(get_service_chain_id(&ServiceProvider::Provider(8)), None);
+        assert_eq!(get_service_chain_id(&ServiceProvider::Chain(BTC_MAINNET_CHAIN_ID)), Some(BTC_MAINNET_CHAIN_ID));
+    }
+
+    #[test]
+    fn test_cache_per_chain() {
+        let id = format!("{}i0", TXID);
+        assert!(cache_inscription(BTC_MAINNET_CHAIN_ID, &id, inscription("10"), 100, 1_000));
+
+        assert_eq!(get_cached_inscription(BTC_MAINNET_CHAIN_ID, &id, 2_000), Some(inscription("10")));
+        assert_eq!(get_cached_inscription(BTC_TESTNET_CHAIN_ID, &id, 2_000), None);
+
+        let entries = list_cached_inscriptions(0, 10);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].0, InscriptionCacheKey::new(BTC_MAINNET_CHAIN_ID, &id));
+        assert_eq!(entries[0].1.confirmed_height, 100);
+        assert_eq!(entries[0].1.confirmed_at, 1_000);
+        assert_eq!(entries[0].1.last_used_at, 2_000);
+    }
+
+    #[test]
+    fn test_oversized_result_is_not_cached() {
+        let mut oversized = inscription("10");
+        oversized.ticker = "x".repeat(1_000);
+        assert!(!cache_inscription(BTC_MAINNET_CHAIN_ID, "ai0", oversized, 100, 1_000));
+        assert!(list_cached_inscriptions(0, 10).is_empty());
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        cache_inscription(BTC_MAINNET_CHAIN_ID, "ai0", inscription("1"), 100, 10);
+        cache_inscription(BTC_MAINNET_CHAIN_ID, "bi0", inscription("2"), 100, 20);
+        cache_inscription(BTC_MAINNET_CHAIN_ID, "ci0", inscription("3"), 100, 30);
+        // @dev Reading the oldest entry makes it the most recently used
+        get_cached_inscription(BTC_MAINNET_CHAIN_ID, "ai0", 40);
+
+        assert_eq!(evict_least_recently_used(), Some(InscriptionCacheKey::new(BTC_MAINNET_CHAIN_ID, "bi0")));
+        assert_eq!(evict_least_recently_used(), Some(InscriptionCacheKey::new(BTC_MAINNET_CHAIN_ID, "ci0")));
+        assert_eq!(list_cached_inscriptions(0, 10).len(), 1);
+
+        // @dev Caching an entry again replaces its use in the LRU index
+        cache_inscription(BTC_MAINNET_CHAIN_ID, "bi0", inscription("2"), 100, 50);
+        cache_inscription(BTC_MAINNET_CHAIN_ID, "bi0", inscription("2"), 100, 5);
+        assert_eq!(evict_least_recently_used(), Some(InscriptionCacheKey::new(BTC_MAINNET_CHAIN_ID, "bi0")));
+        assert_eq!(evict_least_recently_used(), Some(InscriptionCacheKey::new(BTC_MAINNET_CHAIN_ID, "ai0")));
+        assert_eq!(evict_least_recently_used(), None);
+    }
+
+    #[test]
+    fn test_invalidation() {
+        let id = format!("{}i0", TXID);
+        cache_inscription(BTC_MAINNET_CHAIN_ID, &id, inscription("10"), 100, 10);
+        cache_inscription(BTC_TESTNET_CHAIN_ID, &id, inscription("10"), 100, 10);
+        cache_inscription(BTC_MAINNET_CHAIN_ID, &format!("{}i01", TXID), inscription("10"), 100, 10);
+        cache_inscription(BTC_MAINNET_CHAIN_ID, "ai0", inscription("1"), 100, 10);
+
+        // @dev A failed broadcast leaves the UTXO unspent
+        let failed: Result<(), SyronError> = Err(SyronError::SendTransactionFailed { reason: "rejected".to_string() });
+        invalidate_spent(TXID, &failed);
+        assert_eq!(list_cached_inscriptions(0, 10).len(), 4);
+
+        // @dev The inscription is removed from every chain, but not the ones sharing a prefix
+        invalidate_spent(TXID, &Ok(()));
+        let keys: Vec<InscriptionCacheKey> = list_cached_inscriptions(0, 10).into_iter().map(|(key, _)| key).collect();
+        assert_eq!(keys, vec![
+            InscriptionCacheKey::new(BTC_MAINNET_CHAIN_ID, "ai0"),
+            InscriptionCacheKey::new(BTC_MAINNET_CHAIN_ID, &format!("{}i01", TXID)),
+        ]);
+
+        // @dev An UTXO that is not found was spent elsewhere
+        let not_found: Result<(), SyronError> = Err(SyronError::UtxoNotFound { txid: "a".to_string() });
+        invalidate_spent("a", &not_found);
+        assert_eq!(invalidate_inscription("ai0"), 0);
+        assert_eq!(list_cached_inscriptions(0, 10).len(), 1);
+
+        // @dev A new quorum clears the results verified by the previous one
+        assert_eq!(clear_inscription_cache(), 1);
+        assert!(list_cached_inscriptions(0, 10).is_empty());
+    }
+}
@@ -0,0 +1,111 @@
+#[cfg(test)]
+mod tests {
+    use crate::journal::{
+        compensate_on_error, get_journal_entry, open_entry, plan_reconciliation, record_broadcast, record_error,
+        record_payment, record_step, stale_entries, Reconciliation,
+    };
+    use crate::registry::{claim_inscription, get_consumed_inscription};
+    use crate::types::{InscriptionOperation, JournalStep, LiquidationCollateral, SyronError};
+    use crate::{JOURNAL_MAX_ATTEMPTS, JOURNAL_STALE_AFTER_NS};
+
+    const TXID: &str = "5b1d8dd5e9c4a2f6a0a3b59a1f6a1c5a3e1e7d0f0cbb6e6b8f6f2b8b4b8e0a11";
+    const SSI: &str = "tb1qmxkzxw4uvvmrxl3vq8ejc4m7r2gpvjdvk0kvm0";
+    const LIQUIDATOR: &str = "tb1q4ce4kzgkl8qj9l7nd4klhjn2t8upgwv3s4hjdm";
+
+    #[test]
+    fn test_entry_records_each_step() {
+        let id = open_entry(InscriptionOperation::Mint, SSI, TXID, 1);
+        assert_eq!(open_entry(InscriptionOperation::Redemption, SSI, "bb", 1), id + 1);
+
+        record_step(id, JournalStep::InscriptionVerified, 2);
+        record_broadcast(id, "cc", 1_550_000_000, 3);
+        let entry = get_journal_entry(id).unwrap();
+        assert_eq!(entry.step, JournalStep::TransactionBroadcast);
+        assert_eq!(entry.btc_txid, Some("cc".to_string()));
+        assert_eq!(entry.ledger_amount, 1_550_000_000);
+        assert_eq!((entry.created_at, entry.updated_at), (1, 3));
+
+        record_step(id, JournalStep::LedgerUpdated, 4);
+        assert!(get_journal_entry(id).unwrap().step.is_final());
+    }
+
+    #[test]
+    fn test_only_stale_incomplete_entries_are_reconciled() {
+        let in_flight = open_entry(InscriptionOperation::Mint, SSI, "aa", JOURNAL_STALE_AFTER_NS);
+        let interrupted = open_entry(InscriptionOperation::Mint, SSI, "bb", 0);
+        let completed = open_entry(InscriptionOperation::Mint, SSI, "cc", 0);
+        record_step(completed, JournalStep::LedgerUpdated, 0);
+
+        let stale: Vec<u64> = stale_entries(JOURNAL_STALE_AFTER_NS).iter().map(|e| e.id).collect();
+        assert!(stale.contains(&interrupted));
+        assert!(!stale.contains(&in_flight));
+        assert!(!stale.contains(&completed));
+    }
+
+    #[test]
+    fn test_reconciliation_plan() {
+        let id = open_entry(InscriptionOperation::Redemption, SSI, TXID, 0);
+        assert_eq!(plan_reconciliation(&get_journal_entry(id).unwrap()), Reconciliation::Compensate);
+
+        // @dev The transaction may have been signed and broadcast: never release the inscription
+        record_step(id, JournalStep::InscriptionVerified, 0);
+        assert_eq!(plan_reconciliation(&get_journal_entry(id).unwrap()), Reconciliation::GiveUp);
+
+        record_broadcast(id, "cc", 1, 0);
+        let mut entry = get_journal_entry(id).unwrap();
+        assert_eq!(plan_reconciliation(&entry), Reconciliation::UpdateLedger);
+        entry.attempts = JOURNAL_MAX_ATTEMPTS;
+        assert_eq!(plan_reconciliation(&entry), Reconciliation::GiveUp);
+
+        // @dev A liquidation whose collateral was broadcast needs manual book-keeping of the ledger
+        let id = open_entry(InscriptionOperation::Liquidation, SSI, "dd", 0);
+        assert_eq!(plan_reconciliation(&get_journal_entry(id).unwrap()), Reconciliation::Compensate);
+        record_broadcast(id, "ee", 1, 0);
+        assert_eq!(plan_reconciliation(&get_journal_entry(id).unwrap()), Reconciliation::GiveUp);
+    }
+
+    #[test]
+    fn test_liquidation_payment_and_collateral() {
+        let collateral = LiquidationCollateral {
+            payment_txid: "aa".to_string(),
+            liquidator: LIQUIDATOR.to_string(),
+            sdb: SSI.to_string(),
+            amount: 10_000,
+        };
+        let id = open_entry(InscriptionOperation::Liquidation, SSI, TXID, 0);
+        record_payment(id, collateral.clone(), 1_550_000_000, 1);
+
+        // @dev The collateral transfer may be in flight, or was interrupted by a trap after its broadcast
+        let entry = get_journal_entry(id).unwrap();
+        assert_eq!(entry.step, JournalStep::PaymentBroadcast);
+        assert_eq!(plan_reconciliation(&entry), Reconciliation::GiveUp);
+
+        // @dev It failed before broadcasting: retry it
+        record_error(id, &SyronError::KeyNameEmpty, 2);
+        let mut entry = get_journal_entry(id).unwrap();
+        assert_eq!(plan_reconciliation(&entry), Reconciliation::TransferCollateral);
+        entry.attempts = JOURNAL_MAX_ATTEMPTS;
+        assert_eq!(plan_reconciliation(&entry), Reconciliation::GiveUp);
+
+        // @dev Both transactions are kept
+        record_broadcast(id, "bb", 1_550_000_000, 3);
+        let entry = get_journal_entry(id).unwrap();
+        assert_eq!(entry.btc_txid, Some("bb".to_string()));
+        assert_eq!(entry.collateral.map(|collateral| collateral.payment_txid), Some("aa".to_string()));
+    }
+
+    #[test]
+    fn test_failure_before_broadcast_is_compensated() {
+        const TXID: &str = "0a11e0b8b4b8f2f6b8e6bbc0f0d7e1e3a5c1a6f1a9b5a3a0f6a2c4e9d5d81d5b";
+        claim_inscription(TXID, InscriptionOperation::Mint, SSI, 0, 0).unwrap();
+        let id = open_entry(InscriptionOperation::Mint, SSI, TXID, 0);
+
+        let result: Result<(), SyronError> = compensate_on_error(id, TXID, Err(SyronError::KeyNameEmpty), 1);
+        assert_eq!(result, Err(SyronError::KeyNameEmpty));
+
+        let entry = get_journal_entry(id).unwrap();
+        assert_eq!(entry.step, JournalStep::Compensated);
+        assert_eq!(entry.last_error, Some(format!("{}", SyronError::KeyNameEmpty)));
+        assert_eq!(get_consumed_inscription(&format!("{}i0", TXID)), None);
+    }
+}
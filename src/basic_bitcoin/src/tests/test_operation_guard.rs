@@ -0,0 +1,65 @@
+#[cfg(test)]
+mod tests {
+    use crate::guard::{is_locked, LockKey, OperationGuard};
+    use crate::types::SyronError;
+
+    const SSI: &str = "tb1qmxkzxw4uvvmrxl3vq8ejc4m7r2gpvjdvk0kvm0";
+    const LIQUIDATOR: &str = "tb1q4ce4kzgkl8qj9l7nd4klhjn2t8upgwv3s4hjdm";
+
+    #[test]
+    fn test_second_operation_for_same_ssi_is_rejected() {
+        // @dev The first call holds its guard across its await points; the second call arrives in between.
+        let first = OperationGuard::ssi(SSI).unwrap();
+        assert_eq!(
+            OperationGuard::ssi(SSI).unwrap_err(),
+            SyronError::AlreadyProcessing { ssi: SSI.to_string() }
+        );
+
+        // Other accounts are not affected.
+        let other = OperationGuard::ssi(LIQUIDATOR).unwrap();
+        drop(other);
+
+        drop(first);
+        assert!(!is_locked(&LockKey::Ssi(SSI.to_string())));
+        assert!(OperationGuard::ssi(SSI).is_ok());
+    }
+
+    #[test]
+    fn test_liquidation_locks_debtor_and_liquidator() {
+        let liquidation = OperationGuard::liquidation(SSI, LIQUIDATOR).unwrap();
+        assert!(OperationGuard::ssi(SSI).is_err());
+        assert!(OperationGuard::liquidation("tb1qdebtor", LIQUIDATOR).is_err());
+
+        // A rejected guard does not leave its other keys locked.
+        assert!(!is_locked(&LockKey::Ssi("tb1qdebtor".to_string())));
+
+        // The liquidator's SDB pays the liquidation, so its own SSI operations wait.
+        assert!(OperationGuard::ssi(LIQUIDATOR).is_err());
+
+        drop(liquidation);
+        assert!(!is_locked(&LockKey::Ssi(LIQUIDATOR.to_string())));
+        assert!(OperationGuard::liquidation(SSI, LIQUIDATOR).is_ok());
+
+        // @dev Liquidating oneself locks the account once
+        let own = OperationGuard::liquidation(SSI, SSI).unwrap();
+        drop(own);
+        assert!(!is_locked(&LockKey::Ssi(SSI.to_string())));
+    }
+
+    #[test]
+    fn test_guard_is_released_on_early_return() {
+        fn operation(fail: bool) -> Result<(), SyronError> {
+            let _guard = OperationGuard::ssi(SSI)?;
+            assert!(is_locked(&LockKey::Ssi(SSI.to_string())));
+            if fail {
+                return Err(SyronError::ZeroLoan);
+            }
+            Ok(())
+        }
+
+        assert_eq!(operation(true), Err(SyronError::ZeroLoan));
+        assert!(!is_locked(&LockKey::Ssi(SSI.to_string())));
+        assert_eq!(operation(false), Ok(()));
+        assert!(!is_locked(&LockKey::Ssi(SSI.to_string())));
+    }
+}
@@ -0,0 +1,143 @@
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bitcoin::{
+        consensus::serialize,
+        secp256k1::{Message, Secp256k1},
+        util::sighash::SighashCache,
+        Address, EcdsaSighashType, PrivateKey, Script, Witness,
+    };
+
+    use crate::bip322::{to_sign, to_spend};
+    use crate::proof::{get_last_nonce, operation_message, verify_proof, Authorization};
+    use crate::types::{OwnershipProof, SyronError};
+    use crate::PROOF_MAX_VALIDITY_NS;
+
+    const WIF: &str = "L3VFeEujGtevx9w18HD1fhRbCH67Az2dpCymeRE1SoPK6XQtaN2k";
+    const SSI: &str = "bc1q9vza2e8x573nczrlzms0wvx3gsqjx7vavgkx0l";
+    const NOW: u64 = 1_700_000_000_000_000_000;
+    const RECIPIENT: &str = "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq";
+
+    /// Signs the message with the SSI's key (BIP-322 simple format).
+    fn sign(message: &str) -> String {
+        let secp = Secp256k1::new();
+        let private_key = PrivateKey::from_wif(WIF).unwrap();
+        let public_key = private_key.public_key(&secp);
+
+        let to_spend = to_spend(&Address::from_str(SSI).unwrap().script_pubkey(), message.as_bytes());
+        let unsigned = to_sign(&to_spend, Witness::new());
+        let sighash = SighashCache::new(&unsigned)
+            .segwit_signature_hash(0, &Script::new_p2pkh(&public_key.pubkey_hash()), 0, EcdsaSighashType::All)
+            .unwrap();
+        let signature = secp.sign_ecdsa(&Message::from_slice(&sighash[..]).unwrap(), &private_key.inner);
+        let mut signature = signature.serialize_der().to_vec();
+        signature.push(EcdsaSighashType::All as u8);
+
+        base64::encode(serialize(&Witness::from_vec(vec![signature, public_key.to_bytes()])))
+    }
+
+    fn proof(operation: &str, max_amount: u64, nonce: u64, expiry: u64) -> OwnershipProof {
+        targeted_proof(operation, None, max_amount, nonce, expiry)
+    }
+
+    fn targeted_proof(operation: &str, target: Option<&str>, max_amount: u64, nonce: u64, expiry: u64) -> OwnershipProof {
+        OwnershipProof {
+            signature: sign(&operation_message(operation, SSI, target, max_amount, nonce, expiry)),
+            nonce,
+            expiry,
+            max_amount,
+        }
+    }
+
+    #[test]
+    fn test_canonical_message() {
+        assert_eq!(
+            operation_message("redeem_btc", SSI, None, 1_550_000_000, 7, NOW),
+            format!("Syron operation: redeem_btc\nSSI: {}\nAmount: 1550000000\nNonce: 7\nExpiry: {}", SSI, NOW)
+        );
+        assert_eq!(
+            operation_message("send_syron", SSI, Some(RECIPIENT), 1_000, 7, NOW),
+            format!(
+                "Syron operation: send_syron\nSSI: {}\nTarget: {}\nAmount: 1000\nNonce: 7\nExpiry: {}",
+                SSI, RECIPIENT, NOW
+            )
+        );
+    }
+
+    #[test]
+    fn test_valid_proof_consumes_its_nonce() {
+        let proof = proof("redeem_btc", 1_000, 1, NOW + 1);
+        assert_eq!(verify_proof("redeem_btc", SSI, None, &proof, NOW), Ok(()));
+        assert_eq!(get_last_nonce(SSI), Some(1));
+
+        // @dev The same proof cannot be replayed.
+        assert_eq!(
+            verify_proof("redeem_btc", SSI, None, &proof, NOW),
+            Err(SyronError::NonceAlreadyUsed { nonce: 1, last: 1 })
+        );
+    }
+
+    #[test]
+    fn test_proof_is_bound_to_the_operation() {
+        let proof = proof("redeem_btc", 1_000, 1_000, NOW + 1);
+        assert!(matches!(
+            verify_proof("liquidate", SSI, None, &proof, NOW),
+            Err(SyronError::InvalidSignature { .. })
+        ));
+
+        let mut tampered = proof.clone();
+        tampered.max_amount = 2_000;
+        assert!(matches!(
+            verify_proof("redeem_btc", SSI, None, &tampered, NOW),
+            Err(SyronError::InvalidSignature { .. })
+        ));
+        assert_ne!(get_last_nonce(SSI), Some(1_000));
+    }
+
+    #[test]
+    fn test_proof_is_bound_to_the_recipient() {
+        let proof = targeted_proof("send_syron", Some(RECIPIENT), 1_000, 2_000, NOW + 1);
+        assert!(matches!(
+            verify_proof("send_syron", SSI, Some(SSI), &proof, NOW),
+            Err(SyronError::InvalidSignature { .. })
+        ));
+        assert!(matches!(
+            verify_proof("send_syron", SSI, None, &proof, NOW),
+            Err(SyronError::InvalidSignature { .. })
+        ));
+        assert_eq!(verify_proof("send_syron", SSI, Some(RECIPIENT), &proof, NOW), Ok(()));
+    }
+
+    #[test]
+    fn test_expiry() {
+        let expired = proof("redeem_btc", 1_000, 1, NOW - 1);
+        assert_eq!(
+            verify_proof("redeem_btc", SSI, None, &expired, NOW),
+            Err(SyronError::SignatureExpired { expiry: NOW - 1 })
+        );
+
+        let too_long = proof("redeem_btc", 1_000, 1, NOW + PROOF_MAX_VALIDITY_NS + 1);
+        assert!(matches!(
+            verify_proof("redeem_btc", SSI, None, &too_long, NOW),
+            Err(SyronError::InvalidSignature { .. })
+        ));
+    }
+
+    #[test]
+    fn test_signature_threshold() {
+        let threshold = Some(1_000);
+        assert_eq!(Authorization::with_siwb().check_amount(1_000, threshold), Ok(()));
+        assert_eq!(
+            Authorization::with_siwb().check_amount(1_001, threshold),
+            Err(SyronError::SignatureRequired { amount: 1_001, threshold: 1_000 })
+        );
+        assert_eq!(Authorization::with_siwb().check_amount(1_001, None), Ok(()));
+
+        assert_eq!(Authorization::with_proof(5_000).check_amount(5_000, threshold), Ok(()));
+        assert!(matches!(
+            Authorization::with_proof(5_000).check_amount(5_001, None),
+            Err(SyronError::InvalidSignature { .. })
+        ));
+    }
+}
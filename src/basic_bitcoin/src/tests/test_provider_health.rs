@@ -0,0 +1,106 @@
+#[cfg(test)]
+mod tests {
+    use candid::Principal;
+
+    use crate::health::{failover_providers, get_provider_health, is_healthy, record_failure, record_success};
+    use crate::http::{is_provider_failure, is_transport_error};
+    use crate::tests::fixtures::{insert_provider, provider};
+    use crate::types::{HttpOutcallError, Provider, ServiceProvider, SyronError};
+    use crate::{PROVIDER_MAX_CONSECUTIVE_FAILURES, PROVIDER_RETRY_AFTER_NS};
+
+    fn fail(provider_id: u64, times: u32, now: u64) {
+        for _ in 0..times {
+            record_failure(provider_id, "403 Forbidden", now);
+        }
+    }
+
+    #[test]
+    fn test_health_stats() {
+        record_success(4001, 1_500, 10);
+        record_failure(4001, &"x".repeat(1_000), 20);
+
+        let health = get_provider_health(4001);
+        assert_eq!(health.successes, 1);
+        assert_eq!(health.failures, 1);
+        assert_eq!(health.consecutive_failures, 1);
+        assert_eq!(health.last_latency_ns, Some(1_500));
+        assert_eq!(health.last_success_at, Some(10));
+        assert_eq!(health.last_failure_at, Some(20));
+        assert_eq!(health.last_error.unwrap().len(), 256);
+
+        // @dev A success resets the consecutive failures
+        record_success(4001, 900, 30);
+        assert_eq!(get_provider_health(4001).consecutive_failures, 0);
+    }
+
+    #[test]
+    fn test_unhealthy_provider_is_retried_later() {
+        fail(4002, PROVIDER_MAX_CONSECUTIVE_FAILURES - 1, 100);
+        assert!(is_healthy(&get_provider_health(4002), 100));
+
+        fail(4002, 1, 100);
+        assert!(!is_healthy(&get_provider_health(4002), 100));
+        assert!(is_healthy(&get_provider_health(4002), 100 + PROVIDER_RETRY_AFTER_NS));
+    }
+
+    #[test]
+    fn test_failover_order() {
+        insert_provider(provider(4010, Principal::anonymous(), 4000));
+        insert_provider(Provider {
+            primary: true,
+            ..provider(4011, Principal::anonymous(), 4000)
+        });
+        insert_provider(provider(4012, Principal::anonymous(), 4000));
+        insert_provider(provider(4013, Principal::anonymous(), 4001));
+
+        let service = ServiceProvider::Chain(4000);
+        // @dev The primary provider first, then the others of the same chain
+        assert_eq!(failover_providers(&service, 0), vec![4011, 4010, 4012]);
+
+        fail(4011, PROVIDER_MAX_CONSECUTIVE_FAILURES, 0);
+        assert_eq!(failover_providers(&service, 0), vec![4010, 4012]);
+
+        // @dev With no healthy provider, the first one is still tried
+        fail(4010, PROVIDER_MAX_CONSECUTIVE_FAILURES, 0);
+        fail(4012, PROVIDER_MAX_CONSECUTIVE_FAILURES, 0);
+        assert_eq!(failover_providers(&service, 0), vec![4011]);
+
+        // @dev A given provider is never replaced
+        assert_eq!(failover_providers(&ServiceProvider::Provider(4011), 0), vec![4011]);
+    }
+
+    #[test]
+    fn test_provider_failures() {
+        let unavailable: Result<(), SyronError> = Err(SyronError::IndexerUnavailable {
+            provider: 0,
+            reason: "timeout".to_string(),
+        });
+        assert!(is_provider_failure(&unavailable));
+        assert!(!is_provider_failure(&Ok::<(), SyronError>(())));
+        assert!(!is_provider_failure(&Err::<(), SyronError>(SyronError::ZeroLoan)));
+
+        // @dev The indexer's rejection is not a reason to ask another one
+        let not_found: Result<(), SyronError> = Err(SyronError::InvalidIndexerResponse {
+            provider: 0,
+            reason: "inscription not found".to_string(),
+        });
+        assert!(!is_provider_failure(&not_found));
+    }
+
+    #[test]
+    fn test_transport_errors() {
+        let status = |status| HttpOutcallError::InvalidHttpJsonRpcResponse {
+            status,
+            body: "".to_string(),
+            parsing_error: None,
+        };
+        assert!(is_transport_error(&HttpOutcallError::IcError {
+            code: ic_cdk::api::call::RejectionCode::SysTransient,
+            message: "Timeout expired".to_string(),
+        }));
+        assert!(is_transport_error(&status(503)));
+        assert!(is_transport_error(&status(429)));
+        assert!(!is_transport_error(&status(404)));
+        assert!(!is_transport_error(&status(400)));
+    }
+}
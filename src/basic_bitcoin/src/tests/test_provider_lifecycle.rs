@@ -0,0 +1,192 @@
+#[cfg(test)]
+mod tests {
+    use candid::Principal;
+    use ic_cdk::api::management_canister::http_request::HttpHeader;
+
+    use crate::credentials::get_credentials;
+    use crate::indexer::provider_kind;
+    use crate::provider::{
+        get_default_providers, get_missing_default_providers, manage_provider, migrate_provider_hostnames, set_primary_provider, set_service_provider, unregister_provider, update_provider, PROVIDERS,
+        SERVICE_PROVIDER_MAP,
+    };
+    use crate::tests::fixtures::{insert_provider, provider as fixture};
+    use crate::types::{
+        IndexerKind, ManageProviderArgs, Provider, ProviderError, ServiceProvider, StorableServiceProvider,
+        UpdateProviderArgs,
+    };
+
+    fn principal(id: u8) -> Principal {
+        Principal::from_slice(&[id; 29])
+    }
+
+    fn get_provider(provider_id: u64) -> Option<Provider> {
+        PROVIDERS.with(|providers| providers.borrow().get(&provider_id))
+    }
+
+    #[test]
+    fn test_provider_kind_of_stored_providers() {
+        insert_provider(fixture(1100, principal(20), 1100));
+        let mut provider = get_provider(1100).unwrap();
+        assert_eq!(provider_kind(&provider), IndexerKind::UniSat);
+
+        // @dev Providers stored before the kind was recorded are matched by their default hostname
+        provider.kind = None;
+        assert_eq!(provider_kind(&provider), IndexerKind::UniSat);
+        provider.hostname = "api.bestinslot.xyz".to_string();
+        assert_eq!(provider_kind(&provider), IndexerKind::BestInSlot);
+        provider.hostname = "btc.tyrondao.org".to_string();
+        assert_eq!(provider_kind(&provider), IndexerKind::Tyron);
+    }
+
+    #[test]
+    fn test_default_providers_are_added_once() {
+        // @dev The defaults ship without placeholder API keys
+        assert!(get_default_providers().iter().all(|provider| provider.credential_headers.is_none()));
+
+        let missing = get_missing_default_providers().len();
+        insert_provider(fixture(1200, principal(20), 0));
+        let missing_after = get_missing_default_providers();
+        assert_eq!(missing_after.len(), missing - 1);
+        assert!(!missing_after.iter().any(|provider| provider.chain_id == 0 && provider.hostname == "open-api.unisat.io"));
+    }
+
+    #[test]
+    fn test_upgrade_with_slash_suffixed_hostnames() {
+        // @dev The defaults as stored before the hostnames were bare
+        let stored = get_missing_default_providers();
+        for (index, provider) in stored.iter().enumerate() {
+            insert_provider(Provider {
+                hostname: format!("{}/", provider.hostname),
+                ..fixture(1300 + index as u64, principal(20), provider.chain_id)
+            });
+        }
+        assert_eq!(get_missing_default_providers().len(), stored.len());
+
+        assert_eq!(migrate_provider_hostnames(), stored.len());
+        assert_eq!(get_provider(1300).unwrap().hostname, stored[0].hostname);
+        assert!(get_missing_default_providers().is_empty());
+        assert_eq!(migrate_provider_hostnames(), 0);
+    }
+
+    fn no_changes(provider_id: u64) -> UpdateProviderArgs {
+        UpdateProviderArgs {
+            provider_id,
+            hostname: None,
+            credential_path: None,
+            credential_headers: None,
+            cycles_per_call: None,
+            cycles_per_message_byte: None,
+        }
+    }
+
+    #[test]
+    fn test_owner_rotates_credentials() {
+        let owner = principal(21);
+        insert_provider(fixture(1001, owner, 1001));
+
+        update_provider(owner, false, UpdateProviderArgs {
+            credential_headers: Some(vec![HttpHeader {
+                name: "Authorization".to_string(),
+                value: "Bearer secret".to_string(),
+            }]),
+            cycles_per_call: Some(1_000),
+            ..no_changes(1001)
+        })
+        .unwrap();
+
+        let provider = get_provider(1001).unwrap();
+        assert_eq!(get_credentials(1001)[0].value, "Bearer secret");
+        assert_eq!(provider.cycles_per_call, 1_000);
+        // @dev Fields that are not given keep their value
+        assert_eq!(provider.hostname, "open-api.unisat.io");
+    }
+
+    #[test]
+    fn test_only_owner_or_manager_can_change_a_provider() {
+        let owner = principal(22);
+        let stranger = principal(23);
+        insert_provider(fixture(1002, owner, 1002));
+
+        assert_eq!(update_provider(stranger, false, no_changes(1002)), Err(ProviderError::NoPermission));
+        assert_eq!(unregister_provider(stranger, false, 1002), Err(ProviderError::NoPermission));
+        assert!(get_provider(1002).is_some());
+
+        // @dev Managers can change any provider
+        assert_eq!(update_provider(stranger, true, no_changes(1002)), Ok(()));
+        assert_eq!(update_provider(owner, false, no_changes(9999)), Err(ProviderError::ProviderNotFound));
+    }
+
+    #[test]
+    fn test_set_primary_provider() {
+        let owner = principal(24);
+        insert_provider(fixture(1003, owner, 1003));
+        insert_provider(fixture(1004, owner, 1003));
+
+        set_primary_provider(1003).unwrap();
+        assert!(get_provider(1003).unwrap().primary);
+
+        // @dev There is a single primary provider per chain
+        set_primary_provider(1004).unwrap();
+        assert!(!get_provider(1003).unwrap().primary);
+        assert!(get_provider(1004).unwrap().primary);
+    }
+
+    #[test]
+    fn test_manage_provider_rewires_the_service() {
+        insert_provider(fixture(1005, principal(26), 1005));
+
+        manage_provider(ManageProviderArgs {
+            provider_id: 1005,
+            chain_id: Some(1006),
+            primary: Some(true),
+            service: Some(ServiceProvider::Chain(1006)),
+        })
+        .unwrap();
+
+        let provider = get_provider(1005).unwrap();
+        assert_eq!(provider.chain_id, 1006);
+        assert!(provider.primary);
+        let routed = SERVICE_PROVIDER_MAP
+            .with(|map| map.borrow().get(&StorableServiceProvider::new(&ServiceProvider::Chain(1006))));
+        assert_eq!(routed, Some(1005));
+    }
+
+    #[test]
+    fn test_manage_provider_rejects_a_chain_mismatch() {
+        insert_provider(fixture(1008, principal(28), 1008));
+
+        let result = manage_provider(ManageProviderArgs {
+            provider_id: 1008,
+            chain_id: None,
+            primary: Some(true),
+            service: Some(ServiceProvider::Chain(1009)),
+        });
+        assert_eq!(
+            result,
+            Err(ProviderError::ChainMismatch {
+                service_chain_id: 1009,
+                provider_chain_id: 1008,
+            })
+        );
+        // @dev The provider is left unchanged
+        assert!(!get_provider(1008).unwrap().primary);
+        assert!(SERVICE_PROVIDER_MAP
+            .with(|map| map.borrow().get(&StorableServiceProvider::new(&ServiceProvider::Chain(1009))))
+            .is_none());
+    }
+
+    #[test]
+    fn test_unregister_provider_removes_its_services() {
+        let owner = principal(27);
+        insert_provider(fixture(1007, owner, 1007));
+        let service = ServiceProvider::Provider(1007);
+        set_service_provider(&service, &get_provider(1007).unwrap()).unwrap();
+
+        unregister_provider(owner, false, 1007).unwrap();
+        assert!(get_provider(1007).is_none());
+        assert!(SERVICE_PROVIDER_MAP
+            .with(|map| map.borrow().get(&StorableServiceProvider::new(&service)))
+            .is_none());
+        assert_eq!(unregister_provider(owner, false, 1007), Err(ProviderError::ProviderNotFound));
+    }
+}
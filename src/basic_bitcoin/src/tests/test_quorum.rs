@@ -0,0 +1,132 @@
+#[cfg(test)]
+mod tests {
+    use crate::quorum::{check_syron_transfer, get_quorum_config, set_quorum_config, tally_quorum};
+    use crate::types::{InscriptionInfo, ProviderObservation, QuorumConfig, SyronError};
+
+    const SDB: &str = "tb1qmxkzxw4uvvmrxl3vq8ejc4m7r2gpvjdvk0kvm0";
+
+    fn inscription(owner_address: &str, ticker: &str, amt: &str) -> InscriptionInfo {
+        InscriptionInfo {
+            owner_address: owner_address.to_string(),
+            ticker: ticker.to_string(),
+            op: "transfer".to_string(),
+            amt: amt.to_string(),
+            satpoint: None,
+        }
+    }
+
+    fn observed(provider_id: u64, inscription: InscriptionInfo) -> ProviderObservation {
+        ProviderObservation {
+            provider_id,
+            inscription: Some(inscription),
+            error: None,
+        }
+    }
+
+    fn failed(provider_id: u64) -> ProviderObservation {
+        ProviderObservation {
+            provider_id,
+            inscription: None,
+            error: Some("indexer 2 unavailable".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_quorum_reached() {
+        let observations = vec![
+            observed(0, inscription(SDB, "SYRON", "15.5")),
+            observed(2, inscription(SDB, "syron", "15.50")),
+            failed(4),
+        ];
+
+        let result = tally_quorum(observations, 2).expect("quorum should be reached");
+        assert_eq!(result.owner_address, SDB);
+        assert_eq!(result.ticker, "SYRON");
+    }
+
+    #[test]
+    fn test_quorum_disagreement_report() {
+        let observations = vec![
+            observed(0, inscription(SDB, "SYRON", "15.5")),
+            observed(2, inscription(SDB, "SYRON", "16")),
+            observed(4, inscription("tb1qattacker", "SYRON", "15.5")),
+        ];
+
+        let report = tally_quorum(observations.clone(), 2).expect_err("providers disagree");
+        assert_eq!(report.required, 2);
+        assert_eq!(report.agreeing, 1);
+        assert_eq!(report.observations, observations);
+    }
+
+    #[test]
+    fn test_quorum_failures_do_not_count() {
+        let observations = vec![
+            observed(0, inscription(SDB, "SYRON", "15.5")),
+            failed(2),
+            failed(4),
+        ];
+
+        let report = tally_quorum(observations, 2).expect_err("only one provider answered");
+        assert_eq!(report.agreeing, 1);
+    }
+
+    #[test]
+    fn test_quorum_on_op() {
+        let mut mint = inscription(SDB, "SYRON", "15.5");
+        mint.op = "mint".to_string();
+        let observations = vec![observed(0, inscription(SDB, "SYRON", "15.5")), observed(2, mint)];
+
+        assert!(tally_quorum(observations, 2).is_err());
+    }
+
+    #[test]
+    fn test_syron_transfer() {
+        assert_eq!(check_syron_transfer(&inscription(SDB, "syron", "15.5")), Ok(()));
+        assert_eq!(
+            check_syron_transfer(&inscription(SDB, "ordi", "15.5")),
+            Err(SyronError::UnexpectedInscription {
+                field: "ticker".to_string(),
+                expected: "SYRON".to_string(),
+                found: "ordi".to_string(),
+            })
+        );
+
+        let mut deploy = inscription(SDB, "SYRON", "15.5");
+        deploy.op = "deploy".to_string();
+        assert!(matches!(
+            check_syron_transfer(&deploy),
+            Err(SyronError::UnexpectedInscription { field, .. }) if field == "op"
+        ));
+    }
+
+    #[test]
+    fn test_quorum_rejects_split_majorities() {
+        let observations = vec![
+            observed(0, inscription(SDB, "SYRON", "15.5")),
+            observed(1, inscription(SDB, "SYRON", "15.5")),
+            observed(2, inscription(SDB, "SYRON", "20")),
+            observed(3, inscription(SDB, "SYRON", "20")),
+        ];
+
+        assert!(tally_quorum(observations, 2).is_err());
+    }
+
+    #[test]
+    fn test_invalid_quorum_config() {
+        let quorum = QuorumConfig {
+            providers: vec![],
+            min_agreement: 0,
+        };
+        assert!(matches!(set_quorum_config(Some(quorum)), Err(SyronError::InvalidQuorum { .. })));
+
+        // @dev The providers must be registered
+        let quorum = QuorumConfig {
+            providers: vec![7, 8],
+            min_agreement: 2,
+        };
+        assert!(matches!(set_quorum_config(Some(quorum)), Err(SyronError::InvalidQuorum { .. })));
+        assert_eq!(get_quorum_config(), None);
+
+        assert_eq!(set_quorum_config(None), Ok(()));
+    }
+}
@@ -0,0 +1,65 @@
+#[cfg(test)]
+mod tests {
+    use crate::registry::{
+        claim_inscription, get_consumed_inscription, list_consumed_inscriptions, release_inscription,
+        release_on_error, settle_inscription,
+    };
+    use crate::types::{InscriptionOperation, SyronError};
+
+    const TXID: &str = "5b1d8dd5e9c4a2f6a0a3b59a1f6a1c5a3e1e7d0f0cbb6e6b8f6f2b8b4b8e0a11";
+    const SSI: &str = "tb1qmxkzxw4uvvmrxl3vq8ejc4m7r2gpvjdvk0kvm0";
+
+    #[test]
+    fn test_inscription_is_consumed_once() {
+        assert_eq!(claim_inscription(TXID, InscriptionOperation::Mint, SSI, 1_550_000_000, 1), Ok(()));
+        assert_eq!(
+            claim_inscription(TXID, InscriptionOperation::Redemption, SSI, 0, 2),
+            Err(SyronError::InscriptionAlreadyConsumed {
+                inscription_id: format!("{}i0", TXID),
+                operation: InscriptionOperation::Mint,
+            })
+        );
+
+        let consumed = get_consumed_inscription(&format!("{}i0", TXID)).unwrap();
+        assert_eq!(consumed.operation, InscriptionOperation::Mint);
+        assert_eq!(consumed.ssi, SSI);
+        assert_eq!(consumed.timestamp, 1);
+    }
+
+    #[test]
+    fn test_settle_records_inscribed_amount() {
+        const TXID: &str = "settled";
+        claim_inscription(TXID, InscriptionOperation::Liquidation, SSI, 0, 1).unwrap();
+        settle_inscription(TXID, 1_550_000_000);
+        assert_eq!(get_consumed_inscription(&format!("{}i0", TXID)).unwrap().amount, 1_550_000_000);
+    }
+
+    #[test]
+    fn test_failed_operation_releases_inscription() {
+        const TXID: &str = "released";
+        claim_inscription(TXID, InscriptionOperation::Mint, SSI, 0, 1).unwrap();
+        let failed: Result<(), SyronError> = release_on_error(TXID, Err(SyronError::ZeroLoan));
+        assert_eq!(failed, Err(SyronError::ZeroLoan));
+        assert_eq!(get_consumed_inscription(&format!("{}i0", TXID)), None);
+        assert_eq!(claim_inscription(TXID, InscriptionOperation::Mint, SSI, 0, 2), Ok(()));
+
+        assert_eq!(release_on_error(TXID, Ok(())), Ok(()));
+        assert!(get_consumed_inscription(&format!("{}i0", TXID)).is_some());
+    }
+
+    #[test]
+    fn test_list_consumed_inscriptions() {
+        let txids = ["aa", "bb", "cc"];
+        for txid in txids {
+            claim_inscription(txid, InscriptionOperation::Mint, SSI, 0, 1).unwrap();
+        }
+        release_inscription("bb");
+
+        let listed: Vec<String> = list_consumed_inscriptions(0, u64::MAX).into_iter().map(|(id, _)| id).collect();
+        assert!(listed.contains(&"aai0".to_string()));
+        assert!(!listed.contains(&"bbi0".to_string()));
+        assert!(listed.contains(&"cci0".to_string()));
+        assert_eq!(list_consumed_inscriptions(0, 1).len(), 1);
+        assert_eq!(list_consumed_inscriptions(listed.len() as u64 - 1, 10).len(), 1);
+    }
+}
@@ -0,0 +1,99 @@
+#[cfg(test)]
+mod tests {
+    use ic_cdk::api::call::RejectionCode;
+
+    use crate::http::next_max_response_bytes;
+    use crate::response_size::{
+        decayed_max_response_bytes, get_cached_response_bytes, get_max_response_bytes_cap, list_response_sizes,
+        record_response_size, set_max_response_bytes_cap,
+    };
+    use crate::types::{HttpOutcallError, IndexerEndpoint, ResponseSizeStats, SyronError};
+    use crate::{DEFAULT_MAX_RESPONSE_BYTES, MAX_RESPONSE_BYTES_CAP, RESPONSE_BYTES_DECAY_CALLS};
+
+    #[test]
+    fn test_escalation_up_to_the_cap() {
+        let mut sizes = vec![DEFAULT_MAX_RESPONSE_BYTES];
+        while let Some(next) = next_max_response_bytes(*sizes.last().unwrap(), 100_000) {
+            sizes.push(next);
+        }
+        assert_eq!(sizes, vec![2_048, 8_192, 32_768, 100_000]);
+        assert_eq!(next_max_response_bytes(MAX_RESPONSE_BYTES_CAP, MAX_RESPONSE_BYTES_CAP), None);
+    }
+
+    #[test]
+    fn test_succeeded_size_is_cached() {
+        assert_eq!(get_cached_response_bytes(0, IndexerEndpoint::Balance), DEFAULT_MAX_RESPONSE_BYTES);
+
+        // @dev A failed escalation is recorded but not cached
+        record_response_size(0, IndexerEndpoint::Balance, None, 5, 1_000);
+        assert_eq!(get_cached_response_bytes(0, IndexerEndpoint::Balance), DEFAULT_MAX_RESPONSE_BYTES);
+
+        record_response_size(0, IndexerEndpoint::Balance, Some(32_768), 2, 500);
+        assert_eq!(get_cached_response_bytes(0, IndexerEndpoint::Balance), 32_768);
+        assert_eq!(get_cached_response_bytes(0, IndexerEndpoint::Inscription), DEFAULT_MAX_RESPONSE_BYTES);
+
+        // @dev Another provider starts from the default size
+        assert_eq!(get_cached_response_bytes(1, IndexerEndpoint::Balance), DEFAULT_MAX_RESPONSE_BYTES);
+        assert_eq!(
+            list_response_sizes(),
+            vec![(
+                0,
+                IndexerEndpoint::Balance,
+                ResponseSizeStats {
+                    max_response_bytes: 32_768,
+                    successes: 1,
+                    escalations: 7,
+                    extra_cycles: 1_500,
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn test_size_decays_to_the_default() {
+        record_response_size(0, IndexerEndpoint::Utxos, Some(32_768), 2, 500);
+        for _ in 1..RESPONSE_BYTES_DECAY_CALLS {
+            record_response_size(0, IndexerEndpoint::Utxos, Some(32_768), 0, 0);
+        }
+        assert_eq!(get_cached_response_bytes(0, IndexerEndpoint::Utxos), 8_192);
+
+        // @dev A response that is too large again escalates back
+        record_response_size(0, IndexerEndpoint::Utxos, Some(32_768), 1, 100);
+        assert_eq!(get_cached_response_bytes(0, IndexerEndpoint::Utxos), 32_768);
+
+        assert_eq!(decayed_max_response_bytes(8_192), DEFAULT_MAX_RESPONSE_BYTES);
+        assert_eq!(decayed_max_response_bytes(DEFAULT_MAX_RESPONSE_BYTES), DEFAULT_MAX_RESPONSE_BYTES);
+    }
+
+    #[test]
+    fn test_configured_cap() {
+        assert_eq!(get_max_response_bytes_cap(), MAX_RESPONSE_BYTES_CAP);
+        assert_eq!(set_max_response_bytes_cap(Some(65_536)), Ok(()));
+        assert_eq!(get_max_response_bytes_cap(), 65_536);
+        assert_eq!(
+            set_max_response_bytes_cap(Some(1_024)),
+            Err(SyronError::InvalidResponseBytesCap {
+                cap: 1_024,
+                min: DEFAULT_MAX_RESPONSE_BYTES,
+                max: MAX_RESPONSE_BYTES_CAP,
+            })
+        );
+        assert_eq!(get_max_response_bytes_cap(), 65_536);
+        assert_eq!(set_max_response_bytes_cap(None), Ok(()));
+        assert_eq!(get_max_response_bytes_cap(), MAX_RESPONSE_BYTES_CAP);
+    }
+
+    #[test]
+    fn test_response_too_large() {
+        let too_large = HttpOutcallError::IcError {
+            code: RejectionCode::SysFatal,
+            message: "Http body exceeds size limit of 2048 bytes.".to_string(),
+        };
+        assert!(too_large.is_response_too_large());
+        let timeout = HttpOutcallError::IcError {
+            code: RejectionCode::SysTransient,
+            message: "Timeout expired".to_string(),
+        };
+        assert!(!timeout.is_response_too_large());
+    }
+}
@@ -0,0 +1,26 @@
+#[cfg(test)]
+mod tests {
+    use candid::Principal;
+
+    use crate::siwb::is_linked;
+
+    fn principal(id: u8) -> Principal {
+        Principal::from_slice(&[id; 29])
+    }
+
+    #[test]
+    fn test_only_the_linked_principal_is_authorized() {
+        assert!(is_linked(principal(1), Some(principal(1))));
+        assert!(!is_linked(principal(2), Some(principal(1))));
+    }
+
+    #[test]
+    fn test_unlinked_address_is_unauthorized() {
+        assert!(!is_linked(principal(1), None));
+    }
+
+    #[test]
+    fn test_anonymous_caller_is_unauthorized() {
+        assert!(!is_linked(Principal::anonymous(), Some(Principal::anonymous())));
+    }
+}
@@ -0,0 +1,172 @@
+#[cfg(test)]
+mod tests {
+    use bitcoin::{hashes::Hash, Network};
+
+    use crate::spv::{compute_merkle_root, parse_hash, parse_headers, verify_header_chain, verify_inclusion};
+    use crate::types::{MerkleProof, SpvError};
+
+    // @dev Regtest blocks 1 to 6 on top of the regtest genesis block; block 1 has 5 transactions.
+    const REGTEST_HEADERS: [&str; 6] = [
+        "0000002006226e46111a0b59caaf126043eb5bbf28c34f3a5e332a1fc7b2b73cf188910f71ae813e1804985cf987add11b0fed6ad2fb119b2e4291ed54cbbb39272b67e932e8494dffff7f2001000000",
+        "00000020b612cb5d6732818d79e6f624505629beffdf215e4adad558ceb2790d06d6fd5ff9e00e3113f3bfd7653e049d899e5f3c917d020780128ff686e37ce215ab74fe8aea494dffff7f2004000000",
+        "00000020bc4459bb5c586ce7bc04ddb0a33b43cc78f1104fdfc333c0f72462fca2a9536c9953051d0daf36399447027f1ff4ceee27161c808c610b3f961ea3805ab3e793e2ec494dffff7f2000000000",
+        "00000020ab7369faff85daf47d02d38a0f2db45f005206e604a39b16e1730a0a8acdb613a14e2895f7b9e1e7b37f82b38e345462a36edfa6dbce70939cc1bfc6a74ddd5e3aef494dffff7f2001000000",
+        "00000020e38bf89762964e72007f5c2d605e5e5450ea6ed3249fef6c5372a5e7847222365b0f32b53f5bba2e091aa7bec8e8286595b5fd5918968e660e434edd12cd5f1c92f1494dffff7f2000000000",
+        "00000020f16ef13862cc352b086f51219ef5cabccf2db23946ccf22c5c2a28da5a31cc0debb9b6236a142b3ab6a7beb5d35d1f9be9939340e7539069a049767744682c7eeaf3494dffff7f2000000000",
+    ];
+
+    const REGTEST_TXIDS: [&str; 5] = [
+        "13bfd7c96848106375f09f8c954ee17713694313f99896709a781af419789392",
+        "a9589e9e6d9e0c23f741ad53f449142ebb0e91e2aeb7d2a231430b640a625b8e",
+        "7bd05b8741a0bbb8e48373c1ec654c810e1f72834ff637574f12cad58c2a6203",
+        "0673aa1d7bc691cf7e961095e9229dbcad29601a5872e6fe0a579c0e7cce7cd4",
+        "c925e991b0d3cf8434074d18c27160f7ff364ab5c90dcda486f21e39b3ecc7bb",
+    ];
+
+    const REGTEST_MERKLE_ROOT: &str = "e9672b2739bbcb54ed91422e9b11fbd26aed0f1bd1ad87f95c9804183e81ae71";
+
+    // @dev Mainnet block 100000 (4 transactions)
+    const MAINNET_HEADER_100000: &str = "0100000050120119172a610421a6c3011dd330d9df07b63616c2cc1f1cd00200000000006657a9252aacd5c0b2940996ecff952228c3067cc38d4885efb5a4ac4247e9f337221b4d4c86041b0f2b5710";
+
+    fn regtest_headers() -> Vec<Vec<u8>> {
+        REGTEST_HEADERS.iter().map(|header| hex::decode(header).unwrap()).collect()
+    }
+
+    fn regtest_proof(index: u32) -> MerkleProof {
+        let branch: &[&str] = match index {
+            3 => &[
+                "7bd05b8741a0bbb8e48373c1ec654c810e1f72834ff637574f12cad58c2a6203",
+                "60839becffe7d41ec86424c2805a09529b5ec7dd19cb19e45e989b92e38e2c4d",
+                "7cf634167843f2e43238a60f38d903ab39d6d15c6b8f9eb60207b7d60cf73179",
+            ],
+            // @dev The last transaction of an odd level is paired with itself
+            4 => &[
+                "c925e991b0d3cf8434074d18c27160f7ff364ab5c90dcda486f21e39b3ecc7bb",
+                "1816b877e4b18572dd46283f2158bf4ba38ee1d4063e1fbfb2c1915045e569fc",
+                "9df76de659f4414bebd7b09a7d90c8d9798c92c0618bcfef2aab971a8d7ab2ec",
+            ],
+            _ => unreachable!(),
+        };
+        MerkleProof {
+            txid: REGTEST_TXIDS[index as usize].to_string(),
+            block_height: 1,
+            index,
+            branch: branch.iter().map(|hash| hash.to_string()).collect(),
+        }
+    }
+
+    fn compute(proof: &MerkleProof) -> Result<[u8; 32], SpvError> {
+        let branch: Vec<[u8; 32]> = proof.branch.iter().map(|hash| parse_hash(hash).unwrap()).collect();
+        compute_merkle_root(parse_hash(&proof.txid)?, proof.index, &branch)
+    }
+
+    #[test]
+    fn test_merkle_root() {
+        let root = parse_hash(REGTEST_MERKLE_ROOT).unwrap();
+        assert_eq!(compute(&regtest_proof(3)), Ok(root));
+        assert_eq!(compute(&regtest_proof(4)), Ok(root));
+
+        // @dev The header commits to the same root
+        let headers = parse_headers(1, &regtest_headers()).unwrap();
+        assert_eq!(headers[0].merkle_root.to_string(), REGTEST_MERKLE_ROOT);
+
+        // @dev A block with a single transaction has the txid as merkle root
+        let txid = parse_hash(REGTEST_TXIDS[0]).unwrap();
+        assert_eq!(compute_merkle_root(txid, 0, &[]), Ok(txid));
+    }
+
+    #[test]
+    fn test_mainnet_block() {
+        let txids: Vec<[u8; 32]> = [
+            "8c14f0db3df150123e6f3dbbf30f8b955a8249b62ac1d1ff16284aefa3d06d87",
+            "fff2525b8931402dd09222c50775608f75787bd2b87e56995a7bdd30f79702c4",
+            "6359f0868171b1d194cbee1af2f16ea598ae8fad666d9b012c8ed2b79a236ec4",
+            "e9a66845e05d5abc0ad04ec80f774a7e585c6e8db975962d069a522137b80c1d",
+        ]
+        .iter()
+        .map(|txid| parse_hash(txid).unwrap())
+        .collect();
+        let headers = parse_headers(100_000, &[hex::decode(MAINNET_HEADER_100000).unwrap()]).unwrap();
+        assert_eq!(
+            headers[0].block_hash().to_string(),
+            "000000000003ba27aa200b1cecaad478d2b00432346c3f1f3986da1afd33e506"
+        );
+        assert_eq!(verify_header_chain(100_000, &headers, Network::Bitcoin), Ok(()));
+
+        let left = compute_merkle_root(txids[0], 0, &[txids[1]]).unwrap();
+        let root = compute_merkle_root(txids[2], 2, &[txids[3], left]).unwrap();
+        assert_eq!(root, headers[0].merkle_root.into_inner());
+
+        // @dev Regtest difficulty is above the mainnet limit
+        let regtest = parse_headers(1, &regtest_headers()[..1]).unwrap();
+        assert_eq!(verify_header_chain(1, &regtest, Network::Bitcoin), Err(SpvError::InvalidProofOfWork { height: 1 }));
+    }
+
+    #[test]
+    fn test_invalid_merkle_branch() {
+        // @dev The index must fit the depth of the branch
+        let mut proof = regtest_proof(3);
+        proof.index = 8;
+        assert_eq!(compute(&proof), Err(SpvError::InvalidMerkleIndex { index: 8, depth: 3 }));
+
+        // @dev The same branch at another position leads elsewhere
+        let mut proof = regtest_proof(3);
+        proof.index = 2;
+        assert_ne!(compute(&proof), Ok(parse_hash(REGTEST_MERKLE_ROOT).unwrap()));
+        assert_eq!(
+            verify_inclusion(&proof, &regtest_headers(), 6, 6, Network::Regtest),
+            Err(SpvError::MerkleRootMismatch { height: 1 })
+        );
+
+        let mut proof = regtest_proof(3);
+        proof.branch[0] = "not hex".to_string();
+        assert_eq!(
+            verify_inclusion(&proof, &regtest_headers(), 6, 1, Network::Regtest),
+            Err(SpvError::InvalidHash("not hex".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_verify_inclusion() {
+        assert_eq!(verify_inclusion(&regtest_proof(3), &regtest_headers(), 6, 6, Network::Regtest), Ok(6));
+        assert_eq!(verify_inclusion(&regtest_proof(4), &regtest_headers()[..1], 10, 1, Network::Regtest), Ok(10));
+
+        assert_eq!(
+            verify_inclusion(&regtest_proof(3), &regtest_headers(), 5, 6, Network::Regtest),
+            Err(SpvError::InsufficientConfirmations { required: 6, found: 5 })
+        );
+        assert_eq!(
+            verify_inclusion(&regtest_proof(3), &regtest_headers()[..3], 6, 6, Network::Regtest),
+            Err(SpvError::MissingHeaders { expected: 6, found: 3 })
+        );
+        // @dev A block above the tip has no confirmations
+        let mut proof = regtest_proof(3);
+        proof.block_height = 7;
+        assert_eq!(
+            verify_inclusion(&proof, &regtest_headers(), 6, 1, Network::Regtest),
+            Err(SpvError::InsufficientConfirmations { required: 1, found: 0 })
+        );
+    }
+
+    #[test]
+    fn test_header_chain() {
+        let headers = parse_headers(1, &regtest_headers()).unwrap();
+        assert_eq!(verify_header_chain(1, &headers, Network::Regtest), Ok(()));
+
+        // @dev A missing header breaks the links
+        let gapped = [headers[0], headers[1], headers[3]];
+        assert_eq!(verify_header_chain(1, &gapped, Network::Regtest), Err(SpvError::BrokenChain { height: 3 }));
+
+        // @dev A header whose hash is above its target
+        let mut forged = headers.clone();
+        forged[2].nonce += 1;
+        while forged[2].validate_pow(&forged[2].target()).is_ok() {
+            forged[2].nonce += 1;
+        }
+        assert_eq!(verify_header_chain(1, &forged, Network::Regtest), Err(SpvError::InvalidProofOfWork { height: 3 }));
+
+        let mut truncated = regtest_headers();
+        truncated[1].pop();
+        assert!(matches!(parse_headers(1, &truncated), Err(SpvError::InvalidHeader { height: 2, .. })));
+    }
+}
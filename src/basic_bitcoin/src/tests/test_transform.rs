@@ -0,0 +1,142 @@
+#[cfg(test)]
+mod tests {
+    use candid::Nat;
+    use ic_cdk::api::management_canister::http_request::{HttpHeader, HttpResponse, TransformArgs};
+
+    use crate::http::{transform_context, transform_response};
+    use crate::indexer::{indexer_client, IndexerClient};
+    use crate::types::{IndexerEndpoint, IndexerKind};
+
+    // @dev Recorded UniSat inscription info response (testnet).
+    const UNISAT_INSCRIPTION: &str = r#"{"code":0,"msg":"ok","data":{"utxo":{"txid":"5b1d8dd5e9c4a2f6a0a3b59a1f6a1c5a3e1e7d0f0cbb6e6b8f6f2b8b4b8e0a11","vout":0,"satoshi":546,"scriptType":"0014","scriptPk":"0014d9ac233abc6336337e2c01f32c577e1a9016c9ac","codeType":8,"address":"tb1qmxkzxw4uvvmrxl3vq8ejc4m7r2gpvjdvk0kvm0","height":2812034,"idx":1821,"isOpInRBF":false,"inscriptions":[{"inscriptionId":"5b1d8dd5e9c4a2f6a0a3b59a1f6a1c5a3e1e7d0f0cbb6e6b8f6f2b8b4b8e0a11i0","inscriptionNumber":1184025,"isBRC20":true,"moved":false,"offset":0}]},"address":"tb1qmxkzxw4uvvmrxl3vq8ejc4m7r2gpvjdvk0kvm0","offset":0,"inscriptionIndex":0,"inscriptionNumber":1184025,"inscriptionId":"5b1d8dd5e9c4a2f6a0a3b59a1f6a1c5a3e1e7d0f0cbb6e6b8f6f2b8b4b8e0a11i0","contentType":"text/plain;charset=utf-8","contentLength":57,"contentBody":"","height":2812034,"timestamp":1715853262,"inSatoshi":546,"outSatoshi":0,"brc20":{"op":"transfer","tick":"SYRON","lim":"","amt":"15.5","decimal":""},"detail":null,"location":"5b1d8dd5e9c4a2f6a0a3b59a1f6a1c5a3e1e7d0f0cbb6e6b8f6f2b8b4b8e0a11:0:0"}}"#;
+
+    // @dev Recorded UniSat BRC-20 summary response, as served a block later.
+    const UNISAT_BALANCE: &str = r#"{"code":0,"msg":"ok","data":{"height":2812040,"total":1,"start":0,"detail":[{"ticker":"SYRON","overallBalance":"20.5","transferableBalance":"15.5","availableBalance":"5"}]}}"#;
+    const UNISAT_BALANCE_NEXT_BLOCK: &str = r#"{"code":0,"msg":"ok","data":{"height":2812041,"total":1,"start":0,"detail":[{"ticker":"SYRON","overallBalance":"20.5","transferableBalance":"15.5","availableBalance":"5"}]}}"#;
+
+    // @dev Recorded Tyron indexer balance response, which is the UniSat `data` object.
+    const TYRON_BALANCE: &str = r#"{"height":2812040,"total":2,"start":0,"detail":[{"ticker":"ordi","overallBalance":"1000","transferableBalance":"0","availableBalance":"1000"},{"ticker":"SYRON","overallBalance":"20000000.12345678","transferableBalance":"15.5","availableBalance":"19999984.62345678"}]}"#;
+
+    // @dev Recorded BestInSlot responses (18-decimal integers).
+    const BIS_BALANCE: &str = r#"{"data":[{"ticker":"syron","overall_balance":"15500000000000000000","available_balance":"0","block_height":2812040}],"block_height":2812041}"#;
+    const BIS_HOLDER: &str = r#"{"data":{"inscription_name":null,"inscription_id":"5b1d8dd5e9c4a2f6a0a3b59a1f6a1c5a3e1e7d0f0cbb6e6b8f6f2b8b4b8e0a11i0","inscription_number":1184025,"wallet":"tb1qs9cg7lzt4xm9cqzdxzk8t7mzy3m6ud7c4kqm2l","satpoint":"5b1d8dd5e9c4a2f6a0a3b59a1f6a1c5a3e1e7d0f0cbb6e6b8f6f2b8b4b8e0a11:0:0","genesis_height":2812034,"last_transfer_block_height":2812034},"block_height":2812041}"#;
+    const BIS_INSCRIPTION: &str = r#"{"data":[{"event_type":"transfer-inscribe","event":{"source_wallet":"tb1qmxkzxw4uvvmrxl3vq8ejc4m7r2gpvjdvk0kvm0","tick":"syron","amount":"15500000000000000000","using_tx_id":"0"},"inscription_id":"5b1d8dd5e9c4a2f6a0a3b59a1f6a1c5a3e1e7d0f0cbb6e6b8f6f2b8b4b8e0a11i0","satpoint":"5b1d8dd5e9c4a2f6a0a3b59a1f6a1c5a3e1e7d0f0cbb6e6b8f6f2b8b4b8e0a11:0:0","block_height":2812034,"block_timestamp":"2024-05-16T09:54:22.000Z"}],"block_height":2812041}"#;
+
+    fn response(status: u64, body: &str) -> HttpResponse {
+        HttpResponse {
+            status: Nat::from(status),
+            headers: vec![HttpHeader {
+                name: "Date".to_string(),
+                value: "Thu, 16 May 2024 09:55:00 GMT".to_string(),
+            }],
+            body: body.as_bytes().to_vec(),
+        }
+    }
+
+    fn transform(kind: IndexerKind, endpoint: IndexerEndpoint, status: u64, body: &str) -> HttpResponse {
+        let args = TransformArgs {
+            response: response(status, body),
+            context: transform_context(indexer_client(kind).response_fields(endpoint)),
+        };
+        transform_response(args)
+    }
+
+    fn transformed_body(kind: IndexerKind, endpoint: IndexerEndpoint, body: &str) -> String {
+        let transformed = transform(kind, endpoint, 200, body);
+        assert!(transformed.headers.is_empty());
+        String::from_utf8(transformed.body).unwrap()
+    }
+
+    fn assert_no_volatile_fields(body: &str) {
+        for field in ["height", "timestamp", "inscriptionNumber", "satoshi"] {
+            assert!(!body.contains(field), "{} in {}", field, body);
+        }
+    }
+
+    #[test]
+    fn test_unisat_inscription_transform() {
+        let client = indexer_client(IndexerKind::UniSat);
+        let body = transformed_body(IndexerKind::UniSat, IndexerEndpoint::Inscription, UNISAT_INSCRIPTION);
+        assert_no_volatile_fields(&body);
+        assert!(body.len() < UNISAT_INSCRIPTION.len() / 2);
+        assert_eq!(client.parse_inscription(&body), client.parse_inscription(UNISAT_INSCRIPTION));
+    }
+
+    #[test]
+    fn test_unisat_balance_transform() {
+        let client = indexer_client(IndexerKind::UniSat);
+        let body = transformed_body(IndexerKind::UniSat, IndexerEndpoint::Balance, UNISAT_BALANCE);
+        assert_no_volatile_fields(&body);
+        assert_eq!(client.parse_balances(&body), client.parse_balances(UNISAT_BALANCE));
+
+        // @dev Replicas that observe different blocks still agree
+        assert_eq!(
+            body,
+            transformed_body(IndexerKind::UniSat, IndexerEndpoint::Balance, UNISAT_BALANCE_NEXT_BLOCK)
+        );
+    }
+
+    #[test]
+    fn test_tyron_balance_transform() {
+        let client = indexer_client(IndexerKind::Tyron);
+        let body = transformed_body(IndexerKind::Tyron, IndexerEndpoint::Balance, TYRON_BALANCE);
+        assert_no_volatile_fields(&body);
+        let balances = client.parse_balances(&body).unwrap();
+        assert_eq!(balances.len(), 2);
+        assert_eq!(Ok(balances), client.parse_balances(TYRON_BALANCE));
+    }
+
+    #[test]
+    fn test_bis_transforms() {
+        let client = indexer_client(IndexerKind::BestInSlot);
+        let body = transformed_body(IndexerKind::BestInSlot, IndexerEndpoint::Balance, BIS_BALANCE);
+        assert_no_volatile_fields(&body);
+        assert_eq!(client.parse_balances(&body), client.parse_balances(BIS_BALANCE));
+
+        let body = transformed_body(IndexerKind::BestInSlot, IndexerEndpoint::Inscription, BIS_INSCRIPTION);
+        assert_no_volatile_fields(&body);
+        assert!(!body.contains("using_tx_id"));
+        assert!(!body.contains("source_wallet"));
+        let inscription = client.parse_inscription(&body).unwrap();
+        assert_eq!(inscription.amt, "15.5");
+        assert_eq!(inscription.op, "transfer");
+        // @dev The holder is read from the inscription info, not from the wallet that inscribed the transfer
+        assert_eq!(inscription.owner_address, "");
+        assert_eq!(Ok(inscription), client.parse_inscription(BIS_INSCRIPTION));
+
+        let body = transformed_body(IndexerKind::BestInSlot, IndexerEndpoint::Holder, BIS_HOLDER);
+        assert_no_volatile_fields(&body);
+        assert_eq!(
+            client.parse_holder(&body),
+            Ok((
+                "tb1qs9cg7lzt4xm9cqzdxzk8t7mzy3m6ud7c4kqm2l".to_string(),
+                Some("5b1d8dd5e9c4a2f6a0a3b59a1f6a1c5a3e1e7d0f0cbb6e6b8f6f2b8b4b8e0a11:0:0".to_string())
+            ))
+        );
+        assert_eq!(client.holder_endpoint("ai0"), Some("inscription/single_info_id?inscription_id=ai0".to_string()));
+        assert_eq!(indexer_client(IndexerKind::UniSat).holder_endpoint("ai0"), None);
+
+        // @dev The op is the one of the inscribe event
+        let mint = BIS_INSCRIPTION.replace("transfer-inscribe", "mint-inscribe");
+        assert_eq!(client.parse_inscription(&mint).unwrap().op, "mint");
+        assert!(client.parse_inscription(&BIS_INSCRIPTION.replace("transfer-inscribe", "transfer-transfer")).is_err());
+    }
+
+    #[test]
+    fn test_error_responses_are_emptied() {
+        let error = r#"{"timestamp":"2023-03-01T20:35:49.416+00:00","status":403,"error":"Forbidden","message":"AccessDenied"}"#;
+        let transformed = transform(IndexerKind::UniSat, IndexerEndpoint::Balance, 403, error);
+        assert_eq!(transformed.status, Nat::from(403u64));
+        assert!(transformed.body.is_empty());
+        assert!(transformed.headers.is_empty());
+    }
+
+    #[test]
+    fn test_transform_without_fields_canonicalizes() {
+        let args = TransformArgs {
+            response: response(200, r#"{ "b": 1, "a": 2 }"#),
+            context: vec![],
+        };
+        assert_eq!(transform_response(args).body, br#"{"a":2,"b":1}"#.to_vec());
+    }
+}
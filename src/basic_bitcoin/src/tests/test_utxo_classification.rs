@@ -0,0 +1,208 @@
+#[cfg(test)]
+mod tests {
+    use candid::Nat;
+    use ic_cdk::api::management_canister::bitcoin::{Outpoint, Utxo};
+    use ic_cdk::api::management_canister::http_request::{HttpResponse, TransformArgs};
+
+    use crate::classifier::{
+        classify, classify_utxos, get_utxo_tags, list_utxo_tags, tag_utxo, utxo_outpoint, utxo_txid, ClassifiedUtxos,
+    };
+    use crate::http::{transform_context, transform_response};
+    use crate::indexer::{indexer_client, inscription_id};
+    use crate::types::{IndexedUtxo, IndexerEndpoint, IndexerKind, OutpointKey, SyronError, UtxoAssets, UtxoClass};
+
+    const TXID: &str = "5b1d8dd5e9c4a2f6a0a3b59a1f6a1c5a3e1e7d0f0cbb6e6b8f6f2b8b4b8e0a11";
+    const RUNE_TXID: &str = "b61b0172d95e266c18aea0c624db987e971a5d6d4ebc2aaed85da4642d635735";
+    const CARDINAL_TXID: &str = "f5864806e3565c34d1b41e716f72609d00b55ea5eac5b924c9719a842ef42206";
+
+    // @dev UniSat inscription UTXO data (testnet), with the runes of the second UTXO.
+    const UNISAT_UTXOS: &str = r#"{"code":0,"msg":"ok","data":{"cursor":0,"total":2,"totalConfirmed":2,"totalUnconfirmed":0,"totalUnconfirmedSpend":0,"utxo":[{"txid":"5b1d8dd5e9c4a2f6a0a3b59a1f6a1c5a3e1e7d0f0cbb6e6b8f6f2b8b4b8e0a11","vout":0,"satoshi":10000,"scriptType":"0014","scriptPk":"0014d9ac233abc6336337e2c01f32c577e1a9016c9ac","codeType":8,"address":"tb1qmxkzxw4uvvmrxl3vq8ejc4m7r2gpvjdvk0kvm0","height":2812034,"idx":1821,"isOpInRBF":false,"isSpent":false,"inscriptions":[{"inscriptionId":"5b1d8dd5e9c4a2f6a0a3b59a1f6a1c5a3e1e7d0f0cbb6e6b8f6f2b8b4b8e0a11i0","inscriptionNumber":1184025,"isBRC20":true,"moved":false,"offset":0}]},{"txid":"b61b0172d95e266c18aea0c624db987e971a5d6d4ebc2aaed85da4642d635735","vout":1,"satoshi":546,"scriptType":"0014","scriptPk":"0014d9ac233abc6336337e2c01f32c577e1a9016c9ac","codeType":8,"address":"tb1qmxkzxw4uvvmrxl3vq8ejc4m7r2gpvjdvk0kvm0","height":2812040,"idx":12,"isOpInRBF":false,"isSpent":false,"inscriptions":[],"runes":[{"runeid":"2584333:39","rune":"SYRONRUNE","amount":"1000"}]}]}}"#;
+
+    // @dev BestInSlot wallet inscriptions, two of them on the same UTXO.
+    const BIS_UTXOS: &str = r#"{"data":[{"inscription_name":null,"inscription_id":"5b1d8dd5e9c4a2f6a0a3b59a1f6a1c5a3e1e7d0f0cbb6e6b8f6f2b8b4b8e0a11i0","inscription_number":1184025,"satpoint":"5b1d8dd5e9c4a2f6a0a3b59a1f6a1c5a3e1e7d0f0cbb6e6b8f6f2b8b4b8e0a11:0:0","last_transfer_block_height":2812034},{"inscription_name":null,"inscription_id":"b61b0172d95e266c18aea0c624db987e971a5d6d4ebc2aaed85da4642d635735i0","inscription_number":1184030,"satpoint":"5b1d8dd5e9c4a2f6a0a3b59a1f6a1c5a3e1e7d0f0cbb6e6b8f6f2b8b4b8e0a11:0:330","last_transfer_block_height":2812040}],"block_height":2812041}"#;
+
+    fn utxo(txid: &str, vout: u32, value: u64) -> Utxo {
+        let mut txid = hex::decode(txid).unwrap();
+        txid.reverse();
+        Utxo {
+            outpoint: Outpoint { txid, vout },
+            value,
+            height: 2812034,
+        }
+    }
+
+    fn inscribed(txid: &str, vout: u32, inscription_ids: &[&str]) -> IndexedUtxo {
+        IndexedUtxo {
+            outpoint: OutpointKey::new(txid, vout),
+            assets: UtxoAssets {
+                inscription_ids: inscription_ids.iter().map(|id| id.to_string()).collect(),
+                rune_ids: vec![],
+            },
+        }
+    }
+
+    /// The indexed UTXOs of the response, as parsed once transformed.
+    fn parse(kind: IndexerKind, body: &str) -> Vec<IndexedUtxo> {
+        let client = indexer_client(kind);
+        let transformed = transform_response(TransformArgs {
+            response: HttpResponse {
+                status: Nat::from(200u64),
+                headers: vec![],
+                body: body.as_bytes().to_vec(),
+            },
+            context: transform_context(client.response_fields(IndexerEndpoint::Utxos)),
+        });
+        let transformed = String::from_utf8(transformed.body).unwrap();
+        for field in ["satoshi", "height", "inscriptionNumber", "inscription_number"] {
+            assert!(!transformed.contains(field), "{} in {}", field, transformed);
+        }
+        client.parse_utxos(&transformed).unwrap()
+    }
+
+    #[test]
+    fn test_parse_indexed_utxos() {
+        let expected = vec![
+            inscribed(TXID, 0, &[&inscription_id(TXID)]),
+            IndexedUtxo {
+                outpoint: OutpointKey::new(RUNE_TXID, 1),
+                assets: UtxoAssets {
+                    inscription_ids: vec![],
+                    rune_ids: vec!["2584333:39".to_string()],
+                },
+            },
+        ];
+        assert_eq!(parse(IndexerKind::UniSat, UNISAT_UTXOS), expected);
+
+        // @dev The Tyron indexer returns the UniSat `data` object
+        let data = serde_json::from_str::<serde_json::Value>(UNISAT_UTXOS).unwrap()["data"].to_string();
+        assert_eq!(parse(IndexerKind::Tyron, &data), expected);
+
+        assert_eq!(
+            parse(IndexerKind::BestInSlot, BIS_UTXOS),
+            vec![inscribed(TXID, 0, &[&inscription_id(TXID), &inscription_id(RUNE_TXID)])]
+        );
+
+        // @dev Without inscriptions nor runes
+        let client = indexer_client(IndexerKind::UniSat);
+        assert_eq!(client.parse_utxos(r#"{"code":0,"msg":"ok","data":{"utxo":[]}}"#), Ok(vec![]));
+        assert!(client.parse_utxos(r#"{"code":0,"msg":"ok","data":{"utxo":[{"txid":"00","inscriptions":[]}]}}"#).is_err());
+        assert!(client.parse_utxos(r#"{"code":0,"msg":"ok","data":{"utxo":[{"txid":"00","vout":0,"inscriptions":{}}]}}"#).is_err());
+        assert!(indexer_client(IndexerKind::BestInSlot).parse_utxos(r#"{"data":[{"inscription_id":"00i0","satpoint":"00:0:0"}]}"#).is_err());
+    }
+
+    #[test]
+    fn test_classification() {
+        let utxos = vec![
+            // @dev An inscription above the former 600-satoshi heuristic
+            utxo(TXID, 0, 10_000),
+            // @dev A cardinal UTXO below it
+            utxo(CARDINAL_TXID, 0, 330),
+            utxo(RUNE_TXID, 1, 546),
+            utxo(CARDINAL_TXID, 1, 50_000),
+        ];
+        let indexed = vec![inscribed(TXID, 0, &[&inscription_id(TXID)])];
+
+        // @dev The registry tags the runes that the indexer misses
+        let runes = UtxoAssets {
+            inscription_ids: vec![],
+            rune_ids: vec!["2584333:39".to_string()],
+        };
+        assert!(tag_utxo(OutpointKey::new(RUNE_TXID, 1), runes.clone()));
+
+        let classified = classify_utxos(utxos.clone(), indexed.clone());
+        let classes: Vec<UtxoClass> = classified.0.iter().map(|classified| classified.class.clone()).collect();
+        assert_eq!(
+            classes,
+            vec![
+                UtxoClass::Inscribed {
+                    inscription_ids: vec![inscription_id(TXID)]
+                },
+                UtxoClass::Cardinal,
+                UtxoClass::RuneBearing {
+                    rune_ids: runes.rune_ids.clone()
+                },
+                UtxoClass::Cardinal,
+            ]
+        );
+        assert_eq!(classified.cardinal(), vec![utxos[1].clone(), utxos[3].clone()]);
+
+        // @dev Both sources are merged, and inscriptions take precedence over runes
+        assert!(tag_utxo(
+            OutpointKey::new(TXID, 0),
+            UtxoAssets {
+                inscription_ids: vec![inscription_id(TXID), inscription_id(CARDINAL_TXID)],
+                rune_ids: vec!["2584333:39".to_string()],
+            }
+        ));
+        let classified = classify_utxos(utxos.clone(), indexed.clone());
+        assert_eq!(
+            classified.0[0].class,
+            UtxoClass::Inscribed {
+                inscription_ids: vec![inscription_id(TXID), inscription_id(CARDINAL_TXID)]
+            }
+        );
+
+        // @dev Removing the tags makes the UTXO cardinal again, unless an indexer tags it
+        assert!(tag_utxo(OutpointKey::new(RUNE_TXID, 1), UtxoAssets::default()));
+        assert!(tag_utxo(OutpointKey::new(TXID, 0), UtxoAssets::default()));
+        let classified = classify_utxos(utxos.clone(), indexed);
+        assert_eq!(classified.cardinal(), vec![utxos[1].clone(), utxos[2].clone(), utxos[3].clone()]);
+        assert_eq!(classify(UtxoAssets::default()), UtxoClass::Cardinal);
+    }
+
+    #[test]
+    fn test_inscription_utxo() {
+        let utxos = vec![utxo(TXID, 0, 546), utxo(CARDINAL_TXID, 0, 546), utxo(RUNE_TXID, 0, 10_000)];
+        let indexed = vec![
+            inscribed(TXID, 0, &[&inscription_id(TXID)]),
+            // @dev An inscription revealed by another transaction, then sent to this address
+            inscribed(RUNE_TXID, 0, &[&inscription_id(CARDINAL_TXID)]),
+        ];
+        let classified = classify_utxos(utxos.clone(), indexed);
+
+        assert_eq!(classified.inscription_utxo(TXID), Ok(utxos[0].clone()));
+        assert_eq!(classified.inscription_utxo(CARDINAL_TXID), Ok(utxos[2].clone()));
+
+        // @dev The UTXO of a transaction that does not carry its inscription is not spent as one
+        let classified = classify_utxos(utxos[..2].to_vec(), vec![]);
+        assert_eq!(
+            classified.inscription_utxo(TXID),
+            Err(SyronError::UtxoNotInscribed {
+                txid: TXID.to_string(),
+                vout: 0,
+                inscription_id: inscription_id(TXID),
+                class: UtxoClass::Cardinal,
+            })
+        );
+        assert_eq!(
+            ClassifiedUtxos::default().inscription_utxo(TXID),
+            Err(SyronError::UtxoNotFound { txid: TXID.to_string() })
+        );
+    }
+
+    #[test]
+    fn test_utxo_tags() {
+        let outpoint = utxo_outpoint(&utxo(TXID, 3, 546));
+        assert_eq!(outpoint, OutpointKey::new(&TXID.to_ascii_uppercase(), 3));
+        assert_eq!(utxo_txid(&utxo(TXID, 3, 546)), TXID);
+
+        let assets = UtxoAssets {
+            inscription_ids: vec![inscription_id(TXID)],
+            rune_ids: vec![],
+        };
+        assert!(tag_utxo(outpoint.clone(), assets.clone()));
+        assert_eq!(get_utxo_tags(&outpoint), Some(assets.clone()));
+        assert_eq!(list_utxo_tags(0, 10), vec![(outpoint.clone(), assets)]);
+
+        // @dev Too many inscriptions to be stored
+        let assets = UtxoAssets {
+            inscription_ids: vec![inscription_id(TXID); 20],
+            rune_ids: vec![],
+        };
+        assert!(!tag_utxo(OutpointKey::new(CARDINAL_TXID, 0), assets));
+        assert_eq!(get_utxo_tags(&OutpointKey::new(CARDINAL_TXID, 0)), None);
+
+        assert!(tag_utxo(outpoint.clone(), UtxoAssets::default()));
+        assert_eq!(list_utxo_tags(0, 10), vec![]);
+    }
+}
@@ -0,0 +1,91 @@
+#[cfg(test)]
+mod tests {
+    use ic_cdk::api::management_canister::http_request::HttpHeader;
+
+    use crate::types::ValidationError;
+    use crate::validate::{
+        get_allowed_hostnames, set_allowed_hostnames, validate_credential_headers, validate_credential_path,
+        validate_hostname, validate_provider,
+    };
+
+    fn header(name: &str, value: &str) -> HttpHeader {
+        HttpHeader {
+            name: name.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_hostname_allowlist() {
+        let allowed = vec!["api.bestinslot.xyz".to_string()];
+        assert_eq!(validate_hostname("api.bestinslot.xyz", &allowed), Ok(()));
+        assert_eq!(
+            validate_hostname("evil.example.com", &allowed),
+            Err(ValidationError::HostNotAllowed("evil.example.com".to_string()))
+        );
+
+        for hostname in [
+            "https://api.bestinslot.xyz",
+            "api.bestinslot.xyz:8080",
+            "api.bestinslot.xyz/v3",
+            "user@api.bestinslot.xyz",
+            "",
+        ] {
+            assert!(
+                matches!(validate_hostname(hostname, &allowed), Err(ValidationError::UrlParseError(_))),
+                "{}",
+                hostname
+            );
+        }
+    }
+
+    #[test]
+    fn test_default_and_configured_allowlist() {
+        assert!(get_allowed_hostnames().contains(&"open-api.unisat.io".to_string()));
+        assert_eq!(validate_provider("open-api.unisat.io", "", &[]), Ok(()));
+
+        set_allowed_hostnames(Some(vec!["indexer.example.com".to_string()])).unwrap();
+        assert_eq!(validate_provider("indexer.example.com", "/api/", &[]), Ok(()));
+        assert_eq!(
+            validate_provider("open-api.unisat.io", "", &[]),
+            Err(ValidationError::HostNotAllowed("open-api.unisat.io".to_string()))
+        );
+        assert!(set_allowed_hostnames(Some(vec!["https://indexer.example.com".to_string()])).is_err());
+    }
+
+    #[test]
+    fn test_credential_path() {
+        for path in ["", "v3/", "/api/", "/v1?apikey=abc"] {
+            assert_eq!(validate_credential_path(path), Ok(()), "{}", path);
+        }
+        for path in [
+            "https://evil.example.com/",
+            "//evil.example.com/",
+            ":8080/api",
+            "/api/../admin",
+            "..",
+            "/api/%2E%2E/admin",
+            "/api\\admin",
+            "@evil.example.com",
+            "/api/ admin",
+        ] {
+            assert_eq!(validate_credential_path(path), Err(ValidationError::CredentialPathNotAllowed), "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_credential_headers() {
+        assert_eq!(
+            validate_credential_headers(&[header("Authorization", "Bearer key"), header("X-API-KEY", "key")]),
+            Ok(())
+        );
+        assert_eq!(
+            validate_credential_headers(&[header("Host", "evil.example.com")]),
+            Err(ValidationError::CredentialHeaderNotAllowed)
+        );
+        assert_eq!(
+            validate_credential_headers(&[header("x-api-key", "key\r\nHost: evil.example.com")]),
+            Err(ValidationError::CredentialHeaderNotAllowed)
+        );
+    }
+}
@@ -0,0 +1,209 @@
+#[cfg(test)]
+mod tests {
+    use bitcoin::{
+        blockdata::{
+            opcodes::{self, all::{OP_CHECKSIG, OP_DUP, OP_EQUAL, OP_EQUALVERIFY, OP_HASH160, OP_PUSHNUM_1}},
+            script::{Builder, Script},
+            witness::Witness,
+        },
+        consensus::{deserialize, serialize},
+        OutPoint, Transaction, TxIn, TxOut,
+    };
+    use ic_ckbtc_minter_tyron::address::BitcoinAddress;
+
+    use crate::vsize::{
+        compact_size, compute_fee, estimate_vsize, estimate_weight, output_weight, weight, weight_to_vsize, InputType,
+    };
+
+    // @dev Mainnet f5864806e3565c34d1b41e716f72609d00b55ea5eac5b924c9719a842ef42206, from a P2WPKH input to a P2SH output
+    const MAINNET_P2WPKH_TX: &str = "02000000000101595895ea20179de87052b4046dfe6fd515860505d6511a9004cf12a1f93cac7c0100000000ffffffff01deb807000000000017a9140f3444e271620c736808aa7b33e370bd87cb5a078702483045022100fb60dad8df4af2841adc0346638c16d0b8035f5e3f3753b88db122e70c79f9370220756e6633b17fd2710e626347d28d60b0a2d6cbb41de51740644b9fb3ba7751040121028fa937ca8cba2197a37c007176ed8941055d3bcb8627d085e94553e62f057dcc00000000";
+
+    // @dev Mainnet a6eab3c14ab5272a58a5ba91505ba1a4b6d7a3a9fcbd187b6cd99a7b6d548cb7, from a P2PKH input to a P2PKH output
+    const MAINNET_P2PKH_TX: &str = "0100000001a15d57094aa7a21a28cb20b59aab8fc7d1149a3bdbcddba9c622e4f5f6a99ece010000006c493046022100f93bb0e7d8db7bd46e40132d1f8242026e045f03a0efe71bbb8e3f475e970d790221009337cd7f1f929f00cc6ff01f03729b069a7c21b59b1736ddfee5db5946c5da8c0121033b9b137ee87d5a812d6f506efdd37f0affa7ffc310711c06c7f3e097c9447c52ffffffff0100e1f505000000001976a9140389035a9225b3839e2bbf32d826a1e222031fd888ac00000000";
+
+    const INPUT_TYPES: [InputType; 4] = [InputType::P2wpkh, InputType::P2tr, InputType::P2shP2wpkh, InputType::P2pkh];
+
+    fn addresses() -> Vec<BitcoinAddress> {
+        vec![
+            BitcoinAddress::P2wpkhV0([1; 20]),
+            BitcoinAddress::P2wshV0([2; 32]),
+            BitcoinAddress::P2trV1([3; 32]),
+            BitcoinAddress::P2sh([4; 20]),
+            BitcoinAddress::P2pkh([5; 20]),
+        ]
+    }
+
+    fn script_pubkey(address: &BitcoinAddress) -> Script {
+        match address {
+            BitcoinAddress::P2wpkhV0(hash) => Builder::new().push_opcode(opcodes::OP_FALSE).push_slice(hash).into_script(),
+            BitcoinAddress::P2wshV0(hash) => Builder::new().push_opcode(opcodes::OP_FALSE).push_slice(hash).into_script(),
+            BitcoinAddress::P2trV1(key) => Builder::new().push_opcode(OP_PUSHNUM_1).push_slice(key).into_script(),
+            BitcoinAddress::P2sh(hash) => Builder::new()
+                .push_opcode(OP_HASH160)
+                .push_slice(hash)
+                .push_opcode(OP_EQUAL)
+                .into_script(),
+            BitcoinAddress::P2pkh(hash) => Builder::new()
+                .push_opcode(OP_DUP)
+                .push_opcode(OP_HASH160)
+                .push_slice(hash)
+                .push_opcode(OP_EQUALVERIFY)
+                .push_opcode(OP_CHECKSIG)
+                .into_script(),
+        }
+    }
+
+    /// A signed input of the given type, with an ECDSA signature of `signature_size` bytes.
+    fn signed_input(input_type: InputType, signature_size: usize) -> TxIn {
+        let signature = vec![0x30; signature_size];
+        let public_key = [0x02; 33];
+        let mut witness = Witness::new();
+        let script_sig = match input_type {
+            InputType::P2wpkh => {
+                witness.push(&signature);
+                witness.push(public_key);
+                Script::new()
+            }
+            InputType::P2tr => {
+                witness.push([0x11; 64]);
+                Script::new()
+            }
+            InputType::P2shP2wpkh => {
+                witness.push(&signature);
+                witness.push(public_key);
+                let redeem_script = script_pubkey(&BitcoinAddress::P2wpkhV0([6; 20]));
+                Builder::new().push_slice(redeem_script.as_bytes()).into_script()
+            }
+            InputType::P2pkh => Builder::new().push_slice(&signature).push_slice(&public_key).into_script(),
+        };
+        TxIn {
+            previous_output: OutPoint::null(),
+            script_sig,
+            sequence: 0xffffffff,
+            witness,
+        }
+    }
+
+    fn signed_transaction(inputs: &[InputType], outputs: &[BitcoinAddress], signature_size: usize) -> Transaction {
+        Transaction {
+            version: 2,
+            lock_time: 0,
+            input: inputs.iter().map(|input| signed_input(*input, signature_size)).collect(),
+            output: outputs
+                .iter()
+                .map(|address| TxOut {
+                    value: 10_000,
+                    script_pubkey: script_pubkey(address),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_input_and_output_weights() {
+        assert_eq!(InputType::P2wpkh.weight(), 273);
+        assert_eq!(InputType::P2tr.weight(), 230);
+        assert_eq!(InputType::P2shP2wpkh.weight(), 365);
+        assert_eq!(InputType::P2pkh.weight(), 596);
+
+        let weights: Vec<u64> = addresses().iter().map(output_weight).collect();
+        assert_eq!(weights, vec![124, 172, 172, 128, 136]);
+    }
+
+    #[test]
+    fn test_matches_signed_transactions() {
+        // @dev With signatures of the maximum length, the estimate is exact
+        for input_type in INPUT_TYPES {
+            for address in addresses() {
+                let outputs = [address];
+                let transaction = signed_transaction(&[input_type], &outputs, 73);
+                assert_eq!(estimate_weight(&[input_type], &outputs), transaction.weight() as u64, "{:?}", input_type);
+                assert_eq!(estimate_vsize(&[input_type], &outputs), transaction.vsize() as u64);
+            }
+        }
+
+        // @dev Legacy inputs get an empty witness once the transaction has a segwit input
+        let transaction = signed_transaction(&INPUT_TYPES, &addresses(), 73);
+        assert_eq!(estimate_weight(&INPUT_TYPES, &addresses()), transaction.weight() as u64);
+
+        // @dev A legacy-only transaction has no witness at all
+        let inputs = [InputType::P2pkh; 3];
+        let transaction = signed_transaction(&inputs, &addresses(), 73);
+        assert_eq!(estimate_weight(&inputs, &addresses()), transaction.weight() as u64);
+        assert_eq!(transaction.weight(), 4 * transaction.size());
+    }
+
+    #[test]
+    fn test_never_underestimates() {
+        // @dev Shorter DER signatures make the signed transaction smaller than the estimate
+        let inputs = [InputType::P2wpkh, InputType::P2wpkh, InputType::P2shP2wpkh];
+        let outputs = [BitcoinAddress::P2trV1([3; 32]), BitcoinAddress::P2wpkhV0([1; 20])];
+        for signature_size in 70..=73 {
+            let transaction = signed_transaction(&inputs, &outputs, signature_size);
+            assert!(estimate_vsize(&inputs, &outputs) >= transaction.vsize() as u64);
+        }
+        let transaction = signed_transaction(&inputs, &outputs, 71);
+        assert_eq!(estimate_weight(&inputs, &outputs) - transaction.weight() as u64, 2 * inputs.len() as u64);
+    }
+
+    #[test]
+    fn test_many_outputs() {
+        assert_eq!(compact_size(0xfc), 1);
+        assert_eq!(compact_size(0xfd), 3);
+        assert_eq!(compact_size(0x10000), 5);
+
+        // @dev 253 outputs take a 3-byte count
+        let outputs = vec![BitcoinAddress::P2wpkhV0([1; 20]); 253];
+        let inputs = [InputType::P2wpkh; 2];
+        let transaction = signed_transaction(&inputs, &outputs, 73);
+        assert_eq!(estimate_weight(&inputs, &outputs), transaction.weight() as u64);
+    }
+
+    /// The size of the transaction without witness data.
+    fn stripped_size(transaction: &Transaction) -> u64 {
+        let mut stripped = transaction.clone();
+        stripped.input.iter_mut().for_each(|input| input.witness.clear());
+        serialize(&stripped).len() as u64
+    }
+
+    #[test]
+    fn test_mainnet_weights() {
+        // @dev Weights and vsizes as reported by Bitcoin Core
+        let raw_tx = hex::decode(MAINNET_P2WPKH_TX).unwrap();
+        let transaction: Transaction = deserialize(&raw_tx).unwrap();
+        assert_eq!(transaction.txid().to_string(), "f5864806e3565c34d1b41e716f72609d00b55ea5eac5b924c9719a842ef42206");
+        assert_eq!(raw_tx.len(), 193);
+        assert_eq!(weight(stripped_size(&transaction), raw_tx.len() as u64), 442);
+        assert_eq!(weight_to_vsize(442), 111);
+
+        // @dev The 72-byte signature is one byte below the maximum
+        let outputs = [BitcoinAddress::P2sh([4; 20])];
+        assert_eq!(estimate_weight(&[InputType::P2wpkh], &outputs), 443);
+        assert_eq!(estimate_vsize(&[InputType::P2wpkh], &outputs), 111);
+
+        // @dev Without a witness, the weight is four times the size
+        let raw_tx = hex::decode(MAINNET_P2PKH_TX).unwrap();
+        let transaction: Transaction = deserialize(&raw_tx).unwrap();
+        assert_eq!(transaction.txid().to_string(), "a6eab3c14ab5272a58a5ba91505ba1a4b6d7a3a9fcbd187b6cd99a7b6d548cb7");
+        assert_eq!(stripped_size(&transaction), raw_tx.len() as u64);
+        assert_eq!(weight(stripped_size(&transaction), raw_tx.len() as u64), 772);
+
+        let outputs = [BitcoinAddress::P2pkh([5; 20])];
+        assert_eq!(estimate_weight(&[InputType::P2pkh], &outputs), 772);
+        assert_eq!(estimate_vsize(&[InputType::P2pkh], &outputs), 193);
+    }
+
+    #[test]
+    fn test_compute_fee() {
+        // @dev At 10 sat/vB, the P2WPKH transaction pays for 111 vbytes rather than its 193 bytes
+        assert_eq!(compute_fee(111, 10_000), 1_110);
+
+        // @dev Fractions of a satoshi are rounded up, to not fall below the rate
+        assert_eq!(compute_fee(111, 2_500), 278);
+        assert_eq!(compute_fee(1, 1), 1);
+        assert_eq!(compute_fee(111, 0), 0);
+
+        assert_eq!(weight_to_vsize(440), 110);
+        assert_eq!(weight_to_vsize(441), 111);
+    }
+}
@@ -3,8 +3,9 @@ use serde::Serialize;
 use ic_cdk::api::management_canister::http_request::HttpHeader;
 use ic_stable_structures::{BoundedStorable, Storable};
 use std::borrow::Cow;
-use crate::{constants::STORABLE_SERVICE_MAX_SIZE, AUTH_SET_STORABLE_MAX_SIZE, PROVIDER_MAX_SIZE};
+use crate::{constants::STORABLE_SERVICE_MAX_SIZE, AUTH_SET_STORABLE_MAX_SIZE, CACHED_INSCRIPTION_MAX_SIZE, CONSUMED_INSCRIPTION_MAX_SIZE, INSCRIPTION_CACHE_KEY_MAX_SIZE, INSCRIPTION_CACHE_USE_MAX_SIZE, INSCRIPTION_ID_MAX_SIZE, JOURNAL_ENTRY_MAX_SIZE, OUTPOINT_KEY_MAX_SIZE, PROVIDER_CREDENTIALS_MAX_SIZE, PROVIDER_HEALTH_MAX_SIZE, PROVIDER_MAX_SIZE, SSI_MAX_SIZE, UTXO_ASSETS_MAX_SIZE};
 use ic_cdk::api::call::RejectionCode;
+use ic_ckbtc_minter_tyron::updates::update_balance::UpdateBalanceError;
 use thiserror::Error;
 
 #[derive(CandidType, Deserialize)]
@@ -142,6 +143,13 @@ impl BoundedStorable for AuthSet {
 pub struct Metadata {
     pub next_provider_id: u64,
     pub open_rpc_access: bool,
+    pub inscription_quorum: Option<QuorumConfig>,
+    // Redemptions and liquidations above this amount require a BIP-322 ownership proof.
+    pub signature_threshold: Option<u64>,
+    // Provider hostnames that can be registered; `None` allows the hostnames of the default providers.
+    pub allowed_hostnames: Option<Vec<String>>,
+    // Indexer responses can grow up to this size; `None` allows the IC limit.
+    pub max_response_bytes_cap: Option<u64>,
 }
 
 impl Default for Metadata {
@@ -149,6 +157,10 @@ impl Default for Metadata {
         Self {
             next_provider_id: 0,
             open_rpc_access: true,
+            inscription_quorum: None,
+            signature_threshold: None,
+            allowed_hostnames: None,
+            max_response_bytes_cap: None,
         }
     }
 }
@@ -168,6 +180,13 @@ pub struct ProviderApi {
     pub headers: Option<Vec<HttpHeader>>,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, CandidType, Serialize, Deserialize)]
+pub enum IndexerKind {
+    Tyron,
+    UniSat,
+    BestInSlot,
+}
+
 #[derive(Clone, CandidType, Deserialize)]
 pub struct Provider {
     #[serde(rename = "providerId")]
@@ -175,9 +194,12 @@ pub struct Provider {
     pub owner: Principal,
     #[serde(rename = "chainId")]
     pub chain_id: u64,
+    // @dev None for the providers stored before the kind was recorded, see `indexer::provider_kind`
+    pub kind: Option<IndexerKind>,
     pub hostname: String,
     #[serde(rename = "credentialPath")]
     pub credential_path: String,
+    // @dev Masked: the header values are kept in the credential store
     #[serde(rename = "credentialHeaders")]
     pub credential_headers: Vec<HttpHeader>,
     #[serde(rename = "cyclesPerCall")]
@@ -189,19 +211,100 @@ pub struct Provider {
     pub primary: bool,
 }
 
-impl Provider {
-    pub fn api(&self) -> ProviderApi {
-        ProviderApi {
-            url: format!("https://{}{}", self.hostname, self.credential_path),
-            headers: if self.credential_headers.is_empty() {
-                None
-            } else {
-                Some(self.credential_headers.clone())
-            },
+/// The public view of a provider, without owner or credentials.
+#[derive(Clone, Debug, PartialEq, Eq, CandidType, Deserialize)]
+pub struct ProviderView {
+    #[serde(rename = "providerId")]
+    pub provider_id: u64,
+    #[serde(rename = "chainId")]
+    pub chain_id: u64,
+    pub hostname: String,
+    #[serde(rename = "cyclesPerCall")]
+    pub cycles_per_call: u64,
+    #[serde(rename = "cyclesPerMessageByte")]
+    pub cycles_per_message_byte: u64,
+    pub primary: bool,
+}
+
+impl From<&Provider> for ProviderView {
+    fn from(provider: &Provider) -> Self {
+        ProviderView {
+            provider_id: provider.provider_id,
+            chain_id: provider.chain_id,
+            hostname: provider.hostname.clone(),
+            cycles_per_call: provider.cycles_per_call,
+            cycles_per_message_byte: provider.cycles_per_message_byte,
+            primary: provider.primary,
         }
     }
 }
 
+/// The credential headers of a provider, with their values.
+#[derive(Clone, Default, CandidType, Deserialize)]
+pub struct ProviderCredentials {
+    pub headers: Vec<HttpHeader>,
+}
+
+impl Storable for ProviderCredentials {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(&bytes, Self).unwrap()
+    }
+}
+
+impl BoundedStorable for ProviderCredentials {
+    const MAX_SIZE: u32 = PROVIDER_CREDENTIALS_MAX_SIZE;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, CandidType, Deserialize)]
+pub enum IndexerEndpoint {
+    Inscription,
+    Balance,
+    Utxos,
+    Holder,
+}
+
+/// Response size limit that last succeeded for a provider's endpoint type, and the cost of escalating it.
+#[derive(Clone, Debug, Default, PartialEq, Eq, CandidType, Deserialize)]
+pub struct ResponseSizeStats {
+    pub max_response_bytes: u64,
+    // Successful calls with the current limit, until it decays.
+    pub successes: u64,
+    pub escalations: u64,
+    // Cycles spent on top of a single call with the initial limit.
+    pub extra_cycles: u128,
+}
+
+/// Outcome of the calls to a provider, used to skip it while it is failing.
+#[derive(Clone, Debug, Default, PartialEq, Eq, CandidType, Deserialize)]
+pub struct ProviderHealth {
+    pub successes: u64,
+    pub failures: u64,
+    pub consecutive_failures: u32,
+    pub last_error: Option<String>,
+    // Time between the request and the response of the last successful call.
+    pub last_latency_ns: Option<u64>,
+    pub last_success_at: Option<u64>,
+    pub last_failure_at: Option<u64>,
+}
+
+impl Storable for ProviderHealth {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(&bytes, Self).unwrap()
+    }
+}
+
+impl BoundedStorable for ProviderHealth {
+    const MAX_SIZE: u32 = PROVIDER_HEALTH_MAX_SIZE;
+    const IS_FIXED_SIZE: bool = false;
+}
+
 impl Storable for Provider {
     fn to_bytes(&self) -> Cow<[u8]> {
         Cow::Owned(Encode!(self).unwrap())
@@ -220,6 +323,7 @@ impl BoundedStorable for Provider {
 pub struct RegisterProviderArgs {
     #[serde(rename = "chainId")]
     pub chain_id: u64,
+    pub kind: IndexerKind,
     pub hostname: String,
     #[serde(rename = "credentialPath")]
     pub credential_path: String,
@@ -231,6 +335,33 @@ pub struct RegisterProviderArgs {
     pub cycles_per_message_byte: u64,
 }
 
+/// Provider details that the owner can change; `None` keeps the current value.
+#[derive(Clone, CandidType, Deserialize)]
+pub struct UpdateProviderArgs {
+    #[serde(rename = "providerId")]
+    pub provider_id: u64,
+    pub hostname: Option<String>,
+    #[serde(rename = "credentialPath")]
+    pub credential_path: Option<String>,
+    #[serde(rename = "credentialHeaders")]
+    pub credential_headers: Option<Vec<HttpHeader>>,
+    #[serde(rename = "cyclesPerCall")]
+    pub cycles_per_call: Option<u64>,
+    #[serde(rename = "cyclesPerMessageByte")]
+    pub cycles_per_message_byte: Option<u64>,
+}
+
+/// Administrative provider details; `service` routes that service to the provider.
+#[derive(Clone, CandidType, Deserialize)]
+pub struct ManageProviderArgs {
+    #[serde(rename = "providerId")]
+    pub provider_id: u64,
+    #[serde(rename = "chainId")]
+    pub chain_id: Option<u64>,
+    pub primary: Option<bool>,
+    pub service: Option<ServiceProvider>,
+}
+
 #[derive(Clone, PartialEq, Eq, Ord, PartialOrd, Hash, Serialize, Deserialize, CandidType)]
 pub enum ServiceProvider {
     Chain(u64),
@@ -290,14 +421,6 @@ pub enum ResolvedServiceProvider {
     Provider(Provider),
 }
 
-impl ResolvedServiceProvider {
-    pub fn api(&self) -> ProviderApi {
-        match self {
-            Self::Provider(provider) => provider.api(),
-        }
-    }
-}
-
 // @dev Provider errors
 
 #[derive(Clone, Debug, PartialEq, Eq, CandidType, Deserialize)]
@@ -310,6 +433,18 @@ pub enum ProviderError {
     ProviderNotFound,
     // #[error("missing required provider")]
     MissingRequiredProvider,
+    // #[error("invalid provider")]
+    ValidationError(ValidationError),
+    // #[error("cycles withdrawal failed: {reason}")]
+    WithdrawalFailed { reason: String },
+    // #[error("the service is on chain {service_chain_id}, the provider on chain {provider_chain_id}")]
+    ChainMismatch { service_chain_id: u64, provider_chain_id: u64 },
+}
+
+impl From<ValidationError> for ProviderError {
+    fn from(err: ValidationError) -> Self {
+        ProviderError::ValidationError(err)
+    }
 }
 
 #[derive(Clone, Hash, Debug, PartialEq, Eq, PartialOrd, Ord, CandidType, Deserialize)]
@@ -407,3 +542,540 @@ impl HttpOutcallError {
 }
 
 pub type ServiceResult<T> = Result<T, ServiceError>;
+
+// @dev Indexer responses
+
+/// A BRC-20 inscription as normalized from any indexer response.
+#[derive(Clone, Debug, PartialEq, Eq, CandidType, Deserialize)]
+pub struct InscriptionInfo {
+    pub owner_address: String,
+    pub ticker: String,
+    pub op: String,
+    pub amt: String,
+    pub satpoint: Option<String>,
+}
+
+/// A BRC-20 transfer inscription as parsed from the witness of its reveal transaction.
+#[derive(Clone, Debug, PartialEq, Eq, CandidType, Deserialize)]
+pub struct Brc20Transfer {
+    pub inscription_id: String,
+    pub content_type: String,
+    pub tick: String,
+    pub amt: String,
+    // The amount in 8-decimal integer units.
+    pub amount: u64,
+}
+
+/// A BRC-20 balance as normalized from any indexer response.
+#[derive(Clone, Debug, PartialEq, Eq, CandidType, Deserialize)]
+pub struct Brc20Balance {
+    pub ticker: String,
+    pub overall_balance: String,
+    pub available_balance: String,
+    pub transferable_balance: String,
+}
+
+// @dev Inscription quorum
+
+/// Requires `min_agreement` of the given `providers` to report the same inscription.
+#[derive(Clone, Debug, PartialEq, Eq, CandidType, Deserialize)]
+pub struct QuorumConfig {
+    pub providers: Vec<u64>,
+    pub min_agreement: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, CandidType, Deserialize)]
+pub struct ProviderObservation {
+    pub provider_id: u64,
+    pub inscription: Option<InscriptionInfo>,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, CandidType, Deserialize)]
+pub struct QuorumReport {
+    pub required: u32,
+    pub agreeing: u32,
+    pub observations: Vec<ProviderObservation>,
+}
+
+// @dev Consumed transfer inscriptions
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, CandidType, Deserialize)]
+pub enum InscriptionOperation {
+    Mint,
+    Redemption,
+    Liquidation,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, CandidType, Deserialize)]
+pub struct ConsumedInscription {
+    pub operation: InscriptionOperation,
+    pub ssi: String,
+    pub amount: u64,
+    pub timestamp: u64,
+}
+
+impl Storable for ConsumedInscription {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(&bytes, Self).unwrap()
+    }
+}
+
+impl BoundedStorable for ConsumedInscription {
+    const MAX_SIZE: u32 = CONSUMED_INSCRIPTION_MAX_SIZE;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub struct StorableInscriptionId(Vec<u8>);
+
+impl StorableInscriptionId {
+    pub fn new(inscription_id: &str) -> Self {
+        Self(inscription_id.as_bytes().to_vec())
+    }
+}
+
+impl std::fmt::Display for StorableInscriptionId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", String::from_utf8_lossy(&self.0))
+    }
+}
+
+impl Storable for StorableInscriptionId {
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        StorableInscriptionId(bytes.to_vec())
+    }
+
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(self.0.to_owned())
+    }
+}
+
+impl BoundedStorable for StorableInscriptionId {
+    const MAX_SIZE: u32 = INSCRIPTION_ID_MAX_SIZE;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+// @dev Inscription cache
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, CandidType, Deserialize)]
+pub struct InscriptionCacheKey {
+    pub inscription_id: String,
+    pub chain_id: u64,
+}
+
+impl InscriptionCacheKey {
+    pub fn new(chain_id: u64, inscription_id: &str) -> Self {
+        Self {
+            inscription_id: inscription_id.to_string(),
+            chain_id,
+        }
+    }
+}
+
+impl Storable for InscriptionCacheKey {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(&bytes, Self).unwrap()
+    }
+}
+
+impl BoundedStorable for InscriptionCacheKey {
+    const MAX_SIZE: u32 = INSCRIPTION_CACHE_KEY_MAX_SIZE;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+/// An indexer result that cannot change while the inscription stays at its confirmed UTXO.
+#[derive(Clone, Debug, PartialEq, Eq, CandidType, Deserialize)]
+pub struct CachedInscription {
+    pub inscription: InscriptionInfo,
+    // Block height of the inscription UTXO.
+    pub confirmed_height: u32,
+    // Time at which the UTXO was seen with enough confirmations.
+    pub confirmed_at: u64,
+    pub last_used_at: u64,
+}
+
+impl Storable for CachedInscription {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(&bytes, Self).unwrap()
+    }
+}
+
+impl BoundedStorable for CachedInscription {
+    const MAX_SIZE: u32 = CACHED_INSCRIPTION_MAX_SIZE;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+/// An entry of the LRU index of the inscription cache, which orders the cached inscriptions by last use.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, CandidType, Deserialize)]
+pub struct InscriptionCacheUse {
+    pub last_used_at: u64,
+    pub key: InscriptionCacheKey,
+}
+
+impl Storable for InscriptionCacheUse {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(&bytes, Self).unwrap()
+    }
+}
+
+impl BoundedStorable for InscriptionCacheUse {
+    const MAX_SIZE: u32 = INSCRIPTION_CACHE_USE_MAX_SIZE;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+// @dev UTXO classification
+
+/// The inscriptions and runes carried by a UTXO.
+#[derive(Clone, Debug, Default, PartialEq, Eq, CandidType, Deserialize)]
+pub struct UtxoAssets {
+    pub inscription_ids: Vec<String>,
+    pub rune_ids: Vec<String>,
+}
+
+impl UtxoAssets {
+    pub fn is_empty(&self) -> bool {
+        self.inscription_ids.is_empty() && self.rune_ids.is_empty()
+    }
+}
+
+impl Storable for UtxoAssets {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(&bytes, Self).unwrap()
+    }
+}
+
+impl BoundedStorable for UtxoAssets {
+    const MAX_SIZE: u32 = UTXO_ASSETS_MAX_SIZE;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+/// The outpoint of a UTXO, with the txid in hex as shown by block explorers.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, CandidType, Deserialize)]
+pub struct OutpointKey {
+    pub txid: String,
+    pub vout: u32,
+}
+
+impl OutpointKey {
+    pub fn new(txid: &str, vout: u32) -> Self {
+        Self {
+            txid: txid.to_ascii_lowercase(),
+            vout,
+        }
+    }
+}
+
+impl Storable for OutpointKey {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(&bytes, Self).unwrap()
+    }
+}
+
+impl BoundedStorable for OutpointKey {
+    const MAX_SIZE: u32 = OUTPOINT_KEY_MAX_SIZE;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+/// A UTXO of an address that an indexer reports with inscriptions or runes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IndexedUtxo {
+    pub outpoint: OutpointKey,
+    pub assets: UtxoAssets,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, CandidType, Deserialize)]
+pub enum UtxoClass {
+    // Only satoshis: the UTXO can pay fees and change.
+    Cardinal,
+    Inscribed { inscription_ids: Vec<String> },
+    RuneBearing { rune_ids: Vec<String> },
+}
+
+// @dev Operation journal
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, CandidType, Deserialize)]
+pub enum JournalStep {
+    Started,
+    InscriptionVerified,
+    // The liquidator's payment was broadcast, but not yet the collateral transfer.
+    PaymentBroadcast,
+    TransactionBroadcast,
+    LedgerUpdated,
+    // The operation stopped before broadcasting and its inscription was released.
+    Compensated,
+    // The reconciler gave up; the entry needs manual book-keeping.
+    Failed,
+}
+
+impl JournalStep {
+    pub fn is_final(&self) -> bool {
+        matches!(self, JournalStep::LedgerUpdated | JournalStep::Compensated | JournalStep::Failed)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, CandidType, Deserialize)]
+pub struct JournalEntry {
+    pub id: u64,
+    pub operation: InscriptionOperation,
+    pub ssi: String,
+    // The reveal transaction of the transfer inscription.
+    pub txid: String,
+    pub step: JournalStep,
+    // The transaction broadcast by the canister, once sent.
+    pub btc_txid: Option<String>,
+    // The amount to debit from the ledger once the transaction is broadcast.
+    pub ledger_amount: u64,
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    pub created_at: u64,
+    pub updated_at: u64,
+    // The collateral transfer of a liquidation, once the liquidator's payment is broadcast.
+    pub collateral: Option<LiquidationCollateral>,
+}
+
+/// What the reconciler needs to transfer the debtor's collateral after the liquidator paid.
+#[derive(Clone, Debug, PartialEq, Eq, CandidType, Deserialize)]
+pub struct LiquidationCollateral {
+    pub payment_txid: String,
+    pub liquidator: String,
+    // The debtor's safety deposit box.
+    pub sdb: String,
+    pub amount: u64,
+}
+
+impl Storable for JournalEntry {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(Encode!(self).unwrap())
+    }
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        Decode!(&bytes, Self).unwrap()
+    }
+}
+
+impl BoundedStorable for JournalEntry {
+    const MAX_SIZE: u32 = JOURNAL_ENTRY_MAX_SIZE;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+// @dev Ownership proofs
+
+/// A BIP-322 signature by the SSI over the canonical operation message.
+#[derive(Clone, Debug, PartialEq, Eq, CandidType, Deserialize)]
+pub struct OwnershipProof {
+    pub signature: String,
+    pub nonce: u64,
+    // Nanoseconds since the epoch.
+    pub expiry: u64,
+    // The maximum amount (in SUSD units) that the signer authorizes.
+    pub max_amount: u64,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub struct StorableSsi(Vec<u8>);
+
+impl StorableSsi {
+    pub fn new(ssi: &str) -> Self {
+        Self(ssi.as_bytes().to_vec())
+    }
+}
+
+impl Storable for StorableSsi {
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        StorableSsi(bytes.to_vec())
+    }
+
+    fn to_bytes(&self) -> Cow<[u8]> {
+        Cow::Owned(self.0.to_owned())
+    }
+}
+
+impl BoundedStorable for StorableSsi {
+    const MAX_SIZE: u32 = SSI_MAX_SIZE;
+    const IS_FIXED_SIZE: bool = false;
+}
+
+// @dev BRC-20 amount errors
+
+#[derive(Clone, Debug, PartialEq, Eq, CandidType, Deserialize, Error)]
+pub enum AmountError {
+    #[error("empty amount")]
+    Empty,
+    #[error("negative amount")]
+    Negative,
+    #[error("exponent notation is not allowed")]
+    Exponent,
+    #[error("invalid character '{0}'")]
+    InvalidCharacter(String),
+    #[error("malformed decimal amount")]
+    Malformed,
+    #[error("too many decimals (max {max}, found {found})")]
+    TooManyDecimals { max: u32, found: u32 },
+    #[error("amount overflows 64 bits")]
+    Overflow,
+}
+
+// @dev Inscription envelope errors
+
+#[derive(Clone, Debug, PartialEq, Eq, CandidType, Deserialize, Error)]
+pub enum EnvelopeError {
+    #[error("invalid transaction: {0}")]
+    InvalidTransaction(String),
+    #[error("the transaction hashes to {found}, not {expected}")]
+    TxidMismatch { expected: String, found: String },
+    #[error("no ordinals envelope found in the taproot witness")]
+    EnvelopeNotFound,
+    #[error("malformed envelope: {0}")]
+    MalformedEnvelope(String),
+    #[error("unsupported content type '{0}'")]
+    UnsupportedContentType(String),
+    #[error("invalid BRC-20 JSON: {0}")]
+    InvalidJson(String),
+    #[error("not a BRC-20 transfer: {0}")]
+    NotBrc20Transfer(String),
+    #[error("ticker mismatch (expected {expected}, found {found})")]
+    TickerMismatch { expected: String, found: String },
+    #[error("invalid BRC-20 amount: {0}")]
+    InvalidAmount(AmountError),
+}
+
+// @dev SPV
+
+/// A Merkle branch from a transaction to the merkle root of its block, with the hashes in display
+/// (byte-reversed) hex like the `blockchain.transaction.get_merkle` response of Electrum servers.
+#[derive(Clone, Debug, PartialEq, Eq, CandidType, Deserialize)]
+pub struct MerkleProof {
+    pub txid: String,
+    pub block_height: u32,
+    // Position of the transaction in the block.
+    pub index: u32,
+    pub branch: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, CandidType, Deserialize, Error)]
+pub enum SpvError {
+    #[error("invalid hash '{0}'")]
+    InvalidHash(String),
+    #[error("index {index} does not fit a Merkle branch of depth {depth}")]
+    InvalidMerkleIndex { index: u32, depth: u32 },
+    #[error("invalid header at height {height}: {reason}")]
+    InvalidHeader { height: u32, reason: String },
+    #[error("invalid proof of work at height {height}")]
+    InvalidProofOfWork { height: u32 },
+    #[error("the header at height {height} does not extend the previous header")]
+    BrokenChain { height: u32 },
+    #[error("missing headers (expected {expected}, found {found})")]
+    MissingHeaders { expected: u32, found: u32 },
+    #[error("insufficient confirmations (required {required}, found {found})")]
+    InsufficientConfirmations { required: u32, found: u32 },
+    #[error("at most {max} confirmations can be required")]
+    TooManyConfirmations { max: u32 },
+    #[error("the Merkle branch does not lead to the merkle root of block {height}")]
+    MerkleRootMismatch { height: u32 },
+}
+
+// @dev Syron errors
+
+#[derive(Clone, Debug, PartialEq, Eq, CandidType, Deserialize, Error)]
+pub enum SyronError {
+    #[error("invalid operation for this endpoint")]
+    InvalidOperation,
+    #[error("insufficient balance (available {available}, requested {requested})")]
+    InsufficientBalance { available: u64, requested: u64 },
+    #[error("amount {requested} is below the minimum of {minimum}")]
+    AmountBelowMinimum { minimum: u64, requested: u64 },
+    #[error("the loan balance is zero")]
+    ZeroLoan,
+    #[error("the ECDSA key name is empty")]
+    KeyNameEmpty,
+    #[error("ledger unavailable: {reason}")]
+    LedgerUnavailable { reason: String },
+    #[error("inscription receiver mismatch (expected {expected}, found {found})")]
+    InscriptionMismatch { expected: String, found: String },
+    #[error("inscribed amount {inscribed} is out of range [{min}, {max}]")]
+    InscribedAmountOutOfRange { inscribed: u64, min: u64, max: u64 },
+    #[error("invalid BRC-20 amount '{amt}': {error}")]
+    InvalidAmount { amt: String, error: AmountError },
+    #[error("indexer {provider} unavailable: {reason}")]
+    IndexerUnavailable { provider: u64, reason: String },
+    #[error("invalid response from indexer {provider}: {reason}")]
+    InvalidIndexerResponse { provider: u64, reason: String },
+    #[error("caller {caller} is not linked to {ssi}")]
+    Unauthorized { caller: Principal, ssi: String },
+    #[error("invalid ownership proof: {reason}")]
+    InvalidSignature { reason: String },
+    #[error("the ownership proof expired at {expiry}")]
+    SignatureExpired { expiry: u64 },
+    #[error("nonce {nonce} was already used, the next nonce must be greater than {last}")]
+    NonceAlreadyUsed { nonce: u64, last: u64 },
+    #[error("the amount {amount} is above {threshold} and requires an ownership proof")]
+    SignatureRequired { amount: u64, threshold: u64 },
+    #[error("another operation for {ssi} is already in progress")]
+    AlreadyProcessing { ssi: String },
+    #[error("inscription {inscription_id} was already consumed by a {operation:?} operation")]
+    InscriptionAlreadyConsumed { inscription_id: String, operation: InscriptionOperation },
+    #[error("invalid quorum: {reason}")]
+    InvalidQuorum { reason: String },
+    #[error("indexer quorum not reached ({} of {} required)", .0.agreeing, .0.required)]
+    QuorumNotReached(QuorumReport),
+    #[error("collateral ratio {ratio} is above the liquidation threshold {threshold}")]
+    CollateralRatioAboveThreshold { ratio: u64, threshold: u64 },
+    #[error("UTXO not found: {txid}")]
+    UtxoNotFound { txid: String },
+    #[error("insufficient funds (available {available} sats, required {required} sats)")]
+    InsufficientFunds { available: u64, required: u64 },
+    #[error("bitcoin_send_transaction failed: {reason}")]
+    SendTransactionFailed { reason: String },
+    #[error("call to {method} failed: {reason}")]
+    CallFailed { method: String, reason: String },
+    #[error("minter error: {0:?}")]
+    Minter(UpdateBalanceError),
+    #[error("provider error: {0:?}")]
+    Provider(ProviderError),
+    #[error("invalid inscription {txid}: {error}")]
+    InvalidInscription { txid: String, error: EnvelopeError },
+    #[error("the indexer reports {field} '{indexed}', but the witness inscribes '{inscribed}'")]
+    InscriptionContentMismatch { field: String, inscribed: String, indexed: String },
+    #[error("invalid SPV proof: {0}")]
+    InvalidSpvProof(SpvError),
+    #[error("response size cap {cap} is out of range [{min}, {max}]")]
+    InvalidResponseBytesCap { cap: u64, min: u64, max: u64 },
+    #[error("the fee did not converge after {iterations} iterations (last fee {fee})")]
+    FeeNotConverged { iterations: u32, fee: u64 },
+    #[error("UTXO {txid}:{vout} does not carry inscription {inscription_id} ({class:?})")]
+    UtxoNotInscribed { txid: String, vout: u32, inscription_id: String, class: UtxoClass },
+    #[error("the inscription {field} must be '{expected}', found '{found}'")]
+    UnexpectedInscription { field: String, expected: String, found: String },
+}
+
+impl From<ProviderError> for SyronError {
+    fn from(err: ProviderError) -> Self {
+        SyronError::Provider(err)
+    }
+}
+
+impl From<UpdateBalanceError> for SyronError {
+    fn from(err: UpdateBalanceError) -> Self {
+        SyronError::Minter(err)
+    }
+}
+
+pub type SyronResult<T> = Result<T, SyronError>;
@@ -0,0 +1,106 @@
+// @notice Validation of service providers
+// Providers are called with the canister's cycles and their responses move funds, so only
+// allowlisted hostnames are accepted, credential paths cannot escape the host and only
+// credential headers known to the indexers can be set.
+
+use ic_cdk::api::management_canister::http_request::HttpHeader;
+
+use crate::{
+    provider::{get_default_service_provider_hostnames, METADATA},
+    types::ValidationError,
+    CREDENTIAL_HEADER_ALLOWLIST,
+};
+
+/// The configured hostname allowlist, or the hostnames of the default providers.
+pub fn get_allowed_hostnames() -> Vec<String> {
+    METADATA
+        .with(|m| m.borrow().get().allowed_hostnames.clone())
+        .unwrap_or_else(|| {
+            let mut hostnames: Vec<String> = get_default_service_provider_hostnames()
+                .into_iter()
+                .map(|(_, hostname)| hostname.to_string())
+                .collect();
+            hostnames.sort();
+            hostnames.dedup();
+            hostnames
+        })
+}
+
+pub fn set_allowed_hostnames(hostnames: Option<Vec<String>>) -> Result<(), ValidationError> {
+    if let Some(hostnames) = &hostnames {
+        for hostname in hostnames {
+            validate_hostname_format(hostname)?;
+        }
+    }
+    METADATA.with(|m| {
+        let mut metadata = m.borrow().get().clone();
+        metadata.allowed_hostnames = hostnames;
+        m.borrow_mut().set(metadata).unwrap();
+    });
+    Ok(())
+}
+
+/// A bare hostname: no scheme, port, path or credentials.
+pub fn validate_hostname_format(hostname: &str) -> Result<(), ValidationError> {
+    let is_valid_label = |label: &str| {
+        !label.is_empty()
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    };
+    if hostname.len() > 253 || !hostname.split('.').all(is_valid_label) {
+        return Err(ValidationError::UrlParseError(format!("invalid hostname: {}", hostname)));
+    }
+    Ok(())
+}
+
+pub fn validate_hostname(hostname: &str, allowed_hostnames: &[String]) -> Result<(), ValidationError> {
+    validate_hostname_format(hostname)?;
+    if !allowed_hostnames.iter().any(|allowed| allowed.eq_ignore_ascii_case(hostname)) {
+        return Err(ValidationError::HostNotAllowed(hostname.to_string()));
+    }
+    Ok(())
+}
+
+/// The path (and query) appended to the hostname: it cannot change the host, the port or leave the API root.
+pub fn validate_credential_path(credential_path: &str) -> Result<(), ValidationError> {
+    let (path, _) = credential_path.split_once('?').unwrap_or((credential_path, ""));
+    let lowercase = path.to_ascii_lowercase();
+    let is_allowed = !credential_path.contains("://")
+        && !credential_path.starts_with("//")
+        && !path.contains(':')
+        && !path.split('/').any(|segment| segment == "..")
+        && !lowercase.contains("%2e")
+        && !lowercase.contains("%2f")
+        && !credential_path
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control() || matches!(c, '\\' | '@' | '#'));
+    if is_allowed {
+        Ok(())
+    } else {
+        Err(ValidationError::CredentialPathNotAllowed)
+    }
+}
+
+pub fn validate_credential_headers(headers: &[HttpHeader]) -> Result<(), ValidationError> {
+    for header in headers {
+        let is_allowed = CREDENTIAL_HEADER_ALLOWLIST
+            .iter()
+            .any(|name| name.eq_ignore_ascii_case(&header.name));
+        // @dev Line breaks in the value would inject headers
+        if !is_allowed || header.value.chars().any(|c| c == '\r' || c == '\n') {
+            return Err(ValidationError::CredentialHeaderNotAllowed);
+        }
+    }
+    Ok(())
+}
+
+pub fn validate_provider(
+    hostname: &str,
+    credential_path: &str,
+    credential_headers: &[HttpHeader]
+) -> Result<(), ValidationError> {
+    validate_hostname(hostname, &get_allowed_hostnames())?;
+    validate_credential_path(credential_path)?;
+    validate_credential_headers(credential_headers)
+}
@@ -0,0 +1,157 @@
+// @notice Virtual size of transactions before they are signed
+// The fee of a transaction depends on its size, which is only known once its inputs are signed. Instead of signing
+// on every iteration of the fee loop, the size is estimated from the script types of the inputs and outputs,
+// with signatures of the maximum length so that the fee is never short.
+// Weights follow BIP-141: non-witness bytes count four times, witness bytes once.
+
+use ic_ckbtc_minter_tyron::address::BitcoinAddress;
+
+// Version and lock time.
+const TX_FIXED_SIZE: u64 = 8;
+
+// The segwit marker and flag bytes, only present if some input has a witness.
+const SEGWIT_HEADER_SIZE: u64 = 2;
+
+// Outpoint (txid and vout) and sequence.
+const INPUT_FIXED_SIZE: u64 = 40;
+
+// The output value.
+const OUTPUT_FIXED_SIZE: u64 = 8;
+
+// A DER-encoded ECDSA signature is at most 72 bytes, plus the sighash type.
+const MAX_ECDSA_SIGNATURE_SIZE: u64 = 73;
+
+// A Schnorr signature with the default sighash type.
+const SCHNORR_SIGNATURE_SIZE: u64 = 64;
+
+const COMPRESSED_PUBLIC_KEY_SIZE: u64 = 33;
+
+// `OP_0 <20-byte key hash>`, the redeem script of a nested P2WPKH input.
+const P2WPKH_REDEEM_SCRIPT_SIZE: u64 = 22;
+
+pub const WITNESS_SCALE_FACTOR: u64 = 4;
+
+/// How an input is spent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InputType {
+    /// A signature and a compressed public key in the witness.
+    P2wpkh,
+    /// A key-path spend, with a single Schnorr signature in the witness.
+    P2tr,
+    /// A P2WPKH program nested in P2SH: the redeem script in the script sig and the P2WPKH witness.
+    P2shP2wpkh,
+    /// A signature and a compressed public key in the script sig.
+    P2pkh,
+}
+
+impl InputType {
+    pub fn is_segwit(&self) -> bool {
+        !matches!(self, InputType::P2pkh)
+    }
+
+    fn script_sig_size(&self) -> u64 {
+        match self {
+            InputType::P2wpkh | InputType::P2tr => 0,
+            InputType::P2shP2wpkh => push_size(P2WPKH_REDEEM_SCRIPT_SIZE),
+            InputType::P2pkh => push_size(MAX_ECDSA_SIGNATURE_SIZE) + push_size(COMPRESSED_PUBLIC_KEY_SIZE),
+        }
+    }
+
+    /// The serialized witness, including the number of its elements.
+    fn witness_size(&self) -> u64 {
+        match self {
+            InputType::P2wpkh | InputType::P2shP2wpkh => {
+                compact_size(2) + push_size(MAX_ECDSA_SIGNATURE_SIZE) + push_size(COMPRESSED_PUBLIC_KEY_SIZE)
+            }
+            InputType::P2tr => compact_size(1) + push_size(SCHNORR_SIGNATURE_SIZE),
+            InputType::P2pkh => 0,
+        }
+    }
+
+    /// The serialized input, without its witness.
+    fn stripped_size(&self) -> u64 {
+        let script_sig_size = self.script_sig_size();
+        INPUT_FIXED_SIZE + compact_size(script_sig_size) + script_sig_size
+    }
+
+    /// The weight of the input in a segwit transaction, without the empty witness of non-segwit inputs.
+    pub fn weight(&self) -> u64 {
+        weight(self.stripped_size(), self.stripped_size() + self.witness_size())
+    }
+}
+
+/// The length of the length prefix of a vector.
+pub fn compact_size(len: u64) -> u64 {
+    match len {
+        0..=0xfc => 1,
+        0xfd..=0xffff => 3,
+        0x10000..=0xffff_ffff => 5,
+        _ => 9,
+    }
+}
+
+/// The size of a data push in a script, or of a witness element.
+fn push_size(len: u64) -> u64 {
+    compact_size(len) + len
+}
+
+pub fn script_pubkey_size(address: &BitcoinAddress) -> u64 {
+    match address {
+        // OP_0 <20 bytes>
+        BitcoinAddress::P2wpkhV0(_) => 22,
+        // OP_0 <32 bytes>
+        BitcoinAddress::P2wshV0(_) => 34,
+        // OP_1 <32 bytes>
+        BitcoinAddress::P2trV1(_) => 34,
+        // OP_HASH160 <20 bytes> OP_EQUAL
+        BitcoinAddress::P2sh(_) => 23,
+        // OP_DUP OP_HASH160 <20 bytes> OP_EQUALVERIFY OP_CHECKSIG
+        BitcoinAddress::P2pkh(_) => 25,
+    }
+}
+
+fn output_size(address: &BitcoinAddress) -> u64 {
+    let script_size = script_pubkey_size(address);
+    OUTPUT_FIXED_SIZE + compact_size(script_size) + script_size
+}
+
+pub fn output_weight(address: &BitcoinAddress) -> u64 {
+    output_size(address) * WITNESS_SCALE_FACTOR
+}
+
+/// The BIP-141 weight of a transaction: its size without witness data counts four times, the witness data once.
+pub fn weight(stripped_size: u64, total_size: u64) -> u64 {
+    stripped_size * (WITNESS_SCALE_FACTOR - 1) + total_size
+}
+
+/// The virtual size is the weight divided by 4, rounded up.
+pub fn weight_to_vsize(weight: u64) -> u64 {
+    weight.div_ceil(WITNESS_SCALE_FACTOR)
+}
+
+/// The fee in satoshis of a transaction of `vsize` vbytes at `fee_per_vbyte` millisatoshis per vbyte.
+/// The fee percentiles of the Bitcoin API are per vbyte, so the fee is rounded up to not fall below the rate.
+pub fn compute_fee(vsize: u64, fee_per_vbyte: u64) -> u64 {
+    (vsize * fee_per_vbyte).div_ceil(1000)
+}
+
+/// The weight of the transaction once its inputs are signed.
+pub fn estimate_weight(inputs: &[InputType], outputs: &[BitcoinAddress]) -> u64 {
+    let counts_size = compact_size(inputs.len() as u64) + compact_size(outputs.len() as u64);
+    let stripped_size = TX_FIXED_SIZE
+        + counts_size
+        + inputs.iter().map(InputType::stripped_size).sum::<u64>()
+        + outputs.iter().map(output_size).sum::<u64>();
+
+    let mut witness_size = 0;
+    if inputs.iter().any(InputType::is_segwit) {
+        // @dev Inputs without a witness still have an empty stack (a zero count byte) in a segwit transaction
+        witness_size = SEGWIT_HEADER_SIZE + inputs.iter().map(|input| input.witness_size().max(1)).sum::<u64>();
+    }
+    weight(stripped_size, stripped_size + witness_size)
+}
+
+/// The virtual size of the transaction once its inputs are signed.
+pub fn estimate_vsize(inputs: &[InputType], outputs: &[BitcoinAddress]) -> u64 {
+    weight_to_vsize(estimate_weight(inputs, outputs))
+}